@@ -1,7 +1,9 @@
 use crate::context_menus::context_items::{get_ci, get_ci_capacity};
-use crate::globals::config_read;
 use crate::ui::*;
-use crate::{context_menus as cm, ui};
+use crate::{
+    context_menus as cm, custom_actions, drives, places, preview, run_command, search,
+    source_viewer, ui, view_settings,
+};
 use main_window::run_with_main_window;
 use slint::{ComponentHandle, VecModel};
 use std::rc::Rc;
@@ -12,6 +14,7 @@ pub enum ContextCallback {
     OpenWithDefault,
     OpenWith,
     OpenWithQuick,
+    OpenWithApp,
     ManageQuick,
     ShowProperties,
     Cut,
@@ -23,6 +26,26 @@ pub enum ContextCallback {
     CreateNewFile,
     CreateNewDirectory,
     CreateNewLink,
+    AnalyzeDiskUsage,
+    CustomAction,
+    OpenTerminalHere,
+    RunCommand,
+    ToggleHidden,
+    SetViewAsDefault,
+    ResetView,
+    ToggleColumn,
+    ViewMode,
+    SetViewMode,
+    TogglePreview,
+    ViewSource,
+    AddPlace,
+    RenamePlace,
+    RemovePlace,
+    MountDrive,
+    UnmountDrive,
+    PowerOffDrive,
+    Search,
+    RevealInFolder,
 }
 
 ///Triggered when a certain menu item is clicked.
@@ -48,11 +71,44 @@ pub fn menuitem_click(context_item: ContextItem, index: i32) {
         c if c == ContextCallback::OpenWithQuick as i32 => {
             cm::files::open_with_quick(&context_item)
         }
+        c if c == ContextCallback::OpenWithApp as i32 => cm::files::open_with_app(&context_item),
         c if c == ContextCallback::ManageQuick as i32 => cm::files::manage_quick(),
         c if c == ContextCallback::CreateNew as i32 => cm::create_new::create_new_hover(index),
         c if c == ContextCallback::CreateNewFile as i32 => cm::create_new::create_new_file(),
         c if c == ContextCallback::CreateNewDirectory as i32 => cm::create_new::create_new_dir(),
         c if c == ContextCallback::CreateNewLink as i32 => cm::create_new::create_new_link(),
+        c if c == ContextCallback::AnalyzeDiskUsage as i32 => cm::files::analyze_disk_usage(),
+        c if c == ContextCallback::OpenTerminalHere as i32 => cm::files::open_terminal_here(),
+        c if c == ContextCallback::RunCommand as i32 => run_command::show(),
+        c if c == ContextCallback::ToggleHidden as i32 => view_settings::toggle_hidden(),
+        c if c == ContextCallback::SetViewAsDefault as i32 => {
+            view_settings::set_current_as_default()
+        }
+        c if c == ContextCallback::ResetView as i32 => view_settings::reset_current(),
+        c if c == ContextCallback::ToggleColumn as i32 => {
+            super::headers::toggle_column(context_item.internal_id)
+        }
+        c if c == ContextCallback::ViewMode as i32 => super::tabs::view_mode_hover(index),
+        c if c == ContextCallback::SetViewMode as i32 => {
+            super::tabs::set_view_mode(context_item.internal_id)
+        }
+        c if c == ContextCallback::TogglePreview as i32 => preview::toggle(),
+        c if c == ContextCallback::ViewSource as i32 => source_viewer::view_selected(),
+        c if c == ContextCallback::AddPlace as i32 => places::add_current(),
+        c if c == ContextCallback::RenamePlace as i32 => {
+            places::start_rename(context_item.internal_id)
+        }
+        c if c == ContextCallback::RemovePlace as i32 => places::remove(context_item.internal_id),
+        c if c == ContextCallback::MountDrive as i32 => drives::mount(context_item.internal_id),
+        c if c == ContextCallback::UnmountDrive as i32 => drives::unmount(context_item.internal_id),
+        c if c == ContextCallback::PowerOffDrive as i32 => {
+            drives::power_off(context_item.internal_id)
+        }
+        c if c == ContextCallback::Search as i32 => search::toggle(),
+        c if c == ContextCallback::RevealInFolder as i32 => search::reveal_selected(),
+        c if c == ContextCallback::CustomAction as i32 => {
+            custom_actions::run_action_index(context_item.internal_id as usize)
+        }
         _ => (),
     }
     if !context_item.click_on_hover {
@@ -75,24 +131,37 @@ pub fn show_context_menu(x: f32, y: f32) {
     run_with_main_window(move |mw| {
         let mut menu: Vec<ContextItem> = Vec::with_capacity(get_ci_capacity());
 
-        let conf = config_read();
-
-        //TODO: check permissions and don't show what we don't have permissions to do
-        let default_mapping =
-            selection::get_common_extension().and_then(|f| conf.get_mapping_default(&f));
-
         if selection::is_single_selected_directory() || selection::is_nothing_selected() {
             menu.push(get_ci("create_new"));
         }
 
-        //Only offer 'open with' if we have mappings for the file's extension
-        if default_mapping.is_some() {
-            let mut open_with_default = get_ci("open_with_default");
-            open_with_default.display =
-                ("Open With ".to_owned() + &default_mapping.unwrap()).into();
-            menu.push(open_with_default);
+        //TODO: check permissions and don't show what we don't have permissions to do
+        let files = selection::selected_files_clone();
+        if !files.is_empty() && files.iter().all(|f| !f.is_dir) {
+            let defaults: Vec<Option<String>> = cm::files::get_default_mappings(&files)
+                .into_iter()
+                .map(|m| m.map(|m| m.display_name))
+                .collect();
+            //Only offer the default if at least one file has an application
+            if defaults.iter().any(|d| d.is_some()) {
+                let mut open_with_default = get_ci("open_with_default");
+                open_with_default.display = match &defaults[0] {
+                    Some(name) if defaults.iter().all(|d| d.as_ref() == Some(name)) => {
+                        ("Open With ".to_owned() + name).into()
+                    }
+                    _ => "Open".into(),
+                };
+                menu.push(open_with_default);
+            }
             menu.push(get_ci("open_with"));
+            if files.len() == 1 {
+                menu.push(get_ci("view_source"));
+            }
         }
+        if files.len() == 1 && search::is_active() {
+            menu.push(get_ci("reveal_in_folder"));
+        }
+        menu.extend(custom_actions::get_context_items(&files));
 
         let is_nothing_selected = selection::is_nothing_selected();
 
@@ -108,6 +177,27 @@ pub fn show_context_menu(x: f32, y: f32) {
         if !is_nothing_selected {
             menu.push(get_ci("delete"));
         }
+        if selection::get_selected_path().is_some() {
+            menu.push(get_ci("analyze_disk_usage"));
+        }
+        menu.push(get_ci("open_terminal_here"));
+        menu.push(get_ci("run_command"));
+        if is_nothing_selected {
+            menu.push(get_ci("search"));
+            menu.push(get_ci("view_mode"));
+            let mut toggle_hidden = get_ci("toggle_hidden");
+            if view_settings::current(&mw).show_hidden {
+                toggle_hidden.display = "Hide Hidden Files".into();
+            }
+            menu.push(toggle_hidden);
+            let mut toggle_preview = get_ci("toggle_preview");
+            if mw.global::<PreviewAdapter>().get_shown() {
+                toggle_preview.display = "Hide Preview".into();
+            }
+            menu.push(toggle_preview);
+            menu.push(get_ci("set_view_as_default"));
+            menu.push(get_ci("reset_view"));
+        }
         menu.push(get_ci("properties"));
 
         let ctx_adapter = mw.global::<ContextAdapter>();
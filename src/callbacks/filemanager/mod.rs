@@ -1,3 +1,4 @@
+use crate::callbacks::tabs;
 use crate::context_menus::files::open_with_default;
 use crate::globals::config_read;
 use crate::ui;
@@ -24,6 +25,7 @@ pub fn fileitem_doubleclicked(item: FileItem) {
                 text: item.file_name.clone(),
                 text_length: item.file_name.len() as i32,
                 selected: true,
+                view_mode: tabs::VIEW_DETAILS,
             },
             true,
         ));
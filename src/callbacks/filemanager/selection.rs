@@ -1,4 +1,4 @@
-use crate::ui::*;
+use crate::{callbacks::tabs::VIEW_ICONS, preview, status_bar, ui::*};
 use main_window::{get_selected_tab_file, run_with_main_window};
 use slint::Model;
 use std::{
@@ -60,6 +60,12 @@ pub fn get_selected_file() -> Option<FileItem> {
     }
 }
 
+///Updates the preview and the status bar
+fn selection_changed() {
+    preview::selection_changed();
+    status_bar::selection_changed();
+}
+
 ///Returns whether or not that particular index is selected
 pub fn is_index_selected(i: i32) -> bool {
     selected_files_read().contains_key(&i)
@@ -75,6 +81,7 @@ pub fn clear_selection() {
         fm.set_is_single_selected(false);
         clear_selection_visual(&mw);
     });
+    selection_changed();
 }
 
 ///This function resets the visual selection state
@@ -99,6 +106,7 @@ pub fn add_to_selected(i: i32, file: FileItem) {
             fm.set_is_single_selected(true);
         }
         fm.set_single_selected_index(i);
+        selection_changed();
     });
 }
 
@@ -118,6 +126,7 @@ pub fn select_all() {
             fm.set_is_single_selected(true);
             fm.set_single_selected_index(0);
         }
+        selection_changed();
     });
 }
 
@@ -150,97 +159,126 @@ pub fn shift_select(i: i32) {
         }
         fm.set_is_single_selected(false);
         fm.set_single_selected_index(i);
+        selection_changed();
     });
 }
 
-///Usually when pressing arrow down on the keyboard.
-///Selection moves down by one from the last.
-pub fn select_down(discard_previous: bool) {
-    run_with_main_window(move |mw| {
-        let mut sel_files = selected_files_write();
-        let fm = mw.global::<FileManager>();
+fn is_icon_view(mw: &MainWindow) -> bool {
+    mw.global::<TabsAdapter>()
+        .invoke_get_current_tab()
+        .view_mode
+        == VIEW_ICONS
+}
 
-        let current_i = fm.get_single_selected_index();
-        let i = if sel_files.len() == 0 {
+///Number of files in a row: the columns of the icon view, 1 in the lists
+fn row_length(mw: &MainWindow) -> i32 {
+    if is_icon_view(mw) {
+        mw.global::<FileManager>().get_grid_columns().max(1)
+    } else {
+        1
+    }
+}
+
+///Moves the selection by offset files from the last selected one.
+///With discard_previous, only the new file is selected. Otherwise the files in between are
+///added to the selection, or removed when going back over them (like shift_select).
+fn move_selection(mw: &MainWindow, offset: i32, discard_previous: bool) {
+    let mut sel_files = selected_files_write();
+    let fm = mw.global::<FileManager>();
+    let len = fm.get_files_len();
+    if len == 0 {
+        return;
+    }
+
+    let current_i = fm.get_single_selected_index();
+    let i = if sel_files.is_empty() {
+        if offset > 0 {
             0
         } else {
-            if current_i == fm.get_files_len() - 1 {
-                //Last index, do nothing
-                return;
+            len - 1
+        }
+    } else {
+        let target = current_i + offset;
+        if target < 0 {
+            //First row, do nothing
+            return;
+        } else if target >= len {
+            //Going down to a shorter last row ends on its last file
+            if offset > 1 && current_i / offset < (len - 1) / offset {
+                len - 1
             } else {
-                current_i + 1
+                return;
             }
-        };
+        } else {
+            target
+        }
+    };
 
-        if discard_previous {
-            //Single select
-            sel_files.clear();
-            clear_selection_visual(&mw);
-            fm.set_is_single_selected(true);
-            sel_files.insert(i, fm.invoke_get_file(i));
-            set_selected_visual(&mw, i, true);
-            fm.set_single_selected_index(i);
+    if discard_previous || sel_files.is_empty() {
+        //Single select
+        sel_files.clear();
+        clear_selection_visual(mw);
+        fm.set_is_single_selected(true);
+        sel_files.insert(i, fm.invoke_get_file(i));
+        set_selected_visual(mw, i, true);
+        fm.set_single_selected_index(i);
+    } else {
+        //Logic for shift_select
+        let (step, between) = if i > current_i {
+            (1, current_i..i)
         } else {
-            //Logic for shift_select
-            if sel_files.contains_key(&i) {
-                sel_files.remove(&current_i);
-                set_selected_visual(&mw, current_i, false);
-            } else {
-                sel_files.insert(i, fm.invoke_get_file(i));
-                set_selected_visual(&mw, i, true);
+            (-1, (i + 1)..(current_i + 1))
+        };
+        if sel_files.contains_key(&i) {
+            //Going back: the files left behind are unselected
+            for j in between {
+                sel_files.remove(&j);
+                set_selected_visual(mw, j, false);
             }
-            fm.set_single_selected_index(i);
-            if sel_files.len() == 1 {
-                fm.set_is_single_selected(true);
-            } else {
-                fm.set_is_single_selected(false);
+        } else {
+            let mut j = current_i;
+            while j != i {
+                j += step;
+                sel_files.insert(j, fm.invoke_get_file(j));
+                set_selected_visual(mw, j, true);
             }
         }
+        fm.set_single_selected_index(i);
+        fm.set_is_single_selected(sel_files.len() == 1);
+    }
+    selection_changed();
+}
+
+///Usually when pressing arrow down on the keyboard.
+///Selection moves down by one row from the last.
+pub fn select_down(discard_previous: bool) {
+    run_with_main_window(move |mw| {
+        move_selection(mw, row_length(mw), discard_previous);
     });
 }
 
 ///Usually when pressing arrow up on the keyboard.
-///Selection moves up by one from the last.
+///Selection moves up by one row from the last.
 pub fn select_up(discard_previous: bool) {
     run_with_main_window(move |mw| {
-        let mut sel_files = selected_files_write();
-        let fm = mw.global::<FileManager>();
+        move_selection(mw, -row_length(mw), discard_previous);
+    });
+}
 
-        let current_i = fm.get_single_selected_index();
-        let i = if sel_files.len() == 0 {
-            fm.get_files_len() - 1
-        } else {
-            if current_i == 0 {
-                //Last index, do nothing
-                return;
-            } else {
-                current_i - 1
-            }
-        };
+///Usually when pressing arrow left on the keyboard, only in the icon view.
+pub fn select_left(discard_previous: bool) {
+    run_with_main_window(move |mw| {
+        if is_icon_view(mw) {
+            move_selection(mw, -1, discard_previous);
+        }
+    });
+}
 
-        if discard_previous {
-            //Single select
-            sel_files.clear();
-            clear_selection_visual(&mw);
-            fm.set_is_single_selected(true);
-            sel_files.insert(i, fm.invoke_get_file(i));
-            set_selected_visual(&mw, i, true);
-            fm.set_single_selected_index(i);
-        } else {
-            //Logic for shift_select
-            if sel_files.contains_key(&i) {
-                sel_files.remove(&current_i);
-                set_selected_visual(&mw, current_i, false);
-            } else {
-                sel_files.insert(i, fm.invoke_get_file(i));
-                set_selected_visual(&mw, i, true);
-            }
-            fm.set_single_selected_index(i);
-            if sel_files.len() == 1 {
-                fm.set_is_single_selected(true);
-            } else {
-                fm.set_is_single_selected(false);
-            }
+///Usually when pressing arrow right on the keyboard, only in the icon view.
+pub fn select_right(discard_previous: bool) {
+    run_with_main_window(move |mw| {
+        if is_icon_view(mw) {
+            move_selection(mw, 1, discard_previous);
         }
     });
 }
@@ -254,6 +292,7 @@ pub fn remove_from_selected(i: i32) {
         }
         sel_files.remove(&i);
         set_selected_visual(&mw, i, false);
+        selection_changed();
     });
 }
 
@@ -1,7 +1,10 @@
+use std::rc::Rc;
+
 use main_window::run_with_main_window;
-use slint::{Model, ModelRc};
+use slint::{Image, Model, ModelRc, SharedPixelBuffer, VecModel};
 
-use crate::{sort, ui::*};
+use super::context_menu::ContextCallback;
+use crate::{columns, sort, ui::*, view_settings};
 
 pub fn on_header_click(header: Header) {
     let new_sort = if header.sort == 0 {
@@ -16,14 +19,9 @@ pub fn on_header_click(header: Header) {
 
     //Sort
     run_with_main_window(move |mw| {
-        //TODO: Consider not sorting in main thread. Could be bad though, since it could lead to
-        //actions happening during a sort.
-        match header.inner_value {
-            0 => sort::sort_by_name(&mw, new_sort == 1, true),
-            1 => sort::sort_by_size(&mw, new_sort == 1, true),
-            2 => sort::sort_by_date(&mw, new_sort == 1, true),
-            _ => {}
-        };
+        if let Some(name) = columns::column_name(header.inner_value) {
+            sort::set_sort(&mw, name, new_sort == 1);
+        }
 
         //Change sort values
         let headers_rc = mw.global::<ColumnHeadersAdapter>().get_headers();
@@ -42,6 +40,44 @@ pub fn on_header_click(header: Header) {
         }
     });
 }
+///Shows the menu listing the columns, the shown ones have a check mark
+pub fn show_header_context_menu(x: f32, y: f32) {
+    run_with_main_window(move |mw| {
+        let settings = view_settings::current(&mw);
+        let menu: Vec<ContextItem> = columns::COLUMNS
+            .iter()
+            .enumerate()
+            //The name column can't be hidden
+            .skip(1)
+            .map(|(i, c)| ContextItem {
+                display: if settings.has_column(c.name) {
+                    format!("✓  {}", c.display)
+                } else {
+                    format!("    {}", c.display)
+                }
+                .into(),
+                callback_id: ContextCallback::ToggleColumn as i32,
+                shortcut: "".into(),
+                icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+                has_separator: false,
+                click_on_hover: false,
+                internal_id: i as i32,
+            })
+            .collect();
+
+        let ctx_adapter = mw.global::<ContextAdapter>();
+        ctx_adapter.set_items(Rc::new(VecModel::from(menu)).into());
+        ctx_adapter.set_x_pos(x + 1f32);
+        ctx_adapter.set_y_pos(y + 1f32);
+    });
+}
+
+pub fn toggle_column(inner_value: i32) {
+    if let Some(name) = columns::column_name(inner_value) {
+        view_settings::toggle_column(name);
+    }
+}
+
 const MINIMUM_HEADER_PCT: f32 = 4.0;
 pub fn on_header_resize(header: Header, size_offset: f32, original_size: f32) {
     /*[old pct    - old size]*/
@@ -91,6 +127,7 @@ pub fn on_header_resize(header: Header, size_offset: f32, original_size: f32) {
         }
         //Now that the other headers are fine, resize the current one
         incr_header_pct(headers_rc, i, -diff_pct);
+        view_settings::save_columns(&mw);
     });
 }
 
@@ -125,4 +162,3 @@ pub fn get_next_non_min_header_idx(
     }
     None
 }
-// TODO: Save header width's to a file (add to config struct)
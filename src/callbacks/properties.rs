@@ -1,9 +1,27 @@
 use crate::{
+    clipboard::copy::copy_text,
+    core::{get_all_groups, get_all_users, Group},
     file_properties::{self},
     ui::*,
+    utils::{
+        file_picker::open_file_picker,
+        types::{format_date, parse_date},
+    },
+    xattr::{
+        AclEntry, ACL_GROUP, ACL_GROUP_OBJ, ACL_MASK, ACL_OTHER, ACL_USER, ACL_USER_OBJ,
+        USER_NAMESPACE,
+    },
 };
+use chrono::{Datelike, Local, NaiveDate, TimeZone, Timelike};
 use prop_window::run_with_prop_window;
-use slint::ComponentHandle;
+use slint::{ComponentHandle, Model, SharedString, VecModel};
+use std::collections::HashMap;
+
+//Special bits, on top of the rwx triplets
+pub const SUID: i32 = 0o4000;
+pub const SGID: i32 = 0o2000;
+pub const STICKY: i32 = 0o1000;
+pub const ALL_PERM_BITS: i32 = 0o7777;
 
 /*
  *  Save and close
@@ -12,16 +30,50 @@ pub fn ok() {
     file_properties::save();
 }
 pub fn cancel() {
-    run_with_prop_window(|w| w.hide().unwrap());
+    run_with_prop_window(|w| {
+        file_properties::cancel_checksums(&w.global::<PropertiesAdapter>());
+        w.hide().unwrap()
+    });
 }
 
 ///Flips the bit corresponding to mask
-pub fn recalculate_bitmask() {
-    run_with_prop_window(|prop_win| {
+///The mask is marked as touched, meaning it will be applied to every file on save,
+///even if they did not share that bit before (mixed state).
+pub fn recalculate_bitmask(mask: i32) {
+    run_with_prop_window(move |prop_win| {
+        let prop_adp = prop_win.global::<PropertiesAdapter>();
+        prop_adp.set_perm_touched_mask(prop_adp.get_perm_touched_mask() | mask);
+        prop_adp.set_perm_mixed_mask(prop_adp.get_perm_mixed_mask() & !mask);
+        prop_adp.set_perm_bits_str(
+            format_mode(
+                get_merged_bitmask(&prop_win),
+                prop_adp.get_perm_mixed_mask(),
+            )
+            .into(),
+        );
+        prop_adp.set_perm_bits_valid(true);
+        prop_adp.set_perm_bits_dirty(true);
+    });
+}
+
+///Called when the octal mode is typed in directly.
+///If it is valid, the checkboxes are updated to match and every bit is considered touched.
+///The text itself is left alone so we don't fight the user while they are typing.
+pub fn octal_edited(s: SharedString) {
+    run_with_prop_window(move |prop_win| {
         let prop_adp = prop_win.global::<PropertiesAdapter>();
-        prop_adp.set_perm_bits_str(format!("{:o}", get_merged_bitmask(&prop_win)).into());
+        if let Some(mode) = parse_mode(&s) {
+            set_split_bitmask(&prop_adp, mode);
+            prop_adp.set_perm_touched_mask(ALL_PERM_BITS);
+            prop_adp.set_perm_mixed_mask(0);
+            prop_adp.set_perm_bits_valid(true);
+            prop_adp.set_perm_bits_dirty(true);
+        } else {
+            prop_adp.set_perm_bits_valid(false);
+        }
     });
 }
+
 ///To update the UI, bits must be stored in their own variable.
 ///This merges them back to an i32 to actually be able to do something with it.
 pub fn get_merged_bitmask(prop_win: &PropertiesWindow) -> i32 {
@@ -36,8 +88,11 @@ pub fn get_merged_bitmask(prop_win: &PropertiesWindow) -> i32 {
     let o_x = if prop_adp.get_o_x() { 64 } else { 0 };
     let o_w = if prop_adp.get_o_w() { 128 } else { 0 };
     let o_r = if prop_adp.get_o_r() { 256 } else { 0 };
+    let sticky = if prop_adp.get_sticky() { STICKY } else { 0 };
+    let sgid = if prop_adp.get_sgid() { SGID } else { 0 };
+    let suid = if prop_adp.get_suid() { SUID } else { 0 };
 
-    a_x | a_w | a_r | g_x | g_w | g_r | o_x | o_w | o_r
+    a_x | a_w | a_r | g_x | g_w | g_r | o_x | o_w | o_r | sticky | sgid | suid
 }
 ///To update the UI, bits must be stored in their own variable.
 ///This sets them from a single variable
@@ -51,4 +106,335 @@ pub fn set_split_bitmask(prop_adp: &PropertiesAdapter, i: i32) {
     prop_adp.set_o_x((i & 64) > 0);
     prop_adp.set_o_w((i & 128) > 0);
     prop_adp.set_o_r((i & 256) > 0);
+    prop_adp.set_sticky((i & STICKY) > 0);
+    prop_adp.set_sgid((i & SGID) > 0);
+    prop_adp.set_suid((i & SUID) > 0);
+}
+
+///Formats a mode in octal, like chmod would take it.
+///The special bits digit is only shown when it is relevant.
+///Digits containing a mixed bit (multiple files that disagree) are shown as '?'
+pub fn format_mode(mode: i32, mixed: i32) -> String {
+    let digits = if (mode | mixed) & 0o7000 != 0 { 4 } else { 3 };
+    (0..digits)
+        .rev()
+        .map(|i| {
+            let shift = i * 3;
+            if (mixed >> shift) & 0o7 != 0 {
+                '?'
+            } else {
+                char::from_digit(((mode >> shift) & 0o7) as u32, 8).unwrap()
+            }
+        })
+        .collect()
+}
+
+///Parses an octal mode as typed by the user, eg. "755" or "4755".
+///Returns None if it is not a valid mode.
+pub fn parse_mode(s: &str) -> Option<i32> {
+    let s = s.trim();
+    if s.is_empty() || s.len() > 4 || !s.chars().all(|c| c.is_digit(8)) {
+        return None;
+    }
+    i32::from_str_radix(s, 8).ok()
+}
+
+/*
+ *  Times
+ *  which is 0 for the access time, 1 for the modification time
+ * */
+
+fn set_time_state(prop_adp: &PropertiesAdapter, which: i32, valid: bool) {
+    if which == 0 {
+        prop_adp.set_atime_valid(valid);
+        prop_adp.set_atime_dirty(true);
+    } else {
+        prop_adp.set_mtime_valid(valid);
+        prop_adp.set_mtime_dirty(true);
+    }
+}
+
+fn set_time(prop_adp: &PropertiesAdapter, which: i32, time: i64) {
+    if which == 0 {
+        prop_adp.set_atime(format_date(time));
+    } else {
+        prop_adp.set_mtime(format_date(time));
+    }
+    set_time_state(prop_adp, which, true);
+}
+
+///The text is already bound to the adapter, only validate it
+pub fn time_edited(which: i32, s: SharedString) {
+    run_with_prop_window(move |prop_win| {
+        set_time_state(
+            &prop_win.global::<PropertiesAdapter>(),
+            which,
+            parse_date(&s).is_some(),
+        );
+    });
+}
+
+pub fn time_now(which: i32) {
+    run_with_prop_window(move |prop_win| {
+        set_time(
+            &prop_win.global::<PropertiesAdapter>(),
+            which,
+            Local::now().timestamp(),
+        );
+    });
+}
+
+pub fn time_picked(which: i32, parts: DateTimeParts) {
+    run_with_prop_window(move |prop_win| {
+        let time = NaiveDate::from_ymd_opt(parts.year, parts.month as u32, parts.day as u32)
+            .and_then(|d| d.and_hms_opt(parts.hour as u32, parts.minute as u32, 0))
+            .and_then(|t| Local.from_local_datetime(&t).earliest());
+        if let Some(time) = time {
+            set_time(
+                &prop_win.global::<PropertiesAdapter>(),
+                which,
+                time.timestamp(),
+            );
+        }
+    });
+}
+
+///Splits the date for the pickers. Defaults to the current time if it can't be parsed.
+pub fn get_date_time_parts(s: SharedString) -> DateTimeParts {
+    let time = parse_date(&s)
+        .and_then(|t| Local.timestamp_opt(t, 0).single())
+        .unwrap_or_else(Local::now);
+    DateTimeParts {
+        year: time.year(),
+        month: time.month() as i32,
+        day: time.day() as i32,
+        hour: time.hour() as i32,
+        minute: time.minute() as i32,
+    }
+}
+
+/*
+ *  Checksums
+ * */
+
+pub fn calculate_checksums() {
+    run_with_prop_window(|prop_win| {
+        file_properties::calculate_checksums(&prop_win.global::<PropertiesAdapter>());
+    });
+}
+
+pub fn cancel_checksums() {
+    run_with_prop_window(|prop_win| {
+        file_properties::cancel_checksums(&prop_win.global::<PropertiesAdapter>());
+    });
+}
+
+pub fn verify_checksum() {
+    run_with_prop_window(|prop_win| {
+        file_properties::verify_checksum(&prop_win.global::<PropertiesAdapter>());
+    });
+}
+
+pub fn copy_checksum(hash: SharedString) {
+    copy_text(hash.to_string());
+}
+
+///The file picker blocks, so this runs in its own thread
+pub fn browse_checksum_file() {
+    std::thread::spawn(|| {
+        if let Ok(path) = open_file_picker() {
+            run_with_prop_window(move |prop_win| {
+                let prop_adp = prop_win.global::<PropertiesAdapter>();
+                prop_adp.set_checksum_expected(path.into());
+                file_properties::verify_checksum(&prop_adp);
+            });
+        }
+    });
+}
+
+/*
+ *  ACL
+ * */
+
+pub fn format_acl_tag(tag: i32) -> SharedString {
+    match tag as u16 {
+        ACL_USER_OBJ => "Owner",
+        ACL_USER => "User",
+        ACL_GROUP_OBJ => "Owning group",
+        ACL_GROUP => "Group",
+        ACL_MASK => "Mask",
+        ACL_OTHER => "Others",
+        _ => "Unknown",
+    }
+    .into()
+}
+
+///Converts an ACL entry for the UI, resolving the name of named entries
+pub fn acl_entry_to_item(
+    e: &AclEntry,
+    is_default: bool,
+    users: &HashMap<u32, String>,
+    groups: &HashMap<u32, Group>,
+) -> AclItem {
+    let qualifier = match e.tag {
+        ACL_USER => users.get(&e.id).cloned().unwrap_or(e.id.to_string()),
+        ACL_GROUP => groups
+            .get(&e.id)
+            .map(|g| g.name.clone())
+            .unwrap_or(e.id.to_string()),
+        _ => String::new(),
+    };
+    AclItem {
+        tag: e.tag as i32,
+        id: e.id as i32, //ACL_UNDEFINED_ID becomes -1
+        qualifier: qualifier.into(),
+        r: e.perm & 4 != 0,
+        w: e.perm & 2 != 0,
+        x: e.perm & 1 != 0,
+        is_default,
+    }
+}
+
+pub fn acl_item_to_entry(item: &AclItem) -> AclEntry {
+    AclEntry {
+        tag: item.tag as u16,
+        perm: (item.r as u16) << 2 | (item.w as u16) << 1 | item.x as u16,
+        id: item.id as u32,
+    }
+}
+
+fn sort_acl_items(items: &mut Vec<AclItem>) {
+    items.sort_by_key(|e| (e.is_default, e.tag, e.id as u32));
+}
+
+///Adds a named entry. kind is 0 for a user and 1 for a group, name can also be a numeric id.
+///The first default entry also brings the base entries along, since a default ACL needs them.
+pub fn acl_add(kind: i32, name: SharedString, is_default: bool) {
+    run_with_prop_window(move |prop_win| {
+        let prop_adp = prop_win.global::<PropertiesAdapter>();
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        let (tag, id) = if kind == 0 {
+            let id = get_all_users()
+                .ok()
+                .and_then(|users| users.into_iter().find(|(_, n)| *n == name).map(|u| u.0))
+                .or(name.parse::<u32>().ok());
+            (ACL_USER, id)
+        } else {
+            let id = get_all_groups()
+                .ok()
+                .and_then(|groups| groups.into_values().find(|g| g.name == name).map(|g| g.gid))
+                .or(name.parse::<u32>().ok());
+            (ACL_GROUP, id)
+        };
+        let Some(id) = id else {
+            prop_adp.set_acl_error(format!("No such user or group: {}", name).into());
+            return;
+        };
+
+        let mut items: Vec<AclItem> = prop_adp.get_acl_entries().iter().collect();
+        if items
+            .iter()
+            .any(|e| e.is_default == is_default && e.tag == tag as i32 && e.id as u32 == id)
+        {
+            prop_adp.set_acl_error(format!("There is already an entry for {}.", name).into());
+            return;
+        }
+        if is_default && !items.iter().any(|e| e.is_default) {
+            let base: Vec<AclItem> = items
+                .iter()
+                .filter(|e| {
+                    !e.is_default
+                        && matches!(e.tag as u16, ACL_USER_OBJ | ACL_GROUP_OBJ | ACL_OTHER)
+                })
+                .map(|e| AclItem {
+                    is_default: true,
+                    ..e.clone()
+                })
+                .collect();
+            items.extend(base);
+        }
+        items.push(AclItem {
+            tag: tag as i32,
+            id: id as i32,
+            qualifier: name.into(),
+            r: true,
+            w: false,
+            x: false,
+            is_default,
+        });
+        sort_acl_items(&mut items);
+        set_acl_items(&prop_adp, items);
+    });
+}
+
+///Removes an entry. Removing one of the base default entries removes the whole default ACL.
+pub fn acl_remove(i: i32) {
+    run_with_prop_window(move |prop_win| {
+        let prop_adp = prop_win.global::<PropertiesAdapter>();
+        let mut items: Vec<AclItem> = prop_adp.get_acl_entries().iter().collect();
+        let Some(removed) = items.get(i as usize).cloned() else {
+            return;
+        };
+        if removed.is_default
+            && matches!(removed.tag as u16, ACL_USER_OBJ | ACL_GROUP_OBJ | ACL_OTHER)
+        {
+            items.retain(|e| !e.is_default);
+        } else {
+            items.remove(i as usize);
+        }
+        set_acl_items(&prop_adp, items);
+    });
+}
+
+fn set_acl_items(prop_adp: &PropertiesAdapter, items: Vec<AclItem>) {
+    if let Some(vec) = prop_adp
+        .get_acl_entries()
+        .as_any()
+        .downcast_ref::<VecModel<AclItem>>()
+    {
+        vec.set_vec(items);
+    }
+    prop_adp.set_acl_dirty(true);
+    prop_adp.set_acl_error("".into());
+}
+
+/*
+ *  Extended attributes
+ * */
+
+///Adds a new user attribute, the user. prefix is optional
+pub fn xattr_add(name: SharedString, value: SharedString) {
+    run_with_prop_window(move |prop_win| {
+        let prop_adp = prop_win.global::<PropertiesAdapter>();
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        let name = if name.starts_with(USER_NAMESPACE) {
+            name.to_string()
+        } else {
+            USER_NAMESPACE.to_string() + name
+        };
+        let attrs = prop_adp.get_xattrs();
+        if attrs.iter().any(|a| a.name == name && !a.removed) {
+            prop_adp.set_xattr_error(format!("{} already exists.", name).into());
+            return;
+        }
+        if let Some(vec) = attrs.as_any().downcast_ref::<VecModel<XattrItem>>() {
+            vec.push(XattrItem {
+                name: name.into(),
+                value,
+                error: "".into(),
+                dirty: true,
+                removed: false,
+                is_new: true,
+                hex: false,
+                nul_terminated: false,
+            });
+        }
+        prop_adp.set_xattr_error("".into());
+    });
 }
@@ -1,14 +1,22 @@
-use crate::ui::{self, *};
+use crate::{
+    drives, icon_theme, udisks,
+    ui::{self, *},
+};
 
-use super::filemanager;
+use super::{filemanager, tabs};
 
 pub fn sidebar_item_clicked(item: SidebarItem) {
+    if !item.mounted {
+        drives::mount_device(&item.device);
+        return;
+    }
     ui::send_message(UIMessage::SetCurrentTabFile(
         TabItem {
             internal_path: item.internal_path,
             text: item.text.clone(),
             text_length: item.text.len() as i32,
             selected: true,
+            view_mode: tabs::VIEW_DETAILS,
         },
         true,
     ));
@@ -26,3 +34,19 @@ pub fn right_arrow_clicked() {
         None => (),
     }
 }
+
+///The theme icon of a drive. Without UDisks2, removable media are the ones mounted under /media
+///or /run/media.
+pub fn drive_icon(drive: &SidebarItem, size: u32) -> slint::Image {
+    let path = drive.internal_path.as_str();
+    let removable = match udisks::cached(&drive.device) {
+        Some(volume) => volume.removable,
+        None => path.starts_with("/media/") || path.starts_with("/run/media/"),
+    };
+    let name = if removable {
+        "drive-removable-media"
+    } else {
+        "drive-harddisk"
+    };
+    icon_theme::named_icon(name, size)
+}
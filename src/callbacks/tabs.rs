@@ -1,9 +1,21 @@
 use crate::{
-    ui::{self, *},
+    callbacks::context_menu::ContextCallback,
+    context_menus::secondary_context_menu::show_secondary_context_menu,
+    ui::{self, main_window::run_with_main_window, *},
     utils::{error_handling::user_notice, is_directory_valid},
 };
 
-use slint::SharedString;
+use slint::{Image, Model, SharedPixelBuffer, SharedString};
+
+///The ways a tab can show its files (TabItem.view_mode), also ViewModes in ui/tabs.slint
+pub const VIEW_DETAILS: i32 = 0;
+pub const VIEW_COMPACT: i32 = 1;
+pub const VIEW_ICONS: i32 = 2;
+const VIEW_MODES: [(i32, &str); 3] = [
+    (VIEW_DETAILS, "Details"),
+    (VIEW_COMPACT, "Compact List"),
+    (VIEW_ICONS, "Icons"),
+];
 
 pub fn breadcrumb_clicked(item: TabItem) {
     ui::send_message(UIMessage::SetCurrentTabFile(item, true));
@@ -21,6 +33,7 @@ pub fn get_breadcrumbs_for(item: &TabItem) -> Vec<TabItem> {
                 text: x.into(),
                 selected: true,
                 text_length: -1,
+                view_mode: VIEW_DETAILS,
             };
             s += x;
             s += "/";
@@ -43,6 +56,48 @@ pub fn breadcrumb_accepted(mut s: SharedString) {
         internal_path: s,
         selected: true,
         text_length: -1,
+        view_mode: VIEW_DETAILS,
     };
     ui::send_message(UIMessage::SetCurrentTabFile(item, true));
 }
+
+///Changes how the current tab shows its files
+pub fn set_view_mode(mode: i32) {
+    run_with_main_window(move |mw| {
+        let adp = mw.global::<TabsAdapter>();
+        let tabs = adp.get_tabs();
+        let i = adp.get_current_tab_idx() as usize;
+        if let Some(mut tab) = tabs.row_data(i) {
+            tab.view_mode = mode;
+            tabs.set_row_data(i, tab);
+        }
+    });
+}
+
+///Triggered when hovering over "view" in the context menu
+pub fn view_mode_hover(parent_index: i32) {
+    run_with_main_window(move |mw| {
+        let current = mw
+            .global::<TabsAdapter>()
+            .invoke_get_current_tab()
+            .view_mode;
+        let menu: Vec<ContextItem> = VIEW_MODES
+            .iter()
+            .map(|(mode, display)| ContextItem {
+                display: if *mode == current {
+                    format!("✓  {}", display)
+                } else {
+                    format!("    {}", display)
+                }
+                .into(),
+                callback_id: ContextCallback::SetViewMode as i32,
+                shortcut: "".into(),
+                icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+                has_separator: false,
+                click_on_hover: false,
+                internal_id: *mode,
+            })
+            .collect();
+        show_secondary_context_menu(menu, parent_index);
+    });
+}
@@ -0,0 +1,110 @@
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::{fs::File, io::Read, path::Path};
+
+/*
+ *  File checksums, all computed in a single pass over the file.
+ * */
+
+pub const ALGORITHMS: [&str; 4] = ["MD5", "SHA-1", "SHA-256", "BLAKE3"];
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+#[derive(Default)]
+pub struct Hashers {
+    md5: Md5,
+    sha1: Sha1,
+    sha256: Sha256,
+    blake3: blake3::Hasher,
+}
+
+impl Hashers {
+    pub fn update(&mut self, data: &[u8]) {
+        self.md5.update(data);
+        self.sha1.update(data);
+        self.sha256.update(data);
+        self.blake3.update(data);
+    }
+
+    ///Hex digests, in the same order as ALGORITHMS
+    pub fn finalize(self) -> [String; 4] {
+        [
+            to_hex(&self.md5.finalize()),
+            to_hex(&self.sha1.finalize()),
+            to_hex(&self.sha256.finalize()),
+            self.blake3.finalize().to_hex().to_string(),
+        ]
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+///Hashes the file, calling progress with the number of bytes read after each chunk.
+///Stops and returns None if progress returns false.
+pub fn hash_file(
+    path: &Path,
+    mut progress: impl FnMut(u64) -> bool,
+) -> Result<Option<[String; 4]>, std::io::Error> {
+    let mut file = File::open(path)?;
+    let mut hashers = Hashers::default();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut total: u64 = 0;
+    loop {
+        let read = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hashers.update(&buf[..read]);
+        total += read as u64;
+        if !progress(total) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(hashers.finalize()))
+}
+
+///Finds the expected hash for file_name in what the user pasted.
+///This is either a bare hash or the content of a checksum file (eg. sha256sum output),
+///where lines look like "<hash>  <name>" or "<hash> *<name>" for binary mode.
+pub fn find_expected_hash(input: &str, file_name: &str) -> Option<String> {
+    let mut only_hash = None;
+    let mut line_count = 0;
+    for line in input.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        if line.starts_with('#') {
+            continue;
+        }
+        line_count += 1;
+        let (hash, name) = match line.split_once(char::is_whitespace) {
+            Some((hash, name)) => (hash, Some(name.trim_start().trim_start_matches('*'))),
+            None => (line, None),
+        };
+        if !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            continue;
+        }
+        match name {
+            //Checksum files may list paths, compare the file name only
+            Some(name) if Path::new(name).file_name() == Some(file_name.as_ref()) => {
+                return Some(hash.to_lowercase())
+            }
+            //A single line is used even if the name differs, eg. the file was renamed
+            _ => only_hash = Some(hash.to_lowercase()),
+        }
+    }
+    if line_count == 1 {
+        only_hash
+    } else {
+        None
+    }
+}
+
+///Returns the algorithm whose hash matches the expected one, if any
+pub fn match_hash(hashes: &[String], expected: &str) -> Option<&'static str> {
+    hashes
+        .iter()
+        .position(|h| h.eq_ignore_ascii_case(expected))
+        .map(|i| ALGORITHMS[i])
+}
@@ -44,6 +44,19 @@ pub fn copy_file(files: Vec<FileItem>, is_cut: bool) {
     std::thread::sleep(std::time::Duration::from_millis(10)); //Make sure clipboard gets set
 }
 
+///Copies plain text to the clipboard
+pub fn copy_text(text: String) {
+    thread::spawn(move || {
+        if let Ok(mut clip) = Clipboard::new() {
+            if clip.set().wait().text(text).is_err() {
+                log_error_str("Could not set the clipboard text");
+            }
+        } else {
+            log_error_str("Could not find a clipboard.");
+        }
+    });
+}
+
 ///Copies a single file while maintaining speed and progress information
 ///Will return Err if the receiver was disconnected (and we should cancel the operation)
 ///Caller is responsible to verify whether or not this will be overwritten
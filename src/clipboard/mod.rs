@@ -16,6 +16,7 @@ pub mod copy;
 pub mod cut;
 pub mod delete;
 pub mod paste;
+pub mod trash;
 
 ///Contains the files to delete after the paste
 pub static CUT_BUFFER: OnceLock<Mutex<Vec<FileItem>>> = OnceLock::new();
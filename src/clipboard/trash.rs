@@ -0,0 +1,90 @@
+use crate::{
+    core::get_uid,
+    utils::{percent_encode_path, xdg},
+};
+use chrono::Local;
+use std::{
+    fs::{create_dir_all, remove_file, rename, OpenOptions},
+    io::{ErrorKind, Write},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+/*
+ *  Freedesktop trash (https://specifications.freedesktop.org/trash-spec/latest/)
+ *
+ *  Files on the same device as the home directory go to $XDG_DATA_HOME/Trash,
+ *  others go to $topdir/.Trash-$uid on their own device.
+ * */
+
+///Moves a file or directory to the trash of its device
+pub fn trash_file(path: &Path) -> Result<(), std::io::Error> {
+    let path = std::path::absolute(path)?;
+    let home_trash = home_trash_dir();
+    let dev = path.symlink_metadata()?.dev();
+    //The home trash may not exist yet, use the closest existing ancestor
+    let trash_dir = match home_trash
+        .ancestors()
+        .find_map(|p| p.metadata().ok())
+        .map(|m| m.dev())
+    {
+        Some(home_dev) if home_dev == dev => home_trash,
+        _ => topdir_trash_dir(&path, dev)?,
+    };
+    create_dir_all(trash_dir.join("files"))?;
+    create_dir_all(trash_dir.join("info"))?;
+
+    let file_name = path
+        .file_name()
+        .ok_or(std::io::Error::from(ErrorKind::InvalidInput))?
+        .to_string_lossy()
+        .to_string();
+
+    //The .trashinfo file is created first (exclusively) to reserve the name
+    let mut i = 1;
+    let (name, mut info) = loop {
+        let name = if i == 1 {
+            file_name.clone()
+        } else {
+            format!("{}.{}", file_name, i)
+        };
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(trash_dir.join("info").join(name.clone() + ".trashinfo"))
+        {
+            Ok(f) => break (name, f),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => i += 1,
+            Err(e) => return Err(e),
+        }
+    };
+    let info_path = trash_dir.join("info").join(name.clone() + ".trashinfo");
+
+    let ret = write!(
+        info,
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode_path(&path),
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    )
+    .and_then(|_| rename(&path, trash_dir.join("files").join(&name)));
+    if ret.is_err() {
+        remove_file(info_path).ok();
+    }
+    ret
+}
+
+fn home_trash_dir() -> PathBuf {
+    xdg::data_home().join("Trash")
+}
+
+///$topdir/.Trash-$uid, where topdir is the mount point the file is on.
+fn topdir_trash_dir(path: &Path, dev: u64) -> Result<PathBuf, std::io::Error> {
+    let mut topdir = path;
+    while let Some(parent) = topdir.parent() {
+        if parent.metadata()?.dev() != dev {
+            break;
+        }
+        topdir = parent;
+    }
+    Ok(topdir.join(format!(".Trash-{}", get_uid())))
+}
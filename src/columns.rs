@@ -0,0 +1,253 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    thread,
+};
+
+use slint::{ComponentHandle, Model, SharedString};
+
+use crate::{
+    callbacks::filemanager::format_date,
+    core::{get_all_groups, get_all_users},
+    mime::mime_type_for_file,
+    sort::{self, call_current_sort, SortKey, SortOptions},
+    ui::{main_window::run_with_main_window, *},
+    utils::types::i32_to_i64,
+    view_settings::ViewSettings,
+};
+
+/*
+ *  The columns that can be shown in the file list.
+ *  The index in COLUMNS is the inner_value of the header, name/size/date are drawn by the UI,
+ *  the others are formatted by format_column.
+ * */
+
+pub struct Column {
+    ///Used in the configuration and the view settings
+    pub name: &'static str,
+    pub display: &'static str,
+    ///0-left, 1-center, 2-right
+    pub alignment: i32,
+    ///Width given to the column when it is shown from the header menu
+    pub default_width: f32,
+}
+
+pub const NAME: i32 = 0;
+pub const SIZE: i32 = 1;
+pub const DATE: i32 = 2;
+pub const MODE: i32 = 3;
+pub const OWNER: i32 = 4;
+pub const GROUP: i32 = 5;
+pub const MIME: i32 = 6;
+pub const EXTENSION: i32 = 7;
+pub const CREATED: i32 = 8;
+pub const ACCESSED: i32 = 9;
+pub const LINK_TARGET: i32 = 10;
+pub const LOCATION: i32 = 11;
+
+#[rustfmt::skip]
+pub const COLUMNS: [Column; 12] = [
+    Column { name: "name", display: "Name", alignment: 0, default_width: 70.0 },
+    Column { name: "size", display: "Size", alignment: 2, default_width: 15.0 },
+    Column { name: "date", display: "Date", alignment: 0, default_width: 20.0 },
+    Column { name: "mode", display: "Permissions", alignment: 0, default_width: 15.0 },
+    Column { name: "owner", display: "Owner", alignment: 0, default_width: 12.0 },
+    Column { name: "group", display: "Group", alignment: 0, default_width: 12.0 },
+    Column { name: "mime", display: "Type", alignment: 0, default_width: 20.0 },
+    Column { name: "extension", display: "Extension", alignment: 0, default_width: 10.0 },
+    Column { name: "created", display: "Created", alignment: 0, default_width: 20.0 },
+    Column { name: "accessed", display: "Accessed", alignment: 0, default_width: 20.0 },
+    Column { name: "link_target", display: "Link Target", alignment: 0, default_width: 25.0 },
+    Column { name: "location", display: "Location", alignment: 0, default_width: 30.0 },
+];
+
+//Incremented when the files shown change, older MIME type lookups are dropped
+static MIME_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+pub fn column_index(name: &str) -> Option<i32> {
+    COLUMNS
+        .iter()
+        .position(|c| c.name == name)
+        .map(|i| i as i32)
+}
+
+pub fn column_name(inner_value: i32) -> Option<&'static str> {
+    COLUMNS.get(inner_value as usize).map(|c| c.name)
+}
+
+///The column header for a column name, None if there is no such column
+pub fn header_for(name: &str, width_pct: f32, sort: i32) -> Option<Header> {
+    let inner_value = column_index(name)?;
+    let column = &COLUMNS[inner_value as usize];
+    Some(Header {
+        inner_value,
+        display: column.display.into(),
+        width_pct,
+        alignment: column.alignment,
+        sort,
+    })
+}
+
+/*
+ *  Owner and group names, read once from /etc/passwd and /etc/group
+ * */
+
+fn users() -> &'static HashMap<u32, String> {
+    static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    USERS.get_or_init(|| get_all_users().unwrap_or_default())
+}
+
+fn groups() -> &'static HashMap<u32, String> {
+    static GROUPS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    GROUPS.get_or_init(|| {
+        get_all_groups()
+            .map(|groups| groups.into_iter().map(|(gid, g)| (gid, g.name)).collect())
+            .unwrap_or_default()
+    })
+}
+
+///The user name, or the uid if it has none
+pub fn owner_name(uid: i32) -> String {
+    let uid = uid as u32;
+    users()
+        .get(&uid)
+        .cloned()
+        .unwrap_or_else(|| uid.to_string())
+}
+
+///The group name, or the gid if it has none
+pub fn group_name(gid: i32) -> String {
+    let gid = gid as u32;
+    groups()
+        .get(&gid)
+        .cloned()
+        .unwrap_or_else(|| gid.to_string())
+}
+
+///Formats a mode like ls -l does, eg. "drwxr-xr-x"
+pub fn format_mode_string(mode: i32, is_dir: bool, is_link: bool) -> String {
+    let mut s = String::with_capacity(10);
+    s.push(if is_link {
+        'l'
+    } else if is_dir {
+        'd'
+    } else {
+        '-'
+    });
+    //(special bit, execute char when set, without execute)
+    let special = [(0o4000, 's', 'S'), (0o2000, 's', 'S'), (0o1000, 't', 'T')];
+    for (i, (bit, with_x, without_x)) in special.into_iter().enumerate() {
+        let shift = 6 - i * 3;
+        let perm = (mode >> shift) & 0o7;
+        s.push(if perm & 4 != 0 { 'r' } else { '-' });
+        s.push(if perm & 2 != 0 { 'w' } else { '-' });
+        s.push(match (mode & bit != 0, perm & 1 != 0) {
+            (true, true) => with_x,
+            (true, false) => without_x,
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    s
+}
+
+fn format_time(t: _i64) -> SharedString {
+    if t.a == 0 && t.b == 0 {
+        "".into()
+    } else {
+        format_date(t)
+    }
+}
+
+///The directory containing the file, useful when the files shown come from several (search results)
+pub fn location(file: &FileItem) -> String {
+    Path::new(file.path.as_str())
+        .parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+///Text of the columns that aren't drawn by the UI itself
+pub fn format_column(file: FileItem, column: i32) -> SharedString {
+    match column {
+        MODE => format_mode_string(file.mode, file.is_dir, file.is_link).into(),
+        OWNER => owner_name(file.uid).into(),
+        GROUP => group_name(file.gid).into(),
+        MIME => file.mime_type,
+        EXTENSION => {
+            if file.is_dir {
+                "".into()
+            } else {
+                file.extension
+            }
+        }
+        CREATED => format_time(file.created),
+        ACCESSED => format_time(file.accessed),
+        LINK_TARGET => file.link_target,
+        LOCATION => location(&file).into(),
+        _ => "".into(),
+    }
+}
+
+///The value of a file to sort by for a column
+pub fn sort_key(file: &FileItem, column: i32, options: &SortOptions) -> SortKey {
+    let int = |t: &_i64| SortKey::Number(i32_to_i64((t.a, t.b)));
+    match column {
+        NAME => options.name_key(&file.file_name),
+        SIZE => int(&file.size),
+        DATE => int(&file.date),
+        MODE => SortKey::Number((file.mode & 0o7777) as i64),
+        OWNER => SortKey::Text(owner_name(file.uid)),
+        GROUP => SortKey::Text(group_name(file.gid)),
+        MIME => SortKey::Text(file.mime_type.to_string()),
+        EXTENSION => SortKey::Text(file.extension.to_lowercase()),
+        CREATED => int(&file.created),
+        ACCESSED => int(&file.accessed),
+        LINK_TARGET => SortKey::Text(file.link_target.to_string()),
+        LOCATION => SortKey::Text(location(file)),
+        _ => SortKey::Number(0),
+    }
+}
+
+///The MIME type can need the content of the file, so it is only filled when the column is shown,
+///in a thread. The files shown get their type once all of them are known.
+pub fn fill_mime_types(mw: &MainWindow, settings: &ViewSettings) {
+    let generation = MIME_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if !settings.has_column("mime") {
+        return;
+    }
+    let files: Vec<FileItem> = mw.global::<FileManager>().get_files().iter().collect();
+    let sorted_by_mime = settings.sort == "mime";
+    thread::spawn(move || {
+        let mut types: HashMap<SharedString, SharedString> = HashMap::new();
+        for f in &files {
+            if MIME_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            types.insert(f.path.clone(), mime_type_for_file(f).into());
+        }
+        run_with_main_window(move |mw| {
+            //The directory or the columns changed in the meantime
+            if MIME_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let model = mw.global::<FileManager>().get_files();
+            for i in 0..model.row_count() {
+                let Some(mut f) = model.row_data(i) else {
+                    continue;
+                };
+                if let Some(mime) = types.get(&f.path) {
+                    f.mime_type = mime.clone();
+                    model.set_row_data(i, f);
+                }
+            }
+            if sort::cancel_pending() || sorted_by_mime {
+                call_current_sort(mw);
+            }
+        });
+    });
+}
@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use crate::{
+    core::run_command,
+    utils::{error_handling::user_notice, percent_encode_path},
+};
+
+/*
+ *  Command templates, used by the extension mappings and desktop entries.
+ *
+ *  Arguments are separated by whitespace and may be double quoted, where \", \`, \$ and \\
+ *  are escapes (the quoting of desktop entries' Exec key). Field codes:
+ *      %f  a file path             %F  all the file paths, as separate arguments
+ *      %u  a file URI              %U  all the file URIs, as separate arguments
+ *      %d  the file's directory    %n  the file name
+ *      %%  a literal %
+ *  Without %F or %U the command is run once per file. A template without any field code
+ *  gets %F appended. Other codes (%i, %c, %k and the deprecated ones) are removed.
+ * */
+
+///Splits a template into its arguments, see the quoting rules above.
+pub fn split_args(template: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' if quoted => match chars.next() {
+                Some(e @ ('"' | '`' | '$' | '\\')) => current.push(e),
+                Some(e) => {
+                    current.push('\\');
+                    current.push(e);
+                }
+                None => current.push('\\'),
+            },
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+///Quotes an argument so that split_args gives it back as is.
+///Field codes in it are escaped too.
+pub fn quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/-_.,:=+@".contains(c));
+    if safe {
+        return arg.into();
+    }
+    let mut ret = String::from('"');
+    for c in arg.chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                ret.push('\\');
+                ret.push(c);
+            }
+            '%' => ret.push_str("%%"),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+pub fn file_uri(path: &str) -> String {
+    "file://".to_owned() + &percent_encode_path(Path::new(path))
+}
+
+//Field codes in an argument, %% excluded
+fn field_codes(arg: &str) -> Vec<char> {
+    let mut codes = Vec::new();
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            match chars.next() {
+                Some('%') | None => (),
+                Some(code) => codes.push(code),
+            }
+        }
+    }
+    codes
+}
+
+//Expands the single file codes of an argument. None if the argument only was a removed code.
+fn expand_arg(arg: &str, file: Option<&str>) -> Option<String> {
+    let mut ret = String::with_capacity(arg.len());
+    let mut removed_only = true;
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            ret.push(c);
+            removed_only = false;
+            continue;
+        }
+        let path = file.map(Path::new);
+        let expanded = match chars.next() {
+            Some('%') | None => Some("%".to_string()),
+            Some('f') => file.map(String::from),
+            Some('u') => file.map(file_uri),
+            Some('d') => path
+                .and_then(|p| p.parent())
+                .map(|p| p.to_string_lossy().to_string()),
+            Some('n') => path
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().to_string()),
+            Some(_) => None,
+        };
+        if let Some(s) = expanded {
+            ret.push_str(&s);
+            removed_only = false;
+        }
+    }
+    if removed_only && !arg.is_empty() {
+        None
+    } else {
+        Some(ret)
+    }
+}
+
+///Expands the template for the files, returning the argument vectors of every command to run
+pub fn expand(template: &str, files: &[String]) -> Vec<Vec<String>> {
+    let mut args = split_args(template);
+    if args.is_empty() {
+        return Vec::new();
+    }
+    let codes: Vec<char> = args.iter().flat_map(|a| field_codes(a)).collect();
+    if !codes.iter().any(|c| "fFuUdn".contains(*c)) {
+        args.push("%F".into());
+    }
+    let has_list = args.iter().any(|a| a == "%F" || a == "%U");
+
+    let expand_one = |file: Option<&str>| -> Vec<String> {
+        let mut ret = Vec::new();
+        for arg in &args {
+            match arg.as_str() {
+                "%F" => ret.extend(files.iter().cloned()),
+                "%U" => ret.extend(files.iter().map(|f| file_uri(f))),
+                _ => ret.extend(expand_arg(arg, file)),
+            }
+        }
+        ret
+    };
+
+    if has_list || files.is_empty() {
+        vec![expand_one(files.first().map(|f| f.as_str()))]
+    } else {
+        files.iter().map(|f| expand_one(Some(f))).collect()
+    }
+}
+
+///Runs the template for the files. Errors are shown to the user, not returned.
+pub fn launch(template: &str, files: &[String]) {
+    let commands = expand(template, files);
+    if commands.is_empty() {
+        user_notice("The command to run is empty. Fix your mappings.");
+    }
+    for args in commands {
+        if let Err(e) = run_command(&args) {
+            user_notice(&format!(
+                "Could not run {}: {}",
+                args.first().map(|a| a.as_str()).unwrap_or(template),
+                e
+            ));
+        }
+    }
+}
@@ -3,7 +3,9 @@ use serde_json::Error;
 use slint::VecModel;
 
 use crate::{
+    columns,
     keybinds::keybind::{get_keybind, KeyBind},
+    mime::glob_match,
     ui::*,
 };
 use std::{collections::HashMap, str::FromStr};
@@ -14,6 +16,10 @@ pub struct Config {
     map: HashMap<&'static str, String>,
     extension_mappings_default: Option<HashMap<String, String>>,
     extension_mappings_quick: Option<HashMap<String, Vec<Mapping>>>,
+    custom_actions: Option<Vec<CustomAction>>,
+    hidden_mounts: Option<Vec<MountRule>>,
+    shown_mounts: Option<Vec<MountRule>>,
+    drive_aliases: Option<HashMap<String, String>>,
     pub keybinds: Option<HashMap<KeyBind, String>>,
 }
 
@@ -24,12 +30,69 @@ pub struct Mapping {
     pub command: String,
 }
 
+///A user defined command shown in the context menu, see custom_actions
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CustomAction {
+    pub name: String,
+    ///Path to an image
+    #[serde(default)]
+    pub icon: String,
+    ///Command template, see command_template
+    pub command: String,
+    ///Empty means any. A file matches if either its extension or MIME type does.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    ///"image/*" matches all images
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+    #[serde(default = "default_min_selection")]
+    pub min_selection: usize,
+    #[serde(default)]
+    pub max_selection: Option<usize>,
+    #[serde(default)]
+    pub terminal: bool,
+}
+
+fn default_min_selection() -> usize {
+    1
+}
+
+///Matches mounted filesystems, see hidden_mounts. The fields are globs, the missing ones match
+///anything, a rule without any matches nothing.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct MountRule {
+    #[serde(default)]
+    pub mount: Option<String>,
+    #[serde(default)]
+    pub fs_type: Option<String>,
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+impl MountRule {
+    pub fn matches(&self, mount: &str, fs_type: &str, device: &str) -> bool {
+        let fields = [
+            (&self.mount, mount),
+            (&self.fs_type, fs_type),
+            (&self.device, device),
+        ];
+        fields.iter().any(|(pattern, _)| pattern.is_some())
+            && fields
+                .iter()
+                .all(|(pattern, value)| pattern.as_ref().is_none_or(|p| glob_match(p, value)))
+    }
+}
+
 impl Config {
     pub fn new() -> Self {
         let mut ret = Self {
             map: Config::default_config(),
             extension_mappings_default: None,
             extension_mappings_quick: None,
+            custom_actions: None,
+            hidden_mounts: None,
+            shown_mounts: None,
+            drive_aliases: None,
             keybinds: None,
         };
         ret.init();
@@ -37,6 +100,8 @@ impl Config {
     }
     pub fn init(&mut self) {
         self.init_mappings();
+        self.init_custom_actions();
+        self.init_drives();
         self.init_keybinds();
     }
     //TODO: use json everywhere
@@ -46,34 +111,61 @@ impl Config {
             ("default_path", "/".into()),
             ("terminal", "st".into()),
             ("theme", "dark".into()),
+            //Freedesktop icon theme, the GTK one when empty
+            ("icon_theme", "".into()),
+            //Mounts not shown in the sidebar, unless they match shown_mounts.
+            //eg. {"mount": "/mnt/*"}, {"fs_type": "nfs*"}, {"device": "/dev/sdc*"}
+            (
+                "hidden_mounts",
+                r#"[
+                        {"mount": "/boot"},
+                        {"mount": "/boot/*"},
+                        {"mount": "/snap/*"},
+                        {"mount": "/var/lib/docker/*"},
+                        {"fs_type": "squashfs"},
+                        {"fs_type": "tmpfs"},
+                        {"fs_type": "devtmpfs"},
+                        {"fs_type": "overlay"},
+                        {"device": "/dev/loop*"}
+                    ]"#
+                .into(),
+            ),
+            ("shown_mounts", "[]".into()),
+            //Names of the drives by mount point, eg. {"/mnt/storage": "Media"}.
+            //Otherwise the label of the filesystem is used.
+            ("drive_aliases", "{}".into()),
+            //Percentages of used space from which the drive usage bars change color
+            ("drive_usage_warning", "80".into()),
+            ("drive_usage_critical", "90".into()),
+            ("drive_refresh_secs", "30".into()),
             (
                 //<name>:<width_percent>:<0/1/2 not_selected/ascending/descending>
                 "headers",
                 "name:70:1,size:15:0,date:20:0".into(),
             ),
             ("default_sort", "name".into()),
-            (
-                "extension_mappings_default",
-                r#"{
-                        "sh": "Bash",
-                        "txt": "Neovim"
-                    }"#
-                .into(),
-            ),
-            (
-                "extension_mappings_quick",
-                r#"{
-                        "sh":[
-                            {"display_name": "Bash", "command": "/usr/local/bin/st /bin/bash"},
-                            {"display_name": "Sh", "command": "/usr/local/bin/st /bin/sh"}
-                        ], 
-                        "txt":[
-                            {"display_name": "Neovim", "command": "/usr/local/bin/st /bin/nvim"},
-                            {"display_name": "Nano", "command": "/usr/local/bin/st /bin/nano"}
-                        ]
-                    }"#
-                .into(),
-            ),
+            ("show_hidden", "true".into()),
+            ("show_preview", "false".into()),
+            //How much of a text file the preview pane shows
+            ("preview_text_kb", "16".into()),
+            //How much of a file the viewer reads, larger files only show their start
+            ("viewer_max_kb", "1024".into()),
+            //Numbers in names are compared by value ("file2" before "file10")
+            ("sort_natural", "true".into()),
+            ("sort_case_sensitive", "false".into()),
+            ("sort_dotfiles_first", "false".into()),
+            ("sort_folders_first", "true".into()),
+            //Columns used to order the files that are equal in the sorted column, eg. "extension,name"
+            ("sort_secondary_keys", "name".into()),
+            //A search stops once it found this many files
+            ("search_max_results", "10000".into()),
+            //Per-extension overrides of the applications from the XDG MIME database
+            ("extension_mappings_default", "{}".into()),
+            ("extension_mappings_quick", "{}".into()),
+            //eg. [{"name": "Convert to PNG", "command": "convert %f %f.png",
+            //      "mime_types": ["image/*"], "max_selection": 1}]
+            //Bind them to keys with "action:<name>"
+            ("custom_actions", "[]".into()),
             //Multiple keybinds for the same feature is allowed
             (
                 "keybinds",
@@ -83,11 +175,26 @@ impl Config {
                         "down": "select_down",
                         "shift down": "shift_select_down",
                         "shift up": "shift_select_up",
+                        "left": "select_left",
+                        "right": "select_right",
+                        "shift left": "shift_select_left",
+                        "shift right": "shift_select_right",
                         "enter": "enter",
                         "ctrl c": "copy",
                         "ctrl v": "paste",
                         "ctrl x": "cut",
                         "alt enter": "properties",
+                        "ctrl u": "analyze_disk_usage",
+                        "f4": "open_terminal_here",
+                        "ctrl r": "run_command",
+                        "ctrl h": "toggle_hidden",
+                        "ctrl 1": "view_details",
+                        "ctrl 2": "view_compact",
+                        "ctrl 3": "view_icons",
+                        "f3": "toggle_preview",
+                        "alt v": "view_source",
+                        "ctrl d": "add_place",
+                        "ctrl f": "search",
                         "delete": "delete"
                     }"#
                 .into(),
@@ -104,42 +211,43 @@ impl Config {
             Some(res.ok().unwrap())
         }
     }
-    pub fn get_headers(&self) -> VecModel<Header> {
-        let headers_string: String = self.get("headers").unwrap();
-        let headers_vec: Vec<Header> = headers_string
+    ///Parses the "headers" key into (name, width_pct, sort) for each known column
+    ///Invalid entries are logged and skipped.
+    pub fn get_header_columns(&self) -> Vec<(String, f32, i32)> {
+        let headers_string: String = self.get("headers").unwrap_or_default();
+        let columns: Vec<(String, f32, i32)> = headers_string
             .split(",")
-            .map(|s| {
+            .filter_map(|s| {
                 let mut iter = s.split(":");
-                let name = iter.next().unwrap();
-                let pct = iter.next().unwrap().parse::<f32>().unwrap();
-                let sort = iter.next().unwrap().parse::<i32>().unwrap();
-
-                match name {
-                    "name" => Header {
-                        inner_value: 0,
-                        display: "Name".into(),
-                        width_pct: pct,
-                        alignment: 0,
-                        sort,
-                    },
-                    "size" => Header {
-                        inner_value: 1,
-                        display: "Size".into(),
-                        width_pct: pct,
-                        alignment: 2,
-                        sort,
-                    },
-                    "date" => Header {
-                        inner_value: 2,
-                        display: "Date".into(),
-                        width_pct: pct,
-                        alignment: 0,
-                        sort,
-                    },
-                    _ => panic!("Could not parse headers from configuration"),
+                let name = iter.next()?.trim();
+                let pct = iter.next().and_then(|p| p.parse::<f32>().ok());
+                let sort = iter.next().and_then(|p| p.parse::<i32>().ok());
+                match (pct, sort) {
+                    (Some(pct), Some(sort)) if columns::column_index(name).is_some() => {
+                        Some((name.to_string(), pct, sort))
+                    }
+                    _ => {
+                        log_error_str(&format!(
+                            "Invalid column in the \"headers\" config: '{}'",
+                            s
+                        ));
+                        None
+                    }
                 }
             })
             .collect();
+        if columns.is_empty() {
+            //The file list needs at least the names
+            return vec![("name".into(), 100.0, 1)];
+        }
+        columns
+    }
+    pub fn get_headers(&self) -> VecModel<Header> {
+        let headers_vec: Vec<Header> = self
+            .get_header_columns()
+            .into_iter()
+            .filter_map(|(name, pct, sort)| columns::header_for(&name, pct, sort))
+            .collect();
         let headers_vecmodel = VecModel::default();
         headers_vecmodel.set_vec(headers_vec);
         headers_vecmodel
@@ -190,6 +298,58 @@ impl Config {
         Ok(())
     }
 
+    fn init_custom_actions(&mut self) {
+        let config_string: String = self.get("custom_actions").unwrap();
+        match serde_json::from_str::<Vec<CustomAction>>(&config_string) {
+            Ok(actions) => self.custom_actions = Some(actions),
+            Err(e) => log_error_str(&format!(
+                "Could not parse 'custom_actions' from configuration. Please fix it and restart. Error: {}",
+                e
+            )),
+        }
+    }
+
+    pub fn get_custom_actions(&self) -> &[CustomAction] {
+        self.custom_actions.as_deref().unwrap_or(&[])
+    }
+
+    fn init_drives(&mut self) {
+        let parse_rules = |k: &str| -> Option<Vec<MountRule>> {
+            let config_string: String = self.get(k).unwrap();
+            serde_json::from_str(&config_string)
+                .map_err(|e| {
+                    log_error_str(&format!(
+                        "Could not parse '{}' from configuration. Please fix it and restart. Error: {}",
+                        k, e
+                    ))
+                })
+                .ok()
+        };
+        let (hidden, shown) = (parse_rules("hidden_mounts"), parse_rules("shown_mounts"));
+        self.hidden_mounts = hidden;
+        self.shown_mounts = shown;
+        let config_string: String = self.get("drive_aliases").unwrap();
+        match serde_json::from_str::<HashMap<String, String>>(&config_string) {
+            Ok(aliases) => self.drive_aliases = Some(aliases),
+            Err(e) => log_error_str(&format!(
+                "Could not parse 'drive_aliases' from configuration. Please fix it and restart. Error: {}",
+                e
+            )),
+        }
+    }
+
+    pub fn get_hidden_mounts(&self) -> &[MountRule] {
+        self.hidden_mounts.as_deref().unwrap_or(&[])
+    }
+
+    pub fn get_shown_mounts(&self) -> &[MountRule] {
+        self.shown_mounts.as_deref().unwrap_or(&[])
+    }
+
+    pub fn get_drive_alias(&self, mount: &str) -> Option<&String> {
+        self.drive_aliases.as_ref()?.get(mount)
+    }
+
     pub fn set_default_for(&mut self, ext: &str, name: &str) {
         if let Some(ref mut mappings) = self.extension_mappings_default {
             if let Some(value) = mappings.get_mut(ext) {
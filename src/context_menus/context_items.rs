@@ -1,7 +1,10 @@
 use slint::{Image, SharedPixelBuffer};
 
 use crate::{
-    callbacks::context_menu::ContextCallback, keybinds::keybind::format_keybind, ui::ContextItem,
+    callbacks::context_menu::ContextCallback,
+    icon_theme::{self, MENU_ICON_SIZE},
+    keybinds::keybind::format_keybind,
+    ui::ContextItem,
 };
 use std::{cell::UnsafeCell, collections::HashMap, sync::Once};
 
@@ -21,12 +24,38 @@ static CONTEXT_ITEMS: StaticContextItems = StaticContextItems {
     inner: UnsafeCell::new(None),
 };
 
+//Icons of the theme for the items, the images are loaded in the thread showing the menu
+const ICON_NAMES: [(&str, &str); 18] = [
+    ("create_new", "document-new"),
+    ("open_with", "document-open"),
+    ("cut", "edit-cut"),
+    ("copy", "edit-copy"),
+    ("paste_into", "edit-paste"),
+    ("paste_here", "edit-paste"),
+    ("delete", "edit-delete"),
+    ("analyze_disk_usage", "drive-harddisk"),
+    ("open_terminal_here", "utilities-terminal"),
+    ("run_command", "system-run"),
+    ("search", "system-search"),
+    ("reveal_in_folder", "folder-open"),
+    ("properties", "document-properties"),
+    ("remove_place", "list-remove"),
+    ("add_place", "bookmark-new"),
+    ("mount_drive", "drive-harddisk"),
+    ("unmount_drive", "media-eject"),
+    ("power_off_drive", "system-shutdown"),
+];
+
 pub fn get_ci(k: &str) -> ContextItem {
     INIT.call_once(|| {
         init_context_items();
     });
     let map = unsafe { (*CONTEXT_ITEMS.inner.get()).as_ref().unwrap() };
-    map[k].clone()
+    let mut ci = map[k].clone();
+    if let Some((_, icon)) = ICON_NAMES.iter().find(|(key, _)| *key == k) {
+        ci.icon = icon_theme::named_icon(icon, MENU_ICON_SIZE);
+    }
+    ci
 }
 
 pub fn get_ci_capacity() -> usize {
@@ -138,6 +167,138 @@ fn init_context_items() {
             internal_id: 0,
         },
     );
+    map.insert(
+        "analyze_disk_usage",
+        ContextItem {
+            display: "Analyze Disk Usage".into(),
+            callback_id: ContextCallback::AnalyzeDiskUsage as i32,
+            shortcut: format_keybind("analyze_disk_usage").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "view_source",
+        ContextItem {
+            display: "View".into(),
+            callback_id: ContextCallback::ViewSource as i32,
+            shortcut: format_keybind("view_source").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "open_terminal_here",
+        ContextItem {
+            display: "Open Terminal Here".into(),
+            callback_id: ContextCallback::OpenTerminalHere as i32,
+            shortcut: format_keybind("open_terminal_here").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "run_command",
+        ContextItem {
+            display: "Run Command…".into(),
+            callback_id: ContextCallback::RunCommand as i32,
+            shortcut: format_keybind("run_command").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: true,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "search",
+        ContextItem {
+            display: "Search…".into(),
+            callback_id: ContextCallback::Search as i32,
+            shortcut: format_keybind("search").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: true,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "reveal_in_folder",
+        ContextItem {
+            display: "Show in Folder".into(),
+            callback_id: ContextCallback::RevealInFolder as i32,
+            shortcut: "".into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: true,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "view_mode",
+        ContextItem {
+            display: "View".into(),
+            callback_id: ContextCallback::ViewMode as i32,
+            shortcut: "▶".into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: true,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "toggle_hidden",
+        ContextItem {
+            display: "Show Hidden Files".into(),
+            callback_id: ContextCallback::ToggleHidden as i32,
+            shortcut: format_keybind("toggle_hidden").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "toggle_preview",
+        ContextItem {
+            display: "Show Preview".into(),
+            callback_id: ContextCallback::TogglePreview as i32,
+            shortcut: format_keybind("toggle_preview").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "set_view_as_default",
+        ContextItem {
+            display: "Use This View Everywhere".into(),
+            callback_id: ContextCallback::SetViewAsDefault as i32,
+            shortcut: format_keybind("set_view_as_default").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "reset_view",
+        ContextItem {
+            display: "Reset View".into(),
+            callback_id: ContextCallback::ResetView as i32,
+            shortcut: format_keybind("reset_view").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: true,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
     map.insert(
         "properties",
         ContextItem {
@@ -150,6 +311,78 @@ fn init_context_items() {
             internal_id: 0,
         },
     );
+    map.insert(
+        "rename_place",
+        ContextItem {
+            display: "Rename".into(),
+            callback_id: ContextCallback::RenamePlace as i32,
+            shortcut: format_keybind("rename_place").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "remove_place",
+        ContextItem {
+            display: "Remove".into(),
+            callback_id: ContextCallback::RemovePlace as i32,
+            shortcut: format_keybind("remove_place").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "add_place",
+        ContextItem {
+            display: "Add Current Folder".into(),
+            callback_id: ContextCallback::AddPlace as i32,
+            shortcut: format_keybind("add_place").into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "mount_drive",
+        ContextItem {
+            display: "Mount".into(),
+            callback_id: ContextCallback::MountDrive as i32,
+            shortcut: "".into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "unmount_drive",
+        ContextItem {
+            display: "Unmount".into(),
+            callback_id: ContextCallback::UnmountDrive as i32,
+            shortcut: "".into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
+    map.insert(
+        "power_off_drive",
+        ContextItem {
+            display: "Power Off".into(),
+            callback_id: ContextCallback::PowerOffDrive as i32,
+            shortcut: "".into(),
+            icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+            has_separator: false,
+            click_on_hover: false,
+            internal_id: 0,
+        },
+    );
 
     unsafe { *CONTEXT_ITEMS.inner.get() = Some(map) }
 }
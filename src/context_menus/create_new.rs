@@ -3,10 +3,11 @@ use std::path::PathBuf;
 use crate::{
     callbacks::{context_menu::ContextCallback, filemanager::selection::get_selected_path},
     core::{create_file, verify_file},
+    icon_theme::{self, MENU_ICON_SIZE},
     ui::{self, main_window::run_with_main_window, ContextItem, CreateNewAdapter, CreateNewFile},
     utils::center_window_on_another,
 };
-use slint::{ComponentHandle, Image, SharedPixelBuffer};
+use slint::ComponentHandle;
 
 use super::secondary_context_menu::show_secondary_context_menu;
 
@@ -92,7 +93,7 @@ pub fn create_new_hover(parent_index: i32) {
         display: ("File").into(),
         callback_id: ContextCallback::CreateNewFile as i32,
         shortcut: "".into(),
-        icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+        icon: icon_theme::named_icon("text-x-generic", MENU_ICON_SIZE),
         has_separator: false,
         click_on_hover: false,
         internal_id: 0,
@@ -101,7 +102,7 @@ pub fn create_new_hover(parent_index: i32) {
         display: ("Directory").into(),
         callback_id: ContextCallback::CreateNewDirectory as i32,
         shortcut: "".into(),
-        icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+        icon: icon_theme::named_icon("folder", MENU_ICON_SIZE),
         has_separator: false,
         click_on_hover: false,
         internal_id: 0,
@@ -110,7 +111,7 @@ pub fn create_new_hover(parent_index: i32) {
         display: ("Symlink").into(),
         callback_id: ContextCallback::CreateNewLink as i32,
         shortcut: "".into(),
-        icon: Image::from_rgb8(SharedPixelBuffer::new(0, 0)),
+        icon: icon_theme::named_icon("emblem-symbolic-link", MENU_ICON_SIZE),
         has_separator: false,
         click_on_hover: false,
         internal_id: 0,
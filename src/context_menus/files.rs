@@ -4,35 +4,98 @@ use crate::{
         filemanager::selection::{self, selected_files_read},
     },
     clipboard,
-    core::run_command,
-    enclose,
+    command_template::{launch, split_args},
+    config::Mapping,
+    core::run_command_in,
+    desktop_entries::{apps_for_mime, default_app_for_mime_in, load_mimeapps},
+    disk_usage, enclose,
     file_properties::setup_properties,
     globals::config_read,
+    icon_theme::{self, MENU_ICON_SIZE},
     manage_open_with,
+    mime::{self, mime_type_for_file},
     ui::*,
-    utils::center_window_on_another,
+    utils::{
+        center_window_on_another,
+        error_handling::{log_error_str, user_notice},
+    },
 };
 use main_window::run_with_main_window;
 use prop_window::unwrap_prop_window;
 use slint::{ComponentHandle, Image, SharedPixelBuffer};
-use std::{path::PathBuf, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use super::secondary_context_menu::show_secondary_context_menu;
 
-pub fn open_with_default(files: Vec<FileItem>) {
-    let conf = config_read();
-
-    if let Some(default) = conf.get_mapping_default(&files[0].extension) {
-        if let Some(cmd) = conf
-            .get_mappings_quick(&files[0].extension)
+///The user's mapping for each file's extension if there is one,
+///otherwise the default application for its MIME type.
+///The mappings and applications are looked up once per extension and type.
+pub fn get_default_mappings(files: &[FileItem]) -> Vec<Option<Mapping>> {
+    let user_mappings: HashMap<&str, Mapping> = {
+        let conf = config_read();
+        files
             .iter()
-            .find(|m| m.display_name == *default)
-        {
-            for f in files {
-                run_command(&(cmd.command.to_string() + " " + &f.path));
+            .map(|f| f.extension.as_str())
+            .collect::<HashSet<&str>>()
+            .into_iter()
+            .filter_map(|ext| {
+                let default = conf.get_mapping_default(ext)?;
+                let mapping = conf
+                    .get_mappings_quick(ext)
+                    .into_iter()
+                    .find(|m| m.display_name == *default)?;
+                Some((ext, mapping))
+            })
+            .collect()
+    };
+    let mimeapps = load_mimeapps();
+    let mut apps: HashMap<String, Option<Mapping>> = HashMap::new();
+    files
+        .iter()
+        .map(|file| {
+            if let Some(mapping) = user_mappings.get(file.extension.as_str()) {
+                return Some(mapping.clone());
             }
+            apps.entry(mime_type_for_file(file))
+                .or_insert_with_key(|mime| {
+                    default_app_for_mime_in(mime, &mimeapps).map(|app| Mapping {
+                        display_name: app.name.clone(),
+                        command: app.command(),
+                    })
+                })
+                .clone()
+        })
+        .collect()
+}
+
+pub fn open_with_default(files: Vec<FileItem>) {
+    let mut unassociated = Vec::new();
+    //Files opened with the same command are passed together, in order
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    let mappings = get_default_mappings(&files);
+    for (f, mapping) in files.into_iter().zip(mappings) {
+        let Some(mapping) = mapping else {
+            unassociated.push(f.file_name.to_string());
+            continue;
+        };
+        match groups.iter_mut().find(|(cmd, _)| *cmd == mapping.command) {
+            Some((_, paths)) => paths.push(f.path.to_string()),
+            None => groups.push((mapping.command, vec![f.path.to_string()])),
         }
     }
+    for (cmd, paths) in groups {
+        launch(&cmd, &paths);
+    }
+    if !unassociated.is_empty() {
+        user_notice(&format!(
+            "No application is associated with: {}",
+            unassociated.join(", ")
+        ));
+    }
 }
 
 ///Shows a secondary context menu on the right
@@ -77,6 +140,23 @@ pub fn open_with(parent_index: i32) {
             });
         }
     }
+    drop(conf);
+
+    //Applications from the MIME database, if every file has the same type
+    let apps = mime::common_mime_type(&selection::selected_files_clone())
+        .map(|m| apps_for_mime(&m))
+        .unwrap_or_default();
+    for (i, app) in apps.iter().enumerate() {
+        menu.push(ContextItem {
+            display: (&app.name).into(),
+            callback_id: ContextCallback::OpenWithApp as i32,
+            shortcut: "".into(),
+            icon: icon_theme::named_icon(&app.icon, MENU_ICON_SIZE),
+            has_separator: i == apps.len() - 1,
+            click_on_hover: false,
+            internal_id: i as i32,
+        });
+    }
     menu.push(ContextItem {
         display: ("More").into(),
         callback_id: ContextCallback::ManageQuick as i32,
@@ -93,19 +173,40 @@ pub fn open_with(parent_index: i32) {
 ///Runs the command associated with the selected files extension
 ///Only call this if you are certain that every selected files have the same mapping
 pub fn open_with_quick(context_item: &ContextItem) {
-    let files = selected_files_read();
-    let mut cmd = String::from("");
-    let mut cmd_set = false;
-    for (_, file) in files.iter() {
-        if !cmd_set {
-            cmd = config_read().get_mappings_quick(&file.extension)
-                [context_item.internal_id as usize]
-                .command
-                .clone();
-            cmd_set = true;
-        }
-        run_command(&(cmd.to_owned() + " " + &file.path));
-    }
+    let files = selection::selected_files_clone();
+    let Some(first) = files.first() else {
+        return;
+    };
+    let cmd = config_read().get_mappings_quick(&first.extension)[context_item.internal_id as usize]
+        .command
+        .clone();
+    launch(
+        &cmd,
+        &files
+            .iter()
+            .map(|f| f.path.to_string())
+            .collect::<Vec<String>>(),
+    );
+}
+
+///Opens the selected files with one of the applications for their MIME type.
+///The index is the one from the list built in open_with.
+pub fn open_with_app(context_item: &ContextItem) {
+    let files = selection::selected_files_clone();
+    let Some(app) = mime::common_mime_type(&files).and_then(|m| {
+        apps_for_mime(&m)
+            .into_iter()
+            .nth(context_item.internal_id as usize)
+    }) else {
+        return;
+    };
+    launch(
+        &app.command(),
+        &files
+            .iter()
+            .map(|f| f.path.to_string())
+            .collect::<Vec<String>>(),
+    );
 }
 
 ///See clipboard::copy
@@ -198,9 +299,39 @@ pub fn manage_quick() {
         adp.on_add_mapping(
             enclose! { (rc) move |mapping| manage_open_with::add_mapping(rc.clone(), mapping)},
         );
+        adp.on_set_system_default(
+            enclose! { (rc) move |id| manage_open_with::set_system_default(rc.clone(), id)},
+        );
+        adp.on_check_conflict(
+            enclose! { (rc) move || manage_open_with::check_conflict(&rc.unwrap().global::<ManageOpenWithAdapter>())},
+        );
 
         manage_open_with::setup_manage_open_with(adp, files_rc);
 
         win.show().unwrap();
     });
 }
+
+///Launches the configured terminal in the current tab's directory
+pub fn open_terminal_here() {
+    run_with_main_window(|mw| {
+        let cwd = mw
+            .global::<TabsAdapter>()
+            .invoke_get_current_tab()
+            .internal_path;
+        let Some(term) = config_read().get::<String>("terminal") else {
+            log_error_str("No valid terminal. Fix your config.");
+            return;
+        };
+        if let Err(e) = run_command_in(&split_args(&term), Path::new(cwd.as_str())) {
+            log_error_str(&format!("Could not open the terminal: {}", e));
+        }
+    });
+}
+
+///Analyzes the selected directory, or the current one if nothing is selected
+pub fn analyze_disk_usage() {
+    if let Some(path) = selection::get_selected_path() {
+        disk_usage::analyze(path);
+    }
+}
@@ -1,5 +1,5 @@
-use magic::cookie::{DatabasePaths, Flags};
-use syscalls::syscall0;
+use magic::cookie::{Cookie, DatabasePaths, Flags, Load};
+use syscalls::{syscall0, syscall4, Errno};
 
 use crate::{
     ui::*,
@@ -12,8 +12,10 @@ use std::{
     collections::HashMap,
     ffi::OsStr,
     fs::{self, File, Metadata},
-    path::PathBuf,
+    os::unix::{ffi::OsStrExt, fs::MetadataExt, process::CommandExt},
+    path::{Path, PathBuf},
     process::Command,
+    thread,
     time::SystemTime,
 };
 
@@ -27,48 +29,8 @@ pub fn generate_files_for_path(path: &str) -> Vec<FileItem> {
         .map(|file| {
             if let Ok(f) = file {
                 if let Ok(meta) = std::fs::metadata(f.path()) {
-                    let (size_a, size_b) = if meta.is_dir() {
-                        (0, 0) //So that directories don't get sorted by size
-                    } else {
-                        i64_to_i32(meta.len() as i64)
-                    };
-                    let (date_a, date_b);
-                    if let Ok(modified) = meta.modified() {
-                        if let Ok(modified_dr) = modified.duration_since(SystemTime::UNIX_EPOCH) {
-                            (date_a, date_b) = i64_to_i32(modified_dr.as_secs() as i64);
-                        } else {
-                            return bad_file();
-                        }
-                    } else {
-                        return bad_file();
-                    }
-                    FileItem {
-                        path: f.path().to_str().unwrap().into(),
-                        file_name: f.file_name().to_str().unwrap().into(),
-                        is_dir: meta.is_dir(),
-                        size: _i64 {
-                            a: size_a,
-                            b: size_b,
-                        },
-                        date: _i64 {
-                            a: date_a,
-                            b: date_b,
-                        },
-                        file_type: f
-                            .path()
-                            .extension()
-                            .and_then(OsStr::to_str)
-                            .unwrap_or_else(|| "N/A")
-                            .into(),
-                        is_link: f.file_type().unwrap().is_symlink(),
-                        extension: f
-                            .path()
-                            .extension()
-                            .and_then(OsStr::to_str)
-                            .unwrap_or("")
-                            .into(),
-                        selected: false,
-                    }
+                    let is_link = f.file_type().is_ok_and(|t| t.is_symlink());
+                    file_item(&f.path(), &meta, is_link)
                 } else {
                     bad_file()
                 }
@@ -79,6 +41,79 @@ pub fn generate_files_for_path(path: &str) -> Vec<FileItem> {
         .collect::<Vec<FileItem>>()
 }
 
+///The item of the file at path, meta is its metadata with the links followed
+pub fn file_item(path: &Path, meta: &Metadata, is_link: bool) -> FileItem {
+    let (size_a, size_b) = if meta.is_dir() {
+        (0, 0) //So that directories don't get sorted by size
+    } else {
+        i64_to_i32(meta.len() as i64)
+    };
+    let (date_a, date_b);
+    if let Ok(modified) = meta.modified() {
+        if let Ok(modified_dr) = modified.duration_since(SystemTime::UNIX_EPOCH) {
+            (date_a, date_b) = i64_to_i32(modified_dr.as_secs() as i64);
+        } else {
+            return bad_file();
+        }
+    } else {
+        return bad_file();
+    }
+    let (Some(path_str), Some(file_name)) =
+        (path.to_str(), path.file_name().and_then(OsStr::to_str))
+    else {
+        return bad_file();
+    };
+    FileItem {
+        path: path_str.into(),
+        file_name: file_name.into(),
+        is_dir: meta.is_dir(),
+        size: _i64 {
+            a: size_a,
+            b: size_b,
+        },
+        date: _i64 {
+            a: date_a,
+            b: date_b,
+        },
+        file_type: path
+            .extension()
+            .and_then(OsStr::to_str)
+            .unwrap_or("N/A")
+            .into(),
+        is_link,
+        extension: path
+            .extension()
+            .and_then(OsStr::to_str)
+            .unwrap_or("")
+            .into(),
+        selected: false,
+        mode: meta.mode() as i32,
+        uid: meta.uid() as i32,
+        gid: meta.gid() as i32,
+        created: system_time_to_i64(meta.created()),
+        accessed: system_time_to_i64(meta.accessed()),
+        link_target: if is_link {
+            fs::read_link(path)
+                .map(|t| t.to_string_lossy().into_owned())
+                .unwrap_or_default()
+                .into()
+        } else {
+            "".into()
+        },
+        mime_type: "".into(),
+    }
+}
+
+//Times that can't be read (eg. birth time on some filesystems) are 0
+fn system_time_to_i64(time: std::io::Result<SystemTime>) -> _i64 {
+    let (a, b) = time
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| i64_to_i32(d.as_secs() as i64))
+        .unwrap_or((0, 0));
+    _i64 { a, b }
+}
+
 /*
  *      Generates a map of <uid, name> from /etc/passwd
  * */
@@ -173,6 +208,19 @@ pub fn get_file_encoding(path: &str) -> String {
     return result.unwrap();
 }
 
+thread_local! {
+    //Loading the database is slow, each thread keeps its cookie (they can't be shared)
+    static MIME_COOKIE: Option<Cookie<Load>> =
+        Cookie::open(Flags::ERROR | Flags::MIME_TYPE | Flags::PRESERVE_ATIME)
+            .ok()
+            .and_then(|c| c.load(&DatabasePaths::default()).ok());
+}
+
+///MIME type of the file's content (eg. "text/plain"), None if libmagic fails
+pub fn get_file_mime_type(path: &str) -> Option<String> {
+    MIME_COOKIE.with(|cookie| cookie.as_ref()?.file(path).ok())
+}
+
 pub fn get_file_metadata(path: &str) -> Result<Metadata, std::io::Error> {
     std::fs::metadata(path)
 }
@@ -188,6 +236,13 @@ pub fn bad_file() -> FileItem {
         is_link: false,
         extension: "".into(),
         selected: false,
+        mode: 0,
+        uid: 0,
+        gid: 0,
+        created: _i64 { a: 0, b: 0 },
+        accessed: _i64 { a: 0, b: 0 },
+        link_target: "".into(),
+        mime_type: "".into(),
     }
 }
 pub fn empty_file() -> FileItem {
@@ -201,6 +256,13 @@ pub fn empty_file() -> FileItem {
         is_link: false,
         extension: "".into(),
         selected: false,
+        mode: 0,
+        uid: 0,
+        gid: 0,
+        created: _i64 { a: 0, b: 0 },
+        accessed: _i64 { a: 0, b: 0 },
+        link_target: "".into(),
+        mime_type: "".into(),
     }
 }
 pub fn empty_file_with_path(path: &str) -> FileItem {
@@ -214,13 +276,45 @@ pub fn empty_file_with_path(path: &str) -> FileItem {
         is_link: false,
         extension: "".into(),
         selected: false,
+        mode: 0,
+        uid: 0,
+        gid: 0,
+        created: _i64 { a: 0, b: 0 },
+        accessed: _i64 { a: 0, b: 0 },
+        link_target: "".into(),
+        mime_type: "".into(),
     }
 }
-pub fn run_command(command: &str) {
-    Command::new("setsid")
-        .args(command.split(" ").collect::<Vec<&str>>())
-        .spawn()
-        .expect("failed to execute process");
+///Spawns the program (args[0]) in its own session, so it keeps running after qdfm exits.
+///The child is waited for in the background so it doesn't stay a zombie.
+pub fn run_command(args: &[String]) -> Result<(), std::io::Error> {
+    spawn_detached(args, None)
+}
+
+///Same as run_command, with dir as the working directory
+pub fn run_command_in(args: &[String], dir: &Path) -> Result<(), std::io::Error> {
+    spawn_detached(args, Some(dir))
+}
+
+fn spawn_detached(args: &[String], dir: Option<&Path>) -> Result<(), std::io::Error> {
+    let (program, args) = args
+        .split_first()
+        .ok_or(std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    let mut command = Command::new(program);
+    command.args(args);
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    unsafe {
+        command.pre_exec(|| {
+            syscall0(syscalls::Sysno::setsid)
+                .map(|_| ())
+                .map_err(|e| std::io::Error::from_raw_os_error(e.into_raw()))
+        });
+    }
+    let mut child = command.spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
 }
 
 ///Simple utility to verify the state of a file and return a suitable warning or error
@@ -256,3 +350,34 @@ pub fn create_file(path: PathBuf) {
         log_error_str(&format!("Could not create the file: {}", e));
     }
 }
+
+//Sets the access and/or modification time (seconds since UNIX_EPOCH) via utimensat.
+//None leaves that time as is.
+pub fn set_file_times(
+    path: &Path,
+    atime: Option<i64>,
+    mtime: Option<i64>,
+    follow_links: bool,
+) -> Result<(), Errno> {
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(|_| Errno::EINVAL)?;
+    //libc's timespec has the size of time_t and long of the target
+    let timespec = |time: Option<i64>| libc::timespec {
+        tv_sec: time.unwrap_or(0) as libc::time_t,
+        tv_nsec: if time.is_some() { 0 } else { libc::UTIME_OMIT },
+    };
+    let times = [timespec(atime), timespec(mtime)];
+    unsafe {
+        syscall4(
+            syscalls::Sysno::utimensat,
+            libc::AT_FDCWD as usize,
+            c_path.as_ptr() as usize,
+            times.as_ptr() as usize,
+            if follow_links {
+                0
+            } else {
+                libc::AT_SYMLINK_NOFOLLOW as usize
+            },
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use slint::{Image, SharedPixelBuffer};
+
+use crate::{
+    callbacks::{context_menu::ContextCallback, filemanager::selection},
+    command_template::launch,
+    config::CustomAction,
+    globals::config_read,
+    keybinds::keybind::format_keybind,
+    mime::{glob_match, mime_type_for_file},
+    ui::*,
+    utils::error_handling::{log_error_str, user_notice},
+};
+
+/*
+ *  User defined actions from the configuration ("custom_actions").
+ *  They are shown in the context menu when the selection matches,
+ *  and can be bound to keys with "action:<name>".
+ * */
+
+pub const KEYBIND_PREFIX: &str = "action:";
+
+///True if the action can run on these files
+pub fn action_matches(action: &CustomAction, files: &[FileItem]) -> bool {
+    if files.len() < action.min_selection || action.max_selection.is_some_and(|m| files.len() > m) {
+        return false;
+    }
+    if action.extensions.is_empty() && action.mime_types.is_empty() {
+        return true;
+    }
+    files.iter().all(|f| {
+        let ext_matches = !f.is_dir
+            && action
+                .extensions
+                .iter()
+                .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(&f.extension));
+        ext_matches || {
+            let mime = mime_type_for_file(f);
+            action.mime_types.iter().any(|m| glob_match(m, &mime))
+        }
+    })
+}
+
+///Context items for the actions matching the selection, internal_id is the index in the config
+pub fn get_context_items(files: &[FileItem]) -> Vec<ContextItem> {
+    let actions: Vec<(usize, CustomAction)> = config_read()
+        .get_custom_actions()
+        .iter()
+        .cloned()
+        .enumerate()
+        .filter(|(_, a)| action_matches(a, files))
+        .collect();
+    let count = actions.len();
+    actions
+        .into_iter()
+        .enumerate()
+        .map(|(i, (index, action))| ContextItem {
+            display: action.name.clone().into(),
+            callback_id: ContextCallback::CustomAction as i32,
+            shortcut: format_keybind(&(KEYBIND_PREFIX.to_owned() + &action.name)).into(),
+            icon: if action.icon.is_empty() {
+                Image::from_rgb8(SharedPixelBuffer::new(0, 0))
+            } else {
+                Image::load_from_path(action.icon.as_ref())
+                    .unwrap_or_else(|_| Image::from_rgb8(SharedPixelBuffer::new(0, 0)))
+            },
+            has_separator: i == count - 1,
+            click_on_hover: false,
+            internal_id: index as i32,
+        })
+        .collect()
+}
+
+fn run_action(action: &CustomAction, files: &[FileItem]) {
+    let mut cmd = action.command.clone();
+    if action.terminal {
+        let Some(term) = config_read().get::<String>("terminal") else {
+            log_error_str("No valid terminal. Fix your config.");
+            return;
+        };
+        cmd = term + " " + &cmd;
+    }
+    launch(
+        &cmd,
+        &files
+            .iter()
+            .map(|f| f.path.to_string())
+            .collect::<Vec<String>>(),
+    );
+}
+
+///Runs the action at index in the configuration on the selected files
+pub fn run_action_index(index: usize) {
+    let action = config_read().get_custom_actions().get(index).cloned();
+    if let Some(action) = action {
+        run_action(&action, &selection::selected_files_clone());
+    }
+}
+
+///Called by the keybinds, function is "action:<name>"
+pub fn run_action_keybind(function: &str) {
+    let name = function.trim_start_matches(KEYBIND_PREFIX);
+    let action = config_read()
+        .get_custom_actions()
+        .iter()
+        .find(|a| a.name == name)
+        .cloned();
+    let Some(action) = action else {
+        log_error_str(&format!(
+            "Invalid function for keybind! There is no custom action named '{}'",
+            name
+        ));
+        return;
+    };
+    let files = selection::selected_files_clone();
+    if action_matches(&action, &files) {
+        run_action(&action, &files);
+    } else {
+        user_notice(&format!(
+            "'{}' can't run on the selected files.",
+            action.name
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{globals::config_read, mime::mime_db, utils::xdg};
+
+/*
+ *  Installed applications (https://specifications.freedesktop.org/desktop-entry-spec/latest/)
+ *  and their associations with MIME types (https://specifications.freedesktop.org/mime-apps-spec/latest/)
+ * */
+
+#[derive(Clone, Default)]
+pub struct DesktopEntry {
+    ///File name relative to the applications directory, with / replaced by -
+    pub id: String,
+    pub name: String,
+    pub exec: String,
+    pub icon: String,
+    pub terminal: bool,
+    pub mime_types: Vec<String>,
+}
+
+impl DesktopEntry {
+    ///The Exec key as a command template (see command_template).
+    ///Programs that run in a terminal are wrapped with the configured terminal.
+    pub fn command(&self) -> String {
+        if self.terminal {
+            if let Some(term) = config_read().get::<String>("terminal") {
+                return term + " " + &self.exec;
+            }
+        }
+        self.exec.clone()
+    }
+}
+
+///Unescapes a string value (\s, \n, \t, \r and \\)
+pub fn unescape_value(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => ret.push(' '),
+            Some('n') => ret.push('\n'),
+            Some('t') => ret.push('\t'),
+            Some('r') => ret.push('\r'),
+            Some(c) => ret.push(c),
+            None => ret.push('\\'),
+        }
+    }
+    ret
+}
+
+///Parses the [Desktop Entry] group. Returns None for anything that can't be launched.
+///Entries with NoDisplay are kept since they are still meant to open files.
+pub fn parse_desktop_entry(id: &str, content: &str) -> Option<DesktopEntry> {
+    let mut entry = DesktopEntry {
+        id: id.into(),
+        ..Default::default()
+    };
+    let mut in_group = false;
+    let mut is_application = false;
+    for line in content.lines().map(|l| l.trim()) {
+        if line.starts_with('[') {
+            in_group = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_group || line.starts_with('#') {
+            continue;
+        }
+        //Localized keys (Name[fr]) are skipped
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = unescape_value(value.trim());
+        match key.trim() {
+            "Type" => is_application = value == "Application",
+            "Name" => entry.name = value,
+            "Exec" => entry.exec = value,
+            "Icon" => entry.icon = value,
+            "Terminal" => entry.terminal = value == "true",
+            "MimeType" => {
+                entry.mime_types = value
+                    .split(';')
+                    .filter(|m| !m.is_empty())
+                    .map(String::from)
+                    .collect()
+            }
+            "Hidden" if value == "true" => return None,
+            _ => (),
+        }
+    }
+    if is_application && !entry.exec.is_empty() && !entry.name.is_empty() {
+        Some(entry)
+    } else {
+        None
+    }
+}
+
+fn scan_applications_dir(
+    dir: &Path,
+    prefix: &str,
+    entries: &mut Vec<DesktopEntry>,
+    seen: &mut HashSet<String>,
+) {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for f in read_dir.flatten() {
+        let name = f.file_name().to_string_lossy().to_string();
+        let path = f.path();
+        if path.is_dir() {
+            scan_applications_dir(&path, &(prefix.to_owned() + &name + "-"), entries, seen);
+        } else if name.ends_with(".desktop") {
+            let id = prefix.to_owned() + &name;
+            //A more important directory already provided this id, even if it is hidden there
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            if let Some(entry) = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| parse_desktop_entry(&id, &content))
+            {
+                entries.push(entry);
+            }
+        }
+    }
+}
+
+///All installed applications, read once
+pub fn desktop_entries() -> &'static Vec<DesktopEntry> {
+    static ENTRIES: OnceLock<Vec<DesktopEntry>> = OnceLock::new();
+    ENTRIES.get_or_init(|| {
+        let mut entries = Vec::new();
+        let mut seen = HashSet::new();
+        for dir in xdg::all_data_dirs() {
+            scan_applications_dir(&dir.join("applications"), "", &mut entries, &mut seen);
+        }
+        entries.sort_by_key(|e| e.name.to_lowercase());
+        entries
+    })
+}
+
+pub fn find_desktop_entry(id: &str) -> Option<&'static DesktopEntry> {
+    desktop_entries().iter().find(|e| e.id == id)
+}
+
+/*
+ *  mimeapps.list
+ * */
+
+///Desktop entry ids per MIME type, merged from every mimeapps.list, most important first
+#[derive(Default)]
+pub struct MimeApps {
+    pub defaults: HashMap<String, Vec<String>>,
+    pub added: HashMap<String, Vec<String>>,
+    pub removed: HashMap<String, Vec<String>>,
+}
+
+impl MimeApps {
+    fn is_removed(&self, mime: &str, id: &str) -> bool {
+        self.removed
+            .get(mime)
+            .is_some_and(|ids| ids.iter().any(|i| i == id))
+    }
+}
+
+///mimeapps.list files, most important first
+pub fn mimeapps_paths() -> Vec<PathBuf> {
+    let mut paths = vec![xdg::config_home().join("mimeapps.list")];
+    paths.extend(xdg::config_dirs().iter().map(|d| d.join("mimeapps.list")));
+    paths.extend(
+        xdg::all_data_dirs()
+            .iter()
+            .map(|d| d.join("applications").join("mimeapps.list")),
+    );
+    paths
+}
+
+///Adds the content of a mimeapps.list to apps. Files must be parsed most important first.
+pub fn parse_mimeapps(content: &str, apps: &mut MimeApps) {
+    let mut section = None;
+    for line in content.lines().map(|l| l.trim()) {
+        if line.starts_with('[') {
+            section = match line {
+                "[Default Applications]" => Some(&mut apps.defaults),
+                "[Added Associations]" => Some(&mut apps.added),
+                "[Removed Associations]" => Some(&mut apps.removed),
+                _ => None,
+            };
+            continue;
+        }
+        let Some(ref mut map) = section else {
+            continue;
+        };
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some((mime, ids)) = line.split_once('=') {
+            let list = map.entry(mime.trim().to_string()).or_default();
+            for id in ids.split(';').map(|i| i.trim()).filter(|i| !i.is_empty()) {
+                if !list.iter().any(|i| i == id) {
+                    list.push(id.to_string());
+                }
+            }
+        }
+    }
+}
+
+///Read every time since the files can be changed by other programs
+pub fn load_mimeapps() -> MimeApps {
+    let mut apps = MimeApps::default();
+    for path in mimeapps_paths() {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            parse_mimeapps(&content, &mut apps);
+        }
+    }
+    apps
+}
+
+///Applications that can open the MIME type, the default one first.
+///For the type and then each of its parents: the defaults, the added associations,
+///then every application listing the type.
+pub fn apps_for_mime(mime: &str) -> Vec<DesktopEntry> {
+    apps_for_mime_in(mime, &load_mimeapps())
+}
+
+///apps_for_mime with the mimeapps.list files already read, when looking up several types
+pub fn apps_for_mime_in(mime: &str, mimeapps: &MimeApps) -> Vec<DesktopEntry> {
+    let mut ret: Vec<DesktopEntry> = Vec::new();
+    for m in mime_db().with_parents(mime) {
+        let listed = mimeapps
+            .defaults
+            .get(&m)
+            .into_iter()
+            .chain(mimeapps.added.get(&m))
+            .flatten()
+            .filter_map(|id| find_desktop_entry(id));
+        let supporting = desktop_entries()
+            .iter()
+            .filter(|e| e.mime_types.iter().any(|t| mime_db().unalias(t) == m));
+        for entry in listed.chain(supporting) {
+            if !mimeapps.is_removed(&m, &entry.id) && !ret.iter().any(|e| e.id == entry.id) {
+                ret.push(entry.clone());
+            }
+        }
+    }
+    ret
+}
+
+pub fn default_app_for_mime_in(mime: &str, mimeapps: &MimeApps) -> Option<DesktopEntry> {
+    apps_for_mime_in(mime, mimeapps).into_iter().next()
+}
+
+///Makes id the first default of mime in the content of a mimeapps.list.
+///Everything else is kept as is, and the association is no longer removed if it was.
+pub fn set_default_in_mimeapps(content: &str, mime: &str, id: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let section_of = |lines: &[String], name: &str| -> Option<(usize, usize)> {
+        let start = lines.iter().position(|l| l.trim() == name)?;
+        let end = lines[start + 1..]
+            .iter()
+            .position(|l| l.trim().starts_with('['))
+            .map_or(lines.len(), |i| start + 1 + i);
+        Some((start, end))
+    };
+    let key_line = |lines: &[String], (start, end): (usize, usize)| {
+        (start + 1..end).find(|i| {
+            lines[*i]
+                .split_once('=')
+                .is_some_and(|(k, _)| k.trim() == mime)
+        })
+    };
+    let ids_of = |line: &str| -> Vec<String> {
+        line.split_once('=')
+            .map(|(_, ids)| ids)
+            .unwrap_or("")
+            .split(';')
+            .map(|i| i.trim().to_string())
+            .filter(|i| !i.is_empty())
+            .collect()
+    };
+
+    if let Some(section) = section_of(&lines, "[Removed Associations]") {
+        if let Some(i) = key_line(&lines, section) {
+            let ids: Vec<String> = ids_of(&lines[i]).into_iter().filter(|i| i != id).collect();
+            if ids.is_empty() {
+                lines.remove(i);
+            } else {
+                lines[i] = format!("{}={};", mime, ids.join(";"));
+            }
+        }
+    }
+
+    match section_of(&lines, "[Default Applications]") {
+        Some(section) => match key_line(&lines, section) {
+            Some(i) => {
+                let mut ids = vec![id.to_string()];
+                ids.extend(ids_of(&lines[i]).into_iter().filter(|i| i != id));
+                lines[i] = format!("{}={};", mime, ids.join(";"));
+            }
+            None => lines.insert(section.0 + 1, format!("{}={};", mime, id)),
+        },
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push("[Default Applications]".into());
+            lines.push(format!("{}={};", mime, id));
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+///Sets the user's default application for the MIME type in ~/.config/mimeapps.list,
+///which other XDG aware programs use too.
+pub fn set_default_app(mime: &str, id: &str) -> Result<(), std::io::Error> {
+    let path = xdg::config_home().join("mimeapps.list");
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    //Written next to it then renamed so that readers never see a partial file.
+    //A symlinked mimeapps.list (eg. from a dotfiles repository) is kept, its target is replaced.
+    let path = path.canonicalize().unwrap_or(path);
+    let tmp = path.with_extension("list.qdfm-tmp");
+    std::fs::write(&tmp, set_default_in_mimeapps(&content, mime, id))?;
+    std::fs::rename(&tmp, &path)
+}
+
+///The user's default application for the MIME type from the mimeapps.list files only
+pub fn mimeapps_default(mime: &str) -> Option<&'static DesktopEntry> {
+    let mimeapps = load_mimeapps();
+    mimeapps
+        .defaults
+        .get(&mime_db().unalias(mime))?
+        .iter()
+        .find_map(|id| find_desktop_entry(id))
+}
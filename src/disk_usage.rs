@@ -0,0 +1,586 @@
+use crate::{
+    callbacks::tabs,
+    clipboard::{delete::delete_files_sync, trash::trash_file},
+    context_menus::files::open_with_default,
+    core::empty_file_with_path,
+    drives::get_drives,
+    ui::{self, disk_usage_window::run_with_disk_usage_window, *},
+    utils::{
+        error_handling::{log_error_str, user_notice},
+        types::{format_size, i64_to_i32},
+    },
+};
+use slint::{ComponentHandle, Model, VecModel};
+use std::{
+    fs::{read_dir, symlink_metadata, Metadata},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, sleep},
+    time::Duration,
+};
+
+/*
+ *  Disk usage analyzer
+ *
+ *  A directory (or a drive) is scanned into a tree of UsageNode, with the top level entries
+ *  split between threads. The window then shows the children of the current node both as a
+ *  treemap and as a list, and lets the user drill down.
+ * */
+
+///How many entries are drawn in the treemap, the rest are too small to see anyway
+const TREEMAP_MAX_ENTRIES: usize = 200;
+
+pub struct UsageNode {
+    pub name: String,
+    pub size: u64, //On disk, including the children
+    pub is_dir: bool,
+    pub children: Vec<UsageNode>, //Sorted by size, biggest first
+}
+
+struct Analyzer {
+    root_path: PathBuf,
+    root: UsageNode,
+    //Indices in the children from the root to the node currently shown
+    current: Vec<usize>,
+}
+
+impl Analyzer {
+    fn current_node(&self) -> &UsageNode {
+        self.current
+            .iter()
+            .fold(&self.root, |node, i| &node.children[*i])
+    }
+    fn current_path(&self) -> PathBuf {
+        let mut path = self.root_path.clone();
+        let mut node = &self.root;
+        for i in &self.current {
+            node = &node.children[*i];
+            path.push(&node.name);
+        }
+        path
+    }
+    ///Replaces the node at the path by its new scan, or removes it when it no longer exists.
+    ///The sizes of its ancestors follow, and the current node stays the same.
+    fn replace(&mut self, path: &Path, scanned: Option<UsageNode>) {
+        fn replace_at(node: &mut UsageNode, names: &[String], scanned: Option<UsageNode>) {
+            let Some((name, rest)) = names.split_first() else {
+                return;
+            };
+            let Some(i) = node.children.iter().position(|c| c.name == *name) else {
+                return;
+            };
+            let before = node.children[i].size;
+            match (rest.is_empty(), scanned) {
+                (true, Some(scanned)) => node.children[i] = scanned,
+                (true, None) => {
+                    node.children.remove(i);
+                }
+                (false, scanned) => replace_at(&mut node.children[i], rest, scanned),
+            }
+            let after = node
+                .children
+                .get(i)
+                .filter(|c| c.name == *name)
+                .map_or(0, |c| c.size);
+            node.size = node.size.saturating_sub(before) + after;
+            node.children.sort_unstable_by(|a, b| b.size.cmp(&a.size));
+        }
+        let Ok(relative) = path.strip_prefix(&self.root_path) else {
+            return;
+        };
+        let names: Vec<String> = relative
+            .iter()
+            .map(|n| n.to_string_lossy().to_string())
+            .collect();
+        //The indices of the current node change with the sort
+        let mut node = &self.root;
+        let current: Vec<String> = self
+            .current
+            .iter()
+            .map(|i| {
+                node = &node.children[*i];
+                node.name.clone()
+            })
+            .collect();
+        replace_at(&mut self.root, &names, scanned);
+        self.current.clear();
+        let mut node = &self.root;
+        for name in current {
+            match node.children.iter().position(|c| c.name == name) {
+                Some(i) => {
+                    self.current.push(i);
+                    node = &node.children[i];
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+static ANALYZER: Mutex<Option<Analyzer>> = Mutex::new(None);
+//Incremented on every scan so an older one can notice it's been replaced
+static SCAN_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+struct ScanProgress {
+    files: AtomicU64,
+    bytes: AtomicU64,
+    generation: usize,
+}
+
+impl ScanProgress {
+    fn canceled(&self) -> bool {
+        SCAN_GENERATION.load(Ordering::SeqCst) != self.generation
+    }
+}
+
+fn disk_size(meta: &Metadata) -> u64 {
+    meta.blocks() * 512 //st_blocks is always in 512B units
+}
+
+///Recursively scans a directory. Other filesystems are not entered.
+fn scan_node(path: &Path, name: String, meta: &Metadata, progress: &ScanProgress) -> UsageNode {
+    let mut node = UsageNode {
+        name,
+        size: disk_size(meta),
+        is_dir: meta.is_dir(),
+        children: Vec::new(),
+    };
+    progress.files.fetch_add(1, Ordering::Relaxed);
+    progress.bytes.fetch_add(node.size, Ordering::Relaxed);
+
+    if node.is_dir && !progress.canceled() {
+        if let Ok(dir) = read_dir(path) {
+            for entry in dir.flatten() {
+                let child_path = entry.path();
+                if let Ok(child_meta) = symlink_metadata(&child_path) {
+                    if child_meta.dev() != meta.dev() {
+                        continue;
+                    }
+                    let child = scan_node(
+                        &child_path,
+                        entry.file_name().to_string_lossy().to_string(),
+                        &child_meta,
+                        progress,
+                    );
+                    node.size += child.size;
+                    node.children.push(child);
+                }
+            }
+        }
+        node.children.sort_unstable_by(|a, b| b.size.cmp(&a.size));
+    }
+    node
+}
+
+///Scans the root's entries in parallel, each thread taking the next entry until none are left.
+fn scan_parallel(root: &Path, progress: &ScanProgress) -> Option<UsageNode> {
+    let root_meta = symlink_metadata(root).ok()?;
+    let entries: Vec<PathBuf> = read_dir(root).ok()?.flatten().map(|e| e.path()).collect();
+    let next = AtomicUsize::new(0);
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+
+    let mut children: Vec<UsageNode> = thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut nodes = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::SeqCst);
+                        let Some(path) = entries.get(i) else {
+                            break;
+                        };
+                        if let Ok(meta) = symlink_metadata(path) {
+                            if meta.dev() == root_meta.dev() {
+                                let name = path
+                                    .file_name()
+                                    .map(|n| n.to_string_lossy().to_string())
+                                    .unwrap_or_default();
+                                nodes.push(scan_node(path, name, &meta, progress));
+                            }
+                        }
+                    }
+                    nodes
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_default())
+            .collect()
+    });
+    children.sort_unstable_by(|a, b| b.size.cmp(&a.size));
+    Some(UsageNode {
+        name: root.to_string_lossy().to_string(),
+        size: disk_size(&root_meta) + children.iter().map(|c| c.size).sum::<u64>(),
+        is_dir: true,
+        children,
+    })
+}
+
+/*
+ *  Squarified treemap (Bruls, Huizing, van Wijk)
+ * */
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreemapCell {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+///Worst aspect ratio of a row of areas laid along a side of length side
+fn worst_ratio(max: f64, min: f64, sum: f64, side: f64) -> f64 {
+    let side2 = side * side;
+    let sum2 = sum * sum;
+    (side2 * max / sum2).max(sum2 / (side2 * min))
+}
+
+///Lays out the sizes (sorted biggest first) in the w*h rectangle.
+///Returns one cell per size, in the same order. Zero sizes get empty cells.
+pub fn squarify(sizes: &[f64], w: f64, h: f64) -> Vec<TreemapCell> {
+    let total: f64 = sizes.iter().sum();
+    if total <= 0.0 || w <= 0.0 || h <= 0.0 {
+        return vec![
+            TreemapCell {
+                x: 0.0,
+                y: 0.0,
+                w: 0.0,
+                h: 0.0
+            };
+            sizes.len()
+        ];
+    }
+    let scale = w * h / total;
+    let areas: Vec<f64> = sizes.iter().map(|s| s * scale).collect();
+    let mut cells = Vec::with_capacity(areas.len());
+    let (mut x, mut y, mut w, mut h) = (0.0, 0.0, w, h);
+    let mut i = 0;
+    while i < areas.len() {
+        let side = w.min(h);
+        let mut end = i + 1;
+        let mut sum = areas[i];
+        if areas[i] <= 0.0 {
+            cells.push(TreemapCell {
+                x,
+                y,
+                w: 0.0,
+                h: 0.0,
+            });
+            i += 1;
+            continue;
+        }
+        //Grow the row while it improves the aspect ratio
+        let mut worst = worst_ratio(areas[i], areas[i], sum, side);
+        while end < areas.len() && areas[end] > 0.0 {
+            let new_worst = worst_ratio(areas[i], areas[end], sum + areas[end], side);
+            if new_worst > worst {
+                break;
+            }
+            worst = new_worst;
+            sum += areas[end];
+            end += 1;
+        }
+        let thickness = sum / side;
+        let mut offset = 0.0;
+        for area in &areas[i..end] {
+            let length = area / thickness;
+            if w >= h {
+                cells.push(TreemapCell {
+                    x,
+                    y: y + offset,
+                    w: thickness,
+                    h: length,
+                });
+            } else {
+                cells.push(TreemapCell {
+                    x: x + offset,
+                    y,
+                    w: length,
+                    h: thickness,
+                });
+            }
+            offset += length;
+        }
+        if w >= h {
+            x += thickness;
+            w -= thickness;
+        } else {
+            y += thickness;
+            h -= thickness;
+        }
+        i = end;
+    }
+    cells
+}
+
+/*
+ *  UI
+ * */
+
+///Opens the analyzer window and scans the given directory
+pub fn analyze(path: String) {
+    run_with_disk_usage_window(move |w| {
+        w.show().ok();
+        start_scan(PathBuf::from(path));
+    });
+    //Listing the drives asks UDisks2, they are added once known
+    thread::spawn(|| {
+        let drives = get_drives();
+        run_with_disk_usage_window(move |w| {
+            w.global::<DiskUsageAdapter>()
+                .set_drives(Rc::new(VecModel::from(drives)).into());
+        });
+    });
+}
+
+pub fn start_scan(root: PathBuf) {
+    let generation = SCAN_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let progress = Arc::new(ScanProgress {
+        files: AtomicU64::new(0),
+        bytes: AtomicU64::new(0),
+        generation,
+    });
+    let done = Arc::new(AtomicBool::new(false));
+    let root_str = root.to_string_lossy().to_string();
+    run_with_disk_usage_window(move |w| {
+        let adp = w.global::<DiskUsageAdapter>();
+        adp.set_scanning(true);
+        adp.set_current_path(root_str.into());
+        adp.set_selected(-1);
+        adp.set_can_go_up(false);
+        adp.set_entries(Rc::new(VecModel::<UsageEntry>::default()).into());
+        adp.set_cells(Rc::new(VecModel::<UsageCell>::default()).into());
+    });
+
+    //UI updates
+    thread::spawn({
+        let progress = progress.clone();
+        let done = done.clone();
+        move || {
+            let delay = Duration::from_millis(500); //UI update frequency
+            while !done.load(Ordering::SeqCst) && !progress.canceled() {
+                let status = format!(
+                    "Scanning... {} files, {}",
+                    progress.files.load(Ordering::Relaxed),
+                    format_size(progress.bytes.load(Ordering::Relaxed), false)
+                );
+                run_with_disk_usage_window(move |w| {
+                    w.global::<DiskUsageAdapter>().set_status(status.into());
+                });
+                sleep(delay);
+            }
+        }
+    });
+
+    thread::spawn(move || {
+        let node = scan_parallel(&root, &progress);
+        done.store(true, Ordering::SeqCst);
+        if progress.canceled() {
+            return;
+        }
+        let status = match node {
+            Some(node) => {
+                let status = format!(
+                    "{} files, {} on disk",
+                    progress.files.load(Ordering::Relaxed),
+                    format_size(node.size, false)
+                );
+                if let Ok(mut analyzer) = ANALYZER.lock() {
+                    *analyzer = Some(Analyzer {
+                        root_path: root,
+                        root: node,
+                        current: Vec::new(),
+                    });
+                }
+                status
+            }
+            None => format!("Could not read {}", root.to_string_lossy()),
+        };
+        run_with_disk_usage_window(move |w| {
+            let adp = w.global::<DiskUsageAdapter>();
+            adp.set_scanning(false);
+            adp.set_status(status.into());
+        });
+        refresh_view();
+    });
+}
+
+///Shows the children of the current node
+fn refresh_view() {
+    run_with_disk_usage_window(|w| {
+        let Ok(analyzer) = ANALYZER.lock() else {
+            return;
+        };
+        let Some(analyzer) = analyzer.as_ref() else {
+            return;
+        };
+        let node = analyzer.current_node();
+        let path = analyzer.current_path();
+        let entries: Vec<UsageEntry> = node
+            .children
+            .iter()
+            .map(|c| {
+                let (a, b) = i64_to_i32(c.size as i64);
+                UsageEntry {
+                    name: c.name.clone().into(),
+                    path: path.join(&c.name).to_string_lossy().to_string().into(),
+                    size: _i64 { a, b },
+                    percent: if node.size > 0 {
+                        c.size as f32 / node.size as f32
+                    } else {
+                        0.0
+                    },
+                    is_dir: c.is_dir,
+                }
+            })
+            .collect();
+        let sizes: Vec<f64> = node
+            .children
+            .iter()
+            .take(TREEMAP_MAX_ENTRIES)
+            .map(|c| c.size as f64)
+            .collect();
+        //Laid out with the real aspect ratio so the cells look square, then normalized
+        let ratio = if w.get_treemap_height() > 0.0 {
+            (w.get_treemap_width() / w.get_treemap_height()) as f64
+        } else {
+            1.0
+        };
+        let cells: Vec<UsageCell> = squarify(&sizes, ratio, 1.0)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, c)| c.w > 0.0 && c.h > 0.0)
+            .map(|(i, c)| UsageCell {
+                x: (c.x / ratio) as f32,
+                y: c.y as f32,
+                w: (c.w / ratio) as f32,
+                h: c.h as f32,
+                index: i as i32,
+            })
+            .collect();
+
+        let adp = w.global::<DiskUsageAdapter>();
+        adp.set_current_path(path.to_string_lossy().to_string().into());
+        adp.set_can_go_up(!analyzer.current.is_empty());
+        adp.set_selected(-1);
+        adp.set_entries(Rc::new(VecModel::from(entries)).into());
+        adp.set_cells(Rc::new(VecModel::from(cells)).into());
+    });
+}
+
+pub fn scan_drive(index: i32) {
+    run_with_disk_usage_window(move |w| {
+        if let Some(drive) = w
+            .global::<DiskUsageAdapter>()
+            .get_drives()
+            .row_data(index as usize)
+        {
+            start_scan(PathBuf::from(drive.internal_path.as_str()));
+        }
+    });
+}
+
+///Drills down into a directory
+pub fn enter(index: i32) {
+    if let Ok(mut analyzer) = ANALYZER.lock() {
+        if let Some(analyzer) = analyzer.as_mut() {
+            match analyzer.current_node().children.get(index as usize) {
+                Some(child) if child.is_dir => analyzer.current.push(index as usize),
+                _ => return,
+            }
+        }
+    }
+    refresh_view();
+}
+
+pub fn go_up() {
+    if let Ok(mut analyzer) = ANALYZER.lock() {
+        if let Some(analyzer) = analyzer.as_mut() {
+            analyzer.current.pop();
+        }
+    }
+    refresh_view();
+}
+
+///Opens directories in the current tab, files with their default program
+pub fn open(entry: UsageEntry) {
+    if entry.is_dir {
+        ui::send_message(UIMessage::SetCurrentTabFile(
+            TabItem {
+                internal_path: entry.path,
+                selected: true,
+                text: entry.name.clone(),
+                text_length: entry.name.len() as i32,
+                view_mode: tabs::VIEW_DETAILS,
+            },
+            true,
+        ));
+    } else {
+        let mut file = empty_file_with_path(&entry.path);
+        file.file_name = entry.name.clone();
+        file.extension = Path::new(entry.name.as_str())
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_default()
+            .into();
+        open_with_default(vec![file]);
+    }
+}
+
+///Trashes or deletes an entry of the current directory, then scans it again once done:
+///it's removed from the view if it no longer exists, and its size updated otherwise
+pub fn remove(entry: UsageEntry, permanently: bool) {
+    let path = PathBuf::from(entry.path.as_str());
+    let is_dir = entry.is_dir;
+    thread::spawn(move || {
+        if permanently {
+            let mut file = empty_file_with_path(&path.to_string_lossy());
+            file.is_dir = is_dir;
+            delete_files_sync(vec![file]);
+        } else {
+            if let Err(e) = trash_file(&path) {
+                user_notice(&format!(
+                    "Could not move {} to the trash: {}",
+                    path.to_string_lossy(),
+                    e
+                ));
+            }
+            ui::send_message(ui::UIMessage::Refresh);
+        }
+        let progress = ScanProgress {
+            files: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            generation: SCAN_GENERATION.load(Ordering::SeqCst),
+        };
+        let scanned = symlink_metadata(&path).ok().map(|meta| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            scan_node(&path, name.to_string(), &meta, &progress)
+        });
+        match ANALYZER.lock() {
+            Ok(mut analyzer) => {
+                if let Some(analyzer) = analyzer.as_mut() {
+                    analyzer.replace(&path, scanned);
+                }
+            }
+            Err(_) => log_error_str("Could not get the disk usage lock."),
+        }
+        refresh_view();
+    });
+}
+
+pub fn close() {
+    //Stops a scan in progress and frees the tree
+    SCAN_GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut analyzer) = ANALYZER.lock() {
+        *analyzer = None;
+    }
+    run_with_disk_usage_window(|w| {
+        w.hide().ok();
+    });
+}
@@ -1,46 +1,401 @@
-use slint::VecModel;
+use slint::{ComponentHandle, Model, VecModel};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
 use sysinfo::Disk;
 use sysinfo::DiskExt;
 use sysinfo::SystemExt;
 
-use crate::globals::sysinfo_lock;
-use crate::ui::*;
+use crate::callbacks::tabs;
+use crate::config::{Config, MountRule};
+use crate::context_menus::context_items::get_ci;
+use crate::globals::{config_read, sysinfo_lock};
+use crate::status_bar;
+use crate::udisks::{self, Volume};
+use crate::ui::{self, main_window::run_with_main_window, *};
+use crate::utils::{error_handling::user_notice, types::format_size};
 
-static HIDDEN_MOUNTS: [&str; 1] = ["/boot"];
+///Where udev links the devices by filesystem label
+const BY_LABEL: &str = "/dev/disk/by-label";
 
-pub fn get_drives() -> Rc<VecModel<SidebarItem>> {
+///How full a drive is, as shown under it in the sidebar
+struct Usage {
+    pct: i32,
+    level: i32,
+    text: String,
+}
+
+///The "drive_usage_warning" and "drive_usage_critical" percentages
+fn usage_thresholds(conf: &Config) -> (u64, u64) {
+    (
+        conf.get("drive_usage_warning").unwrap_or(80),
+        conf.get("drive_usage_critical").unwrap_or(90),
+    )
+}
+
+fn usage(d: &Disk, (warning, critical): (u64, u64)) -> Option<Usage> {
+    if d.total_space() == 0 {
+        return None;
+    }
+    let pct = drive_pct(d);
+    Some(Usage {
+        pct: pct as i32,
+        level: if pct >= critical {
+            2
+        } else if pct >= warning {
+            1
+        } else {
+            0
+        },
+        text: format!(
+            "{} used, {} free of {}",
+            drive_pct_formatted(d),
+            format_size(d.available_space(), false),
+            format_size(d.total_space(), false)
+        ),
+    })
+}
+
+///Asks UDisks2 and reads the mounts, better called from a thread
+pub fn get_drives() -> Vec<SidebarItem> {
+    let volumes = udisks::volumes();
     let mut system = sysinfo_lock();
     system.refresh_disks_list();
 
-    let drives: Rc<VecModel<SidebarItem>> = Rc::new(VecModel::default());
+    let conf = config_read();
+    let thresholds = usage_thresholds(&conf);
+    let is_hidden = |mount: &str, fs_type: &str, device: &str| {
+        is_hidden(
+            conf.get_hidden_mounts(),
+            conf.get_shown_mounts(),
+            mount,
+            fs_type,
+            device,
+        )
+    };
+    let mut drives: Vec<SidebarItem> = Vec::new();
+    let labels = labels();
 
     for d in system.disks() {
         let drive_name = d.mount_point().to_str().unwrap();
-        if is_hidden(drive_name) {
+        let device = d.name().to_string_lossy();
+        if is_hidden(
+            drive_name,
+            &String::from_utf8_lossy(d.file_system()),
+            &device,
+        ) {
             continue;
         }
-        let final_format = format_drive_name(drive_name);
+        let final_format = drive_display_name(
+            drive_name,
+            &device,
+            conf.get_drive_alias(drive_name),
+            &labels,
+        );
+        let volume = volumes
+            .iter()
+            .find(|v| v.mount_points.iter().any(|m| m == drive_name));
+        let usage = usage(d, thresholds);
         drives.push(SidebarItem {
             text: final_format.into(),
             internal_path: drive_name.into(),
+            editable: false,
+            device: volume.map(|v| v.object.as_str()).unwrap_or_default().into(),
+            mounted: true,
+            usage_pct: usage.as_ref().map(|u| u.pct).unwrap_or(-1),
+            usage_level: usage.as_ref().map(|u| u.level).unwrap_or(0),
+            usage_text: usage.map(|u| u.text).unwrap_or_default().into(),
+        });
+    }
+    //The filesystems that can be mounted
+    for v in volumes
+        .iter()
+        .filter(|v| !v.is_mounted() && !v.ignored && !is_hidden("", &v.fs_type, &v.device))
+    {
+        drives.push(SidebarItem {
+            text: v.display_name().into(),
+            internal_path: "".into(),
+            editable: false,
+            device: v.object.as_str().into(),
+            mounted: false,
+            usage_pct: -1,
+            usage_level: 0,
+            usage_text: "".into(),
         });
     }
     drives
 }
 
-fn is_hidden(s: &str) -> bool {
-    for mp in HIDDEN_MOUNTS {
-        if s == mp {
-            return true;
+///Highlights the drive of the current directory
+pub fn select_current(mw: &MainWindow, path: &str) {
+    let sidebar_items = mw.global::<SidebarItems>();
+    let selected = sidebar_items
+        .get_drive_list()
+        .iter()
+        .position(|d| d.internal_path == path);
+    sidebar_items.set_selected_drive(selected.map(|i| i as i32).unwrap_or(-1));
+}
+
+///Lists the drives again, after one was mounted or unmounted.
+///They are listed in the calling thread, only the sidebar is set in the event loop.
+pub fn refresh() {
+    let drives = get_drives();
+    run_with_main_window(move |mw| {
+        mw.global::<SidebarItems>()
+            .set_drive_list(Rc::new(VecModel::from(drives)).into());
+        let current = mw
+            .global::<TabsAdapter>()
+            .invoke_get_current_tab()
+            .internal_path;
+        select_current(mw, &current);
+    });
+}
+
+///Updates the usage bars of the drives and the free space in the status bar.
+///Called periodically and after file operations.
+pub fn refresh_usage() {
+    let thresholds = usage_thresholds(&config_read());
+    let usages: Vec<(String, Usage)> = {
+        let mut system = sysinfo_lock();
+        system.refresh_disks();
+        system
+            .disks()
+            .iter()
+            .filter_map(|d| {
+                Some((
+                    d.mount_point().to_string_lossy().into_owned(),
+                    usage(d, thresholds)?,
+                ))
+            })
+            .collect()
+    };
+    run_with_main_window(move |mw| {
+        let drives = mw.global::<SidebarItems>().get_drive_list();
+        for i in 0..drives.row_count() {
+            let Some(mut drive) = drives.row_data(i) else {
+                continue;
+            };
+            let Some((_, usage)) = usages
+                .iter()
+                .find(|(m, _)| drive.internal_path == m.as_str())
+            else {
+                continue;
+            };
+            //Setting a row redraws it, only do it for the drives that changed
+            if drive.usage_pct != usage.pct || drive.usage_text != usage.text.as_str() {
+                drive.usage_pct = usage.pct;
+                drive.usage_level = usage.level;
+                drive.usage_text = usage.text.as_str().into();
+                drives.set_row_data(i, drive);
+            }
         }
+        status_bar::update_free_space(mw);
+    });
+}
+
+///Refreshes the usage every "drive_refresh_secs" seconds
+pub fn watch_usage() {
+    static WATCH: Once = Once::new();
+    WATCH.call_once(|| {
+        let secs: u64 = config_read().get("drive_refresh_secs").unwrap_or(30);
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(secs.max(1)));
+            refresh_usage();
+        });
+    });
+}
+
+///The free and total space of the filesystem of the path, in bytes
+pub fn filesystem_space(path: &str) -> Option<(u64, u64)> {
+    let path = CString::new(path).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let block_size = stat.f_frsize as u64;
+    Some((
+        stat.f_bavail as u64 * block_size,
+        stat.f_blocks as u64 * block_size,
+    ))
+}
+
+fn open(path: String) {
+    let text = format_drive_name(&path);
+    ui::send_message(UIMessage::SetCurrentTabFile(
+        TabItem {
+            internal_path: path.into(),
+            text_length: text.len() as i32,
+            text: text.into(),
+            selected: true,
+            view_mode: tabs::VIEW_DETAILS,
+        },
+        true,
+    ));
+}
+
+///The UDisks2 volume of a drive of the sidebar
+fn volume_at(mw: &MainWindow, index: i32) -> Option<Volume> {
+    let drive = mw
+        .global::<SidebarItems>()
+        .get_drive_list()
+        .row_data(usize::try_from(index).ok()?)?;
+    udisks::cached(&drive.device)
+}
+
+///Runs an action on the volume in a thread, then lists the drives again
+fn in_thread(volume: Volume, action: impl FnOnce(Volume) + Send + 'static) {
+    thread::spawn(move || {
+        action(volume);
+        refresh();
+    });
+}
+
+///Mounts the volume, then shows it
+pub fn mount_device(device: &str) {
+    let Some(volume) = udisks::cached(device) else {
+        return;
+    };
+    in_thread(volume, |volume| match udisks::mount(&volume) {
+        Ok(path) => open(path),
+        Err(e) => user_notice(&e),
+    });
+}
+
+pub fn mount(index: i32) {
+    run_with_main_window(move |mw| {
+        if let Some(volume) = volume_at(mw, index) {
+            mount_device(&volume.object);
+        }
+    });
+}
+
+pub fn unmount(index: i32) {
+    run_with_main_window(move |mw| {
+        let Some(volume) = volume_at(mw, index) else {
+            return;
+        };
+        in_thread(volume, |volume| {
+            if let Err(e) = udisks::unmount(&volume) {
+                user_notice(&e);
+            }
+        });
+    });
+}
+
+pub fn power_off(index: i32) {
+    run_with_main_window(move |mw| {
+        let Some(volume) = volume_at(mw, index) else {
+            return;
+        };
+        in_thread(volume, |volume| {
+            if let Err(e) = udisks::power_off(&volume) {
+                user_notice(&e);
+            }
+        });
+    });
+}
+
+///Mount, unmount and power off, for the drives UDisks2 knows
+pub fn show_context_menu(index: i32, x: f32, y: f32) {
+    run_with_main_window(move |mw| {
+        let mut menu: Vec<ContextItem> = Vec::new();
+        if let Some(volume) = volume_at(mw, index) {
+            if volume.is_mounted() {
+                menu.push(get_ci("unmount_drive"));
+            } else {
+                menu.push(get_ci("mount_drive"));
+            }
+            if volume.can_power_off {
+                menu.push(get_ci("power_off_drive"));
+            }
+        }
+        for item in menu.iter_mut() {
+            item.internal_id = index;
+        }
+
+        let ctx_adapter = mw.global::<ContextAdapter>();
+        ctx_adapter.set_items(Rc::new(VecModel::from(menu)).into());
+        ctx_adapter.set_x_pos(x + 1f32);
+        ctx_adapter.set_y_pos(y + 1f32);
+    });
+}
+
+///Hidden by the "hidden_mounts" rules, unless a "shown_mounts" one matches
+fn is_hidden(
+    hidden: &[MountRule],
+    shown: &[MountRule],
+    mount: &str,
+    fs_type: &str,
+    device: &str,
+) -> bool {
+    let matches = |rules: &[MountRule]| rules.iter().any(|r| r.matches(mount, fs_type, device));
+    matches(hidden) && !matches(shown)
+}
+
+///Undoes the escaping of udev in the names of /dev/disk/by-label ("My\x20Disk")
+pub fn unescape_udev(name: &str) -> String {
+    let mut bytes: Vec<u8> = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        let hex = tail
+            .strip_prefix(b"x")
+            .and_then(|t| t.get(..2))
+            .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (b, hex) {
+            (b'\\', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &tail[3..];
+            }
+            _ => {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+///The labels of the filesystems, by device (eg. "/dev/sdb1" -> "Backup")
+fn labels() -> HashMap<PathBuf, String> {
+    let Ok(entries) = std::fs::read_dir(BY_LABEL) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|e| {
+            let device = std::fs::canonicalize(e.path()).ok()?;
+            Some((device, unescape_udev(e.file_name().to_str()?)))
+        })
+        .collect()
+}
+
+///The alias of the mount point from the configuration, the label of the filesystem,
+///or the name of the mount point
+fn drive_display_name(
+    mount: &str,
+    device: &str,
+    alias: Option<&String>,
+    labels: &HashMap<PathBuf, String>,
+) -> String {
+    if let Some(alias) = alias {
+        return alias.clone();
+    }
+    //Devices can be symlinks, eg. /dev/mapper/root -> /dev/dm-0
+    let device = std::fs::canonicalize(device).unwrap_or_else(|_| PathBuf::from(device));
+    match labels.get(&device) {
+        Some(label) => label.clone(),
+        None => format_drive_name(mount),
     }
-    false
 }
 
 #[inline]
 fn drive_pct(d: &Disk) -> u64 {
-    100 - (100 * d.available_space() / d.total_space())
+    100u64.saturating_sub(100 * d.available_space() / d.total_space())
 }
 #[inline]
 fn drive_pct_formatted(d: &Disk) -> String {
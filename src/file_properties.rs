@@ -2,29 +2,44 @@ use prop_window::run_with_prop_window;
 use slint::{ComponentHandle, Model, SharedString, VecModel};
 use std::{
     cmp::Ordering,
+    collections::HashSet,
     fs::{set_permissions, Metadata, Permissions},
     io::{Error, ErrorKind},
     os::unix::fs::{lchown, MetadataExt, PermissionsExt},
     path::{Path, PathBuf},
     rc::Rc,
-    sync::mpsc::channel,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        mpsc::channel,
+    },
     thread::sleep,
     time::{Duration, UNIX_EPOCH},
 };
 use walkdir::WalkDir;
 
 use crate::{
-    callbacks::properties::set_split_bitmask,
+    callbacks::properties::{
+        acl_entry_to_item, acl_item_to_entry, format_mode, get_merged_bitmask, set_split_bitmask,
+        ALL_PERM_BITS,
+    },
+    checksums::{find_expected_hash, hash_file, match_hash, ALGORITHMS},
     core::{
         get_all_groups, get_all_users, get_file_encoding, get_file_magic_type, get_file_metadata,
-        get_gid, get_uid, Group,
+        get_gid, get_uid, set_file_times, Group,
     },
+    status_bar::format_counts,
     ui::{self, *},
     utils::{
         error_handling::{log_error, log_error_str},
-        types::{format_date, i32_to_i64, i64_to_i32},
+        types::{format_date, i32_to_i64, i64_to_i32, parse_date},
+    },
+    xattr::{
+        acl_from_mode, decode_acl, encode_acl, format_errno, format_xattr_value, get_xattr,
+        list_xattrs, parse_xattr_value, remove_xattr, set_xattr, AclEntry, XattrText, ACL_ACCESS,
+        ACL_DEFAULT, USER_NAMESPACE,
     },
 };
+use syscalls::Errno;
 
 pub fn setup_properties(items: Vec<FileItem>, prop_adp: PropertiesAdapter) {
     /*
@@ -60,6 +75,8 @@ pub fn setup_properties(items: Vec<FileItem>, prop_adp: PropertiesAdapter) {
     setup_properties_info(&items, &prop_adp, metadata);
     setup_properties_permissions(&items, &prop_adp, metadata);
     setup_properties_advanced(&prop_adp, metadata);
+    setup_properties_acl(&items, &prop_adp, metadata);
+    setup_properties_xattrs(&items, &prop_adp);
 
     //calculate_directory_size also calculates multiple files
     //Still overkill for a single file though.
@@ -70,8 +87,17 @@ pub fn setup_properties(items: Vec<FileItem>, prop_adp: PropertiesAdapter) {
     /*Reset State*/
     prop_adp.set_selected_tab_idx(0);
     prop_adp.set_perm_bits_dirty(false);
+    prop_adp.set_perm_bits_valid(true);
+    prop_adp.set_perm_touched_mask(0);
     prop_adp.set_uid_dirty(false);
     prop_adp.set_gid_dirty(false);
+    prop_adp.set_acl_dirty(false);
+    prop_adp.set_atime_dirty(false);
+    prop_adp.set_mtime_dirty(false);
+    prop_adp.set_atime_valid(true);
+    prop_adp.set_mtime_valid(true);
+    prop_adp.set_times_recursive(false);
+    reset_checksums(&prop_adp);
     //TODO: reset focus too
 
     if items.len() > 1 {
@@ -94,8 +120,9 @@ pub fn setup_properties_info(
         let metadata = metadata.unwrap();
         prop_adp.set_filename((*item.file_name).to_string().into());
 
-        /*Access time*/
-        prop_adp.set_atime(format_date(metadata.atime() as i64));
+        /*Access and modification time*/
+        prop_adp.set_atime(format_date(metadata.atime()));
+        prop_adp.set_mtime(format_date(metadata.mtime()));
 
         /*Creation time*/
         let created = metadata.created();
@@ -121,9 +148,26 @@ pub fn setup_properties_info(
         }
     } else {
         prop_adp.set_filename("Multiple selections".into());
-        //TODO: If they are the same, show the value
-        /*Access time*/
-        prop_adp.set_atime("N/A".into());
+        /*Access and modification time*/
+        //Only shown if they are all the same, but they can still be set for all files
+        let mut atime = None;
+        let mut mtime = None;
+        for (i, f) in items.iter().enumerate() {
+            if let Ok(meta) = get_file_metadata(&f.path) {
+                if i == 0 || atime == Some(meta.atime()) {
+                    atime = Some(meta.atime());
+                } else {
+                    atime = None;
+                }
+                if i == 0 || mtime == Some(meta.mtime()) {
+                    mtime = Some(meta.mtime());
+                } else {
+                    mtime = None;
+                }
+            }
+        }
+        prop_adp.set_atime(atime.map(format_date).unwrap_or_default());
+        prop_adp.set_mtime(mtime.map(format_date).unwrap_or_default());
 
         /*Creation time*/
 
@@ -261,10 +305,11 @@ pub fn setup_properties_permissions(
                 .into(),
             );
         }
-        /*Permission triplets*/
-        let perm = metadata.permissions().mode() & 0o777;
-        set_split_bitmask(prop_adp, perm as i32);
-        prop_adp.set_perm_bits_str(format!("{:o}", perm).into());
+        /*Permission triplets and special bits*/
+        let perm = (metadata.permissions().mode() as i32) & ALL_PERM_BITS;
+        set_split_bitmask(prop_adp, perm);
+        prop_adp.set_perm_mixed_mask(0);
+        prop_adp.set_perm_bits_str(format_mode(perm, 0).into());
 
     //Multiple files
     } else {
@@ -285,7 +330,9 @@ pub fn setup_properties_permissions(
         //Will be none unless all files have the same
         let mut file_gid = Some(first_metadata.gid());
         let mut file_uid = Some(first_metadata.uid());
-        let mut perm = Some(first_metadata.permissions().mode());
+        //Bits set in every file / in at least one file. Where they differ, the bit is mixed.
+        let mut perm_and = first_metadata.permissions().mode() as i32 & ALL_PERM_BITS;
+        let mut perm_or = perm_and;
 
         for f in files {
             if let Ok(meta) = get_file_metadata(&f.path) {
@@ -298,9 +345,8 @@ pub fn setup_properties_permissions(
                     file_uid = None;
                 }
                 //Perms
-                if perm != None && perm != Some(meta.permissions().mode()) {
-                    perm = None;
-                }
+                perm_and &= meta.permissions().mode() as i32;
+                perm_or |= meta.permissions().mode() as i32 & ALL_PERM_BITS;
             } else {
                 log_error_str(&format!("Could not get metadata for {}. Aborting.", f.path));
                 return;
@@ -385,15 +431,12 @@ pub fn setup_properties_permissions(
                 .into(),
             );
         }
-        /*Permission triplets*/
-        if let Some(mut perm) = perm {
-            perm = perm & 0o777;
-            set_split_bitmask(prop_adp, perm as i32);
-            prop_adp.set_perm_bits_str(format!("{:o}", perm).into());
-        } else {
-            set_split_bitmask(prop_adp, 0);
-            prop_adp.set_perm_bits_str("".into());
-        }
+        /*Permission triplets and special bits*/
+        //Mixed bits are shown unchecked and are left untouched on save unless modified
+        let mixed = perm_and ^ perm_or;
+        set_split_bitmask(prop_adp, perm_and);
+        prop_adp.set_perm_mixed_mask(mixed);
+        prop_adp.set_perm_bits_str(format_mode(perm_and, mixed).into());
     }
 }
 pub fn setup_properties_advanced(prop_adp: &PropertiesAdapter, metadata: Option<&Metadata>) {
@@ -411,26 +454,291 @@ pub fn setup_properties_advanced(prop_adp: &PropertiesAdapter, metadata: Option<
     }
 }
 
+/*
+ *  ACL and extended attributes
+ *  Only available for a single file, otherwise the lists are left empty.
+ * */
+
+///If items.len() == 1, then metadata MUST be Some
+pub fn setup_properties_acl(
+    items: &Vec<FileItem>,
+    prop_adp: &PropertiesAdapter,
+    metadata: Option<&Metadata>,
+) {
+    let mut entries: Vec<AclItem> = Vec::new();
+    let mut error = String::new();
+    if items.len() == 1 {
+        let path = Path::new(items[0].path.as_str());
+        let users = get_all_users().unwrap_or_default();
+        let groups = get_all_groups().unwrap_or_default();
+
+        //No ACL xattr means the mode is the whole ACL
+        let access = match get_xattr(path, ACL_ACCESS) {
+            Ok(v) => decode_acl(&v),
+            Err(e) if e == Errno::ENODATA => {
+                Some(acl_from_mode(metadata.unwrap().permissions().mode()))
+            }
+            Err(e) => {
+                error = format_errno(e);
+                None
+            }
+        };
+        let default = if items[0].is_dir {
+            match get_xattr(path, ACL_DEFAULT) {
+                Ok(v) => decode_acl(&v),
+                Err(e) if e == Errno::ENODATA => Some(Vec::new()),
+                Err(e) => {
+                    error = format_errno(e);
+                    None
+                }
+            }
+        } else {
+            Some(Vec::new())
+        };
+
+        match (access, default) {
+            (Some(access), Some(default)) => {
+                entries.extend(
+                    access
+                        .iter()
+                        .map(|e| acl_entry_to_item(e, false, &users, &groups)),
+                );
+                entries.extend(
+                    default
+                        .iter()
+                        .map(|e| acl_entry_to_item(e, true, &users, &groups)),
+                );
+            }
+            _ if error.is_empty() => error = "The ACL of this file is malformed.".into(),
+            _ => (),
+        }
+    }
+    prop_adp.set_acl_entries(Rc::new(VecModel::from(entries)).into());
+    prop_adp.set_acl_error(error.into());
+}
+
+pub fn setup_properties_xattrs(items: &Vec<FileItem>, prop_adp: &PropertiesAdapter) {
+    let mut attrs: Vec<XattrItem> = Vec::new();
+    let mut error = String::new();
+    if items.len() == 1 {
+        let path = Path::new(items[0].path.as_str());
+        match list_xattrs(path) {
+            Ok(names) => {
+                for name in names.into_iter().filter(|n| n.starts_with(USER_NAMESPACE)) {
+                    let (value, error) = match get_xattr(path, &name) {
+                        Ok(v) => (format_xattr_value(&v), String::new()),
+                        Err(e) => (XattrText::default(), format_errno(e)),
+                    };
+                    attrs.push(XattrItem {
+                        name: name.into(),
+                        value: value.text.into(),
+                        error: error.into(),
+                        dirty: false,
+                        removed: false,
+                        is_new: false,
+                        hex: value.hex,
+                        nul_terminated: value.nul_terminated,
+                    });
+                }
+                attrs.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+            Err(e) => error = format_errno(e),
+        }
+    }
+    prop_adp.set_xattrs(Rc::new(VecModel::from(attrs)).into());
+    prop_adp.set_xattr_error(error.into());
+}
+
+///Writes the access and default ACLs if they were modified.
+fn save_acl(prop_adp: &PropertiesAdapter, path: &Path) -> Result<(), String> {
+    if !prop_adp.get_acl_dirty() {
+        return Ok(());
+    }
+    let (default, access): (Vec<AclItem>, Vec<AclItem>) = prop_adp
+        .get_acl_entries()
+        .iter()
+        .partition(|e| e.is_default);
+    let access: Vec<AclEntry> = access.iter().map(acl_item_to_entry).collect();
+    let default: Vec<AclEntry> = default.iter().map(acl_item_to_entry).collect();
+
+    set_xattr(path, ACL_ACCESS, &encode_acl(&access)).map_err(format_errno)?;
+    if default.is_empty() {
+        match remove_xattr(path, ACL_DEFAULT) {
+            Err(e) if e != Errno::ENODATA => return Err(format_errno(e)),
+            _ => (),
+        }
+    } else {
+        set_xattr(path, ACL_DEFAULT, &encode_acl(&default)).map_err(format_errno)?;
+    }
+    prop_adp.set_acl_dirty(false);
+    Ok(())
+}
+
+///Applies the modified user attributes. Errors are kept on each attribute.
+///Returns false if any of them failed.
+fn save_xattrs(prop_adp: &PropertiesAdapter, path: &Path) -> bool {
+    let mut ok = true;
+    let mut remaining = Vec::new();
+    for mut attr in prop_adp.get_xattrs().iter() {
+        let ret = if attr.removed {
+            if attr.is_new {
+                continue;
+            }
+            remove_xattr(path, &attr.name).map_err(format_errno)
+        } else if attr.dirty || attr.is_new {
+            match parse_xattr_value(&attr.value, attr.hex, attr.nul_terminated) {
+                Some(value) => set_xattr(path, &attr.name, &value).map_err(format_errno),
+                None => Err("Invalid hexadecimal value.".into()),
+            }
+        } else {
+            Ok(())
+        };
+        match ret {
+            Ok(()) if attr.removed => continue,
+            Ok(()) => {
+                attr.dirty = false;
+                attr.is_new = false;
+                attr.error = "".into();
+            }
+            Err(e) => {
+                attr.error = e.into();
+                ok = false;
+            }
+        }
+        remaining.push(attr);
+    }
+    prop_adp.set_xattrs(Rc::new(VecModel::from(remaining)).into());
+    ok
+}
+
 /*
  *  Calculates the directory size by iterating with walkdir.
  *  Uses 2 threads, one to update the UI every 500ms and another to calculate
  *  the size and notify the first.
+ *
+ *  Along with the apparent size, this gathers the size on disk, the totals with
+ *  hardlinks counted only once and the size of each top level child.
  * */
 
+///How many of the largest children are shown
+pub const TOP_CHILDREN: usize = 10;
+
+#[derive(Clone, Default)]
+pub struct DirectoryUsage {
+    pub apparent: i64,
+    pub on_disk: i64,
+    //Hardlinked inodes are only counted the first time they are seen
+    pub unique_apparent: i64,
+    pub unique_on_disk: i64,
+    //(name, apparent size) of the top level children
+    pub children: Vec<(String, i64)>,
+    //What the directories contain, or the selected files and what they contain
+    pub files: usize,
+    pub dirs: usize,
+}
+
+impl DirectoryUsage {
+    ///Adds an entry to the totals and to the last child.
+    ///first_link is false if this inode was already counted through another hardlink.
+    pub fn add(&mut self, meta: &Metadata, first_link: bool) {
+        let apparent = meta.size() as i64;
+        let on_disk = meta.blocks() as i64 * 512; //st_blocks is always in 512B units
+        self.apparent += apparent;
+        self.on_disk += on_disk;
+        if first_link {
+            self.unique_apparent += apparent;
+            self.unique_on_disk += on_disk;
+        }
+        if let Some(child) = self.children.last_mut() {
+            child.1 += apparent;
+        }
+    }
+
+    ///The largest children, biggest first
+    pub fn top_children(&self, n: usize) -> Vec<(String, i64)> {
+        let mut children = self.children.clone();
+        children.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+        children.truncate(n);
+        children
+    }
+}
+
+fn set_directory_usage(prop_adp: &PropertiesAdapter, usage: &DirectoryUsage) {
+    let to_i64 = |i: i64| {
+        let (a, b) = i64_to_i32(i);
+        _i64 { a, b }
+    };
+    prop_adp.set_directory_size(to_i64(usage.apparent));
+    prop_adp.set_directory_disk_usage(to_i64(usage.on_disk));
+    prop_adp.set_directory_unique_size(to_i64(usage.unique_apparent));
+    prop_adp.set_directory_unique_disk_usage(to_i64(usage.unique_on_disk));
+    prop_adp.set_directory_contents(format_counts(usage.dirs, usage.files, 0).into());
+    prop_adp.set_largest_children(
+        Rc::new(VecModel::from(
+            usage
+                .top_children(TOP_CHILDREN)
+                .into_iter()
+                .map(|(name, size)| UsageItem {
+                    name: name.into(),
+                    size: to_i64(size),
+                    percent: if usage.apparent > 0 {
+                        size as f32 / usage.apparent as f32
+                    } else {
+                        0.0
+                    },
+                })
+                .collect::<Vec<UsageItem>>(),
+        ))
+        .into(),
+    );
+}
+
+///Walks the paths without following links, progress is called after each entry.
+///For a single directory, the children are its entries. Otherwise, the paths themselves.
+pub fn directory_usage(
+    paths: &[PathBuf],
+    mut progress: impl FnMut(&DirectoryUsage),
+) -> DirectoryUsage {
+    let mut usage = DirectoryUsage::default();
+    let mut seen_inodes: HashSet<(u64, u64)> = HashSet::new();
+    let child_depth = if paths.len() == 1 { 1 } else { 0 };
+    for path in paths {
+        for entry in WalkDir::new(path).follow_links(false).into_iter().flatten() {
+            if entry.depth() == child_depth {
+                usage
+                    .children
+                    .push((entry.file_name().to_string_lossy().to_string(), 0));
+            }
+            if entry.depth() >= child_depth {
+                if entry.file_type().is_dir() {
+                    usage.dirs += 1;
+                } else {
+                    usage.files += 1;
+                }
+            }
+            if let Ok(m) = entry.metadata() {
+                let first_link =
+                    m.is_dir() || m.nlink() < 2 || seen_inodes.insert((m.dev(), m.ino()));
+                usage.add(&m, first_link);
+            }
+            progress(&usage);
+        }
+    }
+    usage
+}
+
 pub fn calculate_directory_size(files: &Vec<FileItem>, prop_adp: &PropertiesAdapter) {
     prop_adp.set_is_directory_calculated(false);
-    prop_adp.set_directory_size(_i64 { a: 0, b: 0 });
+    set_directory_usage(prop_adp, &DirectoryUsage::default());
 
-    let (send_a, recv_a) = channel();
+    let (send_a, recv_a) = channel::<Option<DirectoryUsage>>();
     let (send_b, recv_b) = channel();
     std::thread::spawn(move || {
         let delay = Duration::from_millis(500); //UI update frequency
         while let Ok(data) = recv_a.recv() {
-            if data != 0 {
+            if let Some(usage) = data {
                 run_with_prop_window(move |w| {
-                    let (a, b) = i64_to_i32(data);
-                    w.global::<PropertiesAdapter>()
-                        .set_directory_size(_i64 { a, b });
+                    set_directory_usage(&w.global::<PropertiesAdapter>(), &usage);
                 });
             }
             sleep(delay);
@@ -442,40 +750,170 @@ pub fn calculate_directory_size(files: &Vec<FileItem>, prop_adp: &PropertiesAdap
         }
     });
 
-    let files = files.clone();
+    let paths: Vec<PathBuf> = files
+        .iter()
+        .map(|f| PathBuf::from(f.path.as_str()))
+        .collect();
+    std::thread::spawn(move || {
+        send_a.send(None).ok();
+        let usage = directory_usage(&paths, |usage| {
+            if recv_b.try_recv().is_ok() {
+                send_a.send(Some(usage.clone())).ok();
+            }
+        });
+
+        //Final update so we get it instantly without waiting 500ms
+        run_with_prop_window(move |w| {
+            set_directory_usage(&w.global::<PropertiesAdapter>(), &usage);
+            w.global::<PropertiesAdapter>()
+                .set_is_directory_calculated(true)
+        });
+    });
+}
+
+/*
+ *  Checksums, with the same 2 threads as calculate_directory_size.
+ *  Bumping CHECKSUM_GENERATION cancels the calculation in progress,
+ *  and VERIFY_GENERATION the comparison with the expected hash.
+ * */
+
+static CHECKSUM_GENERATION: AtomicUsize = AtomicUsize::new(0);
+static VERIFY_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+pub fn reset_checksums(prop_adp: &PropertiesAdapter) {
+    CHECKSUM_GENERATION.fetch_add(1, AtomicOrdering::SeqCst);
+    VERIFY_GENERATION.fetch_add(1, AtomicOrdering::SeqCst);
+    prop_adp.set_checksums(
+        Rc::new(VecModel::from(
+            ALGORITHMS
+                .iter()
+                .map(|a| ChecksumItem {
+                    algorithm: (*a).into(),
+                    value: "".into(),
+                })
+                .collect::<Vec<ChecksumItem>>(),
+        ))
+        .into(),
+    );
+    prop_adp.set_checksum_state(0);
+    prop_adp.set_checksum_progress(0.0);
+    prop_adp.set_checksum_error("".into());
+    prop_adp.set_checksum_expected("".into());
+    prop_adp.set_checksum_match(0);
+    prop_adp.set_checksum_match_algorithm("".into());
+}
+
+pub fn cancel_checksums(prop_adp: &PropertiesAdapter) {
+    CHECKSUM_GENERATION.fetch_add(1, AtomicOrdering::SeqCst);
+    prop_adp.set_checksum_state(0);
+}
+
+pub fn calculate_checksums(prop_adp: &PropertiesAdapter) {
+    let files = prop_adp.get_files();
+    if files.row_count() != 1 {
+        return;
+    }
+    let file = files.row_data(0).unwrap();
+    let size = i32_to_i64((file.size.a, file.size.b)).max(1) as f32;
+    let generation = CHECKSUM_GENERATION.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+    prop_adp.set_checksum_state(1);
+    prop_adp.set_checksum_progress(0.0);
+    prop_adp.set_checksum_error("".into());
+
+    let (send_a, recv_a) = channel::<u64>();
+    let (send_b, recv_b) = channel();
+    std::thread::spawn(move || {
+        let delay = Duration::from_millis(500); //UI update frequency
+        while let Ok(data) = recv_a.recv() {
+            run_with_prop_window(move |w| {
+                if CHECKSUM_GENERATION.load(AtomicOrdering::SeqCst) == generation {
+                    w.global::<PropertiesAdapter>()
+                        .set_checksum_progress(data as f32 / size);
+                }
+            });
+            sleep(delay);
+
+            //Usually will be Err when it's done calculating
+            if send_b.send(1).is_err() {
+                break;
+            }
+        }
+    });
+
     std::thread::spawn(move || {
-        let mut total: i64 = 0;
         send_a.send(0).ok();
+        let ret = hash_file(Path::new(file.path.as_str()), |read| {
+            if recv_b.try_recv().is_ok() {
+                send_a.send(read).ok();
+            }
+            CHECKSUM_GENERATION.load(AtomicOrdering::SeqCst) == generation
+        });
 
-        for f in files {
-            if f.is_dir {
-                for entry_res in WalkDir::new(PathBuf::from(f.path.to_string())).follow_links(false)
-                {
-                    if let Ok(entry) = entry_res {
-                        total += match entry.metadata() {
-                            Ok(m) => m.size() as i64,
-                            Err(_) => 0,
-                        };
-                    }
-                    if recv_b.try_recv().is_ok() {
-                        send_a.send(total).ok();
+        run_with_prop_window(move |w| {
+            if CHECKSUM_GENERATION.load(AtomicOrdering::SeqCst) != generation {
+                return;
+            }
+            let prop_adp = w.global::<PropertiesAdapter>();
+            match ret {
+                Ok(Some(hashes)) => {
+                    let checksums = prop_adp.get_checksums();
+                    for (i, hash) in hashes.into_iter().enumerate() {
+                        if let Some(mut item) = checksums.row_data(i) {
+                            item.value = hash.into();
+                            checksums.set_row_data(i, item);
+                        }
                     }
+                    prop_adp.set_checksum_state(2);
+                    verify_checksum(&prop_adp);
                 }
-            } else {
-                total += i32_to_i64((f.size.a, f.size.b));
-                if recv_b.try_recv().is_ok() {
-                    send_a.send(total).ok();
+                Ok(None) => prop_adp.set_checksum_state(0),
+                Err(e) => {
+                    prop_adp.set_checksum_state(3);
+                    prop_adp.set_checksum_error(e.to_string().into());
                 }
             }
-        }
+        });
+    });
+}
 
-        //Final update so we get it instantly without waiting 500ms
+///Compares the expected hash with the calculated ones.
+///The expected value can be a hash, the content of a checksum file or the path to one,
+///which is read in a thread since it can be big or on a slow mount.
+pub fn verify_checksum(prop_adp: &PropertiesAdapter) {
+    let generation = VERIFY_GENERATION.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+    let expected = prop_adp.get_checksum_expected().trim().to_string();
+    let files = prop_adp.get_files();
+    if expected.is_empty() || prop_adp.get_checksum_state() != 2 || files.row_count() != 1 {
+        prop_adp.set_checksum_match(0);
+        prop_adp.set_checksum_match_algorithm("".into());
+        return;
+    }
+    let file_name = files.row_data(0).unwrap().file_name.to_string();
+    let hashes: Vec<String> = prop_adp
+        .get_checksums()
+        .iter()
+        .map(|c| c.value.to_string())
+        .collect();
+
+    std::thread::spawn(move || {
+        let content = if Path::new(&expected).is_file() {
+            std::fs::read_to_string(&expected).unwrap_or_default()
+        } else {
+            expected
+        };
+        let found = find_expected_hash(&content, &file_name).map(|h| match_hash(&hashes, &h));
         run_with_prop_window(move |w| {
-            let (a, b) = i64_to_i32(total);
-            w.global::<PropertiesAdapter>()
-                .set_directory_size(_i64 { a, b });
-            w.global::<PropertiesAdapter>()
-                .set_is_directory_calculated(true)
+            if VERIFY_GENERATION.load(AtomicOrdering::SeqCst) != generation {
+                return;
+            }
+            let (state, algorithm) = match found {
+                Some(Some(algorithm)) => (1, algorithm),
+                Some(None) => (2, ""),
+                None => (3, ""),
+            };
+            let prop_adp = w.global::<PropertiesAdapter>();
+            prop_adp.set_checksum_match(state);
+            prop_adp.set_checksum_match_algorithm(algorithm.into());
         });
     });
 }
@@ -489,7 +927,39 @@ pub fn save() {
 
         let files = prop_adp.get_files();
         let single_file = files.row_count() == 1;
-        for f in files.iter() {
+        //Tab to show if something could not be applied, in which case the window stays open
+        let mut failed_tab: Option<i32> = None;
+
+        //Times, None if unchanged
+        let parse_time = |dirty: bool, valid: bool, s: SharedString| {
+            if !dirty {
+                Ok(None)
+            } else if valid {
+                parse_date(&s).map(Some).ok_or(())
+            } else {
+                Err(())
+            }
+        };
+        let atime = parse_time(
+            prop_adp.get_atime_dirty(),
+            prop_adp.get_atime_valid(),
+            prop_adp.get_atime(),
+        );
+        let mtime = parse_time(
+            prop_adp.get_mtime_dirty(),
+            prop_adp.get_mtime_valid(),
+            prop_adp.get_mtime(),
+        );
+        let (atime, mtime) = match (atime, mtime) {
+            (Ok(a), Ok(m)) => (a, m),
+            _ => {
+                failed_tab = Some(0);
+                (None, None)
+            }
+        };
+        let mut recursive_dirs: Vec<PathBuf> = Vec::new();
+
+        for (i, mut f) in files.iter().enumerate() {
             let path_str = f.path.to_string();
             let mut path = PathBuf::from(&path_str);
 
@@ -504,8 +974,23 @@ pub fn save() {
                         log_error_str("Could not rename file"); //TODO:
                     } else {
                         path = new_path;
+                        //In case the window stays open, so we don't rename twice
+                        f.path = path.to_string_lossy().to_string().into();
+                        f.file_name = new_filename.into();
+                        files.set_row_data(i, f.clone());
                     }
                 }
+
+                //ACL before the permissions, since chmod also updates the ACL
+                if let Err(e) = save_acl(&prop_adp, &path) {
+                    prop_adp.set_acl_error(e.into());
+                    failed_tab = Some(3);
+                } else {
+                    prop_adp.set_acl_error("".into());
+                }
+                if !save_xattrs(&prop_adp, &path) {
+                    failed_tab.get_or_insert(4);
+                }
             }
 
             //Chown uid
@@ -547,22 +1032,71 @@ pub fn save() {
             }
 
             //Permissions
+            //Only the touched bits are applied, the others are kept as they were for each file.
             if prop_adp.get_perm_bits_dirty() {
-                if let Ok(new_mode) =
-                    u32::from_str_radix(&(prop_adp.get_perm_bits_str().to_string()), 8)
-                {
-                    let ret = set_permissions(path, Permissions::from_mode(new_mode));
+                if !prop_adp.get_perm_bits_valid() {
+                    log_error_str("Could not parse the permission mode.")
+                } else if let Ok(meta) = get_file_metadata(&path.to_string_lossy()) {
+                    let touched = prop_adp.get_perm_touched_mask();
+                    let old_mode = meta.permissions().mode() as i32 & ALL_PERM_BITS;
+                    let new_mode = (old_mode & !touched) | (get_merged_bitmask(&w) & touched);
+                    let ret = set_permissions(&path, Permissions::from_mode(new_mode as u32));
                     if ret.is_err() {
                         log_error(ret.err().unwrap());
                     }
                 } else {
-                    log_error_str("Could not parse the permission mode.")
+                    log_error_str(&format!("Could not get metadata for {}.", path_str));
+                }
+            }
+
+            //Times, last so nothing above bumps them
+            if atime.is_some() || mtime.is_some() {
+                if let Err(e) = set_file_times(&path, atime, mtime, true) {
+                    log_error_str(&format!(
+                        "Could not set the times of {}: {}",
+                        path_str,
+                        format_errno(e)
+                    ));
+                }
+                if f.is_dir && prop_adp.get_times_recursive() {
+                    recursive_dirs.push(path);
                 }
             }
         }
+        if !recursive_dirs.is_empty() {
+            set_times_recursive(recursive_dirs, atime, mtime);
+        }
         //Refresh UI
         ui::send_message(UIMessage::Refresh);
-        w.hide().unwrap();
+        if let Some(tab) = failed_tab {
+            prop_adp.set_selected_tab_idx(tab);
+        } else {
+            w.hide().unwrap();
+        }
+    });
+}
+
+///Applies the times to everything inside these directories in the background.
+///Symlinks themselves are updated, not what they point to.
+fn set_times_recursive(dirs: Vec<PathBuf>, atime: Option<i64>, mtime: Option<i64>) {
+    std::thread::spawn(move || {
+        let mut failed = 0;
+        for dir in dirs {
+            for entry in WalkDir::new(dir).min_depth(1).follow_links(false) {
+                match entry {
+                    Ok(entry) => {
+                        if set_file_times(entry.path(), atime, mtime, false).is_err() {
+                            failed += 1;
+                        }
+                    }
+                    Err(_) => failed += 1,
+                }
+            }
+        }
+        if failed > 0 {
+            log_error_str(&format!("Could not set the times of {} file(s).", failed));
+        }
+        ui::send_message(UIMessage::Refresh);
     });
 }
 
@@ -0,0 +1,317 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use slint::Image;
+
+use crate::{
+    globals::config_read,
+    mime::{mime_db, DIRECTORY, UNKNOWN},
+    ui::FileItem,
+    utils::xdg,
+};
+
+/*
+ *  Icons from the freedesktop icon theme
+ *  (https://specifications.freedesktop.org/icon-theme-spec/latest/).
+ *
+ *  The theme is "icon_theme" from the configuration, or the GTK one. An icon is looked up in the
+ *  theme, then in the themes it inherits from, then in hicolor and /usr/share/pixmaps.
+ *  An empty image is returned when no theme has it, the UI then uses its own icons.
+ * */
+
+const FALLBACK_THEME: &str = "hicolor";
+///Used when neither the configuration nor GTK name a theme
+const DEFAULT_THEME: &str = "Adwaita";
+const EXTENSIONS: [&str; 2] = ["png", "svg"];
+pub const MENU_ICON_SIZE: u32 = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum DirKind {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+///A directory of a theme and the sizes of its icons
+#[derive(Debug)]
+pub struct IconDir {
+    pub path: String,
+    pub size: u32,
+    pub scale: u32,
+    pub kind: DirKind,
+    pub min_size: u32,
+    pub max_size: u32,
+    pub threshold: u32,
+}
+
+impl IconDir {
+    pub fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            DirKind::Fixed => self.size == size,
+            DirKind::Scalable => self.min_size <= size && size <= self.max_size,
+            DirKind::Threshold => self.size.abs_diff(size) <= self.threshold,
+        }
+    }
+
+    pub fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let size = size * scale;
+        let (min, max) = match self.kind {
+            DirKind::Fixed => (self.size, self.size),
+            DirKind::Scalable => (self.min_size, self.max_size),
+            DirKind::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        let (min, max) = (min * self.scale, max * self.scale);
+        if size < min {
+            min - size
+        } else {
+            size.saturating_sub(max)
+        }
+    }
+}
+
+pub struct IconTheme {
+    pub name: String,
+    pub inherits: Vec<String>,
+    pub dirs: Vec<IconDir>,
+}
+
+///Parses an index.theme
+pub fn parse_index_theme(name: &str, content: &str) -> IconTheme {
+    let mut sections: HashMap<&str, HashMap<&str, &str>> = HashMap::new();
+    let mut section = "";
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if let Some(s) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = s;
+        } else if let Some((k, v)) = line.split_once('=') {
+            sections
+                .entry(section)
+                .or_default()
+                .insert(k.trim(), v.trim());
+        }
+    }
+
+    let list = |s: Option<&&str>| -> Vec<String> {
+        s.map(|s| {
+            s.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+    };
+    let main = sections.get("Icon Theme");
+    let mut dir_names = list(main.and_then(|m| m.get("Directories")));
+    dir_names.extend(list(main.and_then(|m| m.get("ScaledDirectories"))));
+
+    let dirs = dir_names
+        .into_iter()
+        .filter_map(|path| {
+            let keys = sections.get(path.as_str())?;
+            let num = |k: &str| keys.get(k).and_then(|v| v.parse::<u32>().ok());
+            let size = num("Size")?;
+            Some(IconDir {
+                size,
+                scale: num("Scale").unwrap_or(1),
+                kind: match keys.get("Type").copied() {
+                    Some("Fixed") => DirKind::Fixed,
+                    Some("Scalable") => DirKind::Scalable,
+                    _ => DirKind::Threshold,
+                },
+                min_size: num("MinSize").unwrap_or(size),
+                max_size: num("MaxSize").unwrap_or(size),
+                threshold: num("Threshold").unwrap_or(2),
+                path,
+            })
+        })
+        .collect();
+
+    IconTheme {
+        name: name.to_string(),
+        inherits: list(main.and_then(|m| m.get("Inherits"))),
+        dirs,
+    }
+}
+
+///Where themes are, most important first
+fn base_dirs() -> &'static Vec<PathBuf> {
+    static BASE_DIRS: OnceLock<Vec<PathBuf>> = OnceLock::new();
+    BASE_DIRS.get_or_init(|| {
+        let mut dirs =
+            vec![PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(".icons")];
+        dirs.extend(xdg::all_data_dirs().into_iter().map(|d| d.join("icons")));
+        dirs
+    })
+}
+
+fn load_theme(name: &str) -> Option<IconTheme> {
+    base_dirs().iter().find_map(|base| {
+        let content = std::fs::read_to_string(base.join(name).join("index.theme")).ok()?;
+        Some(parse_index_theme(name, &content))
+    })
+}
+
+///The theme of GTK applications, from gtk-3.0/settings.ini
+fn gtk_theme_name() -> Option<String> {
+    let content =
+        std::fs::read_to_string(xdg::config_home().join("gtk-3.0").join("settings.ini")).ok()?;
+    content.lines().find_map(|l| {
+        let (k, v) = l.split_once('=')?;
+        (k.trim() == "gtk-icon-theme-name").then(|| v.trim().trim_matches('"').to_string())
+    })
+}
+
+///The configured theme followed by the ones it inherits from, hicolor last
+fn themes() -> &'static Vec<IconTheme> {
+    static THEMES: OnceLock<Vec<IconTheme>> = OnceLock::new();
+    THEMES.get_or_init(|| {
+        let configured: String = config_read().get("icon_theme").unwrap_or_default();
+        let name = if configured.is_empty() {
+            gtk_theme_name().unwrap_or_else(|| DEFAULT_THEME.into())
+        } else {
+            configured
+        };
+        let mut themes: Vec<IconTheme> = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut pending = vec![name];
+        while let Some(name) = pending.pop() {
+            if name == FALLBACK_THEME || !seen.insert(name.clone()) {
+                continue;
+            }
+            if let Some(theme) = load_theme(&name) {
+                //Depth first, in the order of Inherits
+                pending.extend(theme.inherits.iter().rev().cloned());
+                themes.push(theme);
+            }
+        }
+        themes.extend(load_theme(FALLBACK_THEME));
+        themes
+    })
+}
+
+//The file names in a directory, read once instead of checking every possible file
+fn dir_contains(dir: &Path, file: &str) -> bool {
+    static LISTINGS: OnceLock<Mutex<HashMap<PathBuf, HashSet<String>>>> = OnceLock::new();
+    let Ok(mut listings) = LISTINGS.get_or_init(Default::default).lock() else {
+        return false;
+    };
+    listings
+        .entry(dir.to_path_buf())
+        .or_insert_with(|| {
+            std::fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .filter_map(|e| e.file_name().into_string().ok())
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+        .contains(file)
+}
+
+fn lookup_in_theme(theme: &IconTheme, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+    let mut closest: Option<(u32, PathBuf)> = None;
+    for dir in &theme.dirs {
+        for base in base_dirs() {
+            let path = base.join(&theme.name).join(&dir.path);
+            for ext in EXTENSIONS {
+                let file = format!("{}.{}", icon, ext);
+                if !dir_contains(&path, &file) {
+                    continue;
+                }
+                if dir.matches_size(size, scale) {
+                    return Some(path.join(file));
+                }
+                let distance = dir.size_distance(size, scale);
+                if closest.as_ref().is_none_or(|(d, _)| distance < *d) {
+                    closest = Some((distance, path.join(file)));
+                }
+            }
+        }
+    }
+    closest.map(|(_, path)| path)
+}
+
+///Finds the file of an icon, trying the names in order. Absolute paths are used as they are.
+pub fn find_icon(names: &[String], size: u32) -> Option<PathBuf> {
+    if let Some(path) = names.iter().find(|n| n.starts_with('/')) {
+        return Some(PathBuf::from(path));
+    }
+    themes()
+        .iter()
+        .find_map(|theme| {
+            names
+                .iter()
+                .find_map(|n| lookup_in_theme(theme, n, size, 1))
+        })
+        .or_else(|| {
+            let pixmaps = Path::new("/usr/share/pixmaps");
+            names.iter().find_map(|n| {
+                EXTENSIONS
+                    .iter()
+                    .map(|ext| format!("{}.{}", n, ext))
+                    .find(|file| dir_contains(pixmaps, file))
+                    .map(|file| pixmaps.join(file))
+            })
+        })
+}
+
+thread_local! {
+    //Images are not Send, each thread (in practice the UI one) has its own
+    static IMAGES: RefCell<HashMap<(Vec<String>, u32), Image>> = RefCell::new(HashMap::new());
+}
+
+fn icon_for_names(names: Vec<String>, size: u32) -> Image {
+    let key = (names, size);
+    if let Some(image) = IMAGES.with(|c| c.borrow().get(&key).cloned()) {
+        return image;
+    }
+    let image = find_icon(&key.0, size)
+        .and_then(|path| Image::load_from_path(&path).ok())
+        .unwrap_or_default();
+    IMAGES.with(|c| c.borrow_mut().insert(key, image.clone()));
+    image
+}
+
+///An icon by name (eg. "edit-copy") or absolute path, empty if there is none
+pub fn named_icon(name: &str, size: u32) -> Image {
+    if name.is_empty() {
+        return Image::default();
+    }
+    icon_for_names(vec![name.to_string()], size)
+}
+
+pub fn mime_icon(mime: &str, size: u32) -> Image {
+    icon_for_names(mime_db().icon_names(mime), size)
+}
+
+///The icon of a file's type. The type comes from the name only, so that it is cheap enough
+///for every file shown.
+pub fn file_icon(file: &FileItem, size: u32) -> Image {
+    if file.is_dir {
+        return icon_for_names(mime_db().icon_names(DIRECTORY), size);
+    }
+    let mime = if !file.mime_type.is_empty() {
+        file.mime_type.to_string()
+    } else {
+        mime_db()
+            .mime_for_name(&file.file_name)
+            .unwrap_or(UNKNOWN)
+            .to_string()
+    };
+    mime_icon(&mime, size)
+}
@@ -1,8 +1,9 @@
 use crate::{
-    callbacks::filemanager::selection,
+    callbacks::{filemanager::selection, tabs},
     context_menus::{self, files::open_with_default},
-    ui,
+    custom_actions, places, preview, run_command, search, source_viewer, ui,
     utils::error_handling::log_error_str,
+    view_settings,
 };
 
 ///Calls the callback for a given function/feature.
@@ -16,6 +17,10 @@ pub fn call_keybind_callback(name: &str) {
         "select_up" => selection::select_up(true),
         "shift_select_down" => selection::select_down(false),
         "shift_select_up" => selection::select_up(false),
+        "select_left" => selection::select_left(true),
+        "select_right" => selection::select_right(true),
+        "shift_select_left" => selection::select_left(false),
+        "shift_select_right" => selection::select_right(false),
         "enter" => {
             //If multiple files are selected, make sure they are all files, then run them
             if selection::get_common_extension().is_some() {
@@ -29,6 +34,7 @@ pub fn call_keybind_callback(name: &str) {
                         selected: true,
                         text: file.file_name.clone(),
                         text_length: file.file_name.len() as i32,
+                        view_mode: tabs::VIEW_DETAILS,
                     },
                     true,
                 ));
@@ -41,6 +47,20 @@ pub fn call_keybind_callback(name: &str) {
         "paste" => context_menus::files::paste(true /*TODO*/),
         "cut" => context_menus::files::cut(),
         "properties" => context_menus::files::show_properties(),
+        "analyze_disk_usage" => context_menus::files::analyze_disk_usage(),
+        "open_terminal_here" => context_menus::files::open_terminal_here(),
+        "run_command" => run_command::show(),
+        "toggle_hidden" => view_settings::toggle_hidden(),
+        "toggle_preview" => preview::toggle(),
+        "view_source" => source_viewer::view_selected(),
+        "add_place" => places::add_current(),
+        "search" => search::toggle(),
+        "view_details" => tabs::set_view_mode(tabs::VIEW_DETAILS),
+        "view_compact" => tabs::set_view_mode(tabs::VIEW_COMPACT),
+        "view_icons" => tabs::set_view_mode(tabs::VIEW_ICONS),
+        action if action.starts_with(custom_actions::KEYBIND_PREFIX) => {
+            custom_actions::run_action_keybind(action)
+        }
         _ => {
             log_error_str(&format!(
                 "Invalid function for keybind! You may want to verify that you typed it correctly. '{}'",name
@@ -1,6 +1,9 @@
 pub mod ui {
+    pub mod disk_usage_window;
     pub mod main_window;
     pub mod prop_window;
+    pub mod run_command_window;
+    pub mod source_viewer_window;
     pub mod ui_listener;
     pub use ui_listener::*;
     slint::include_modules!();
@@ -8,19 +11,37 @@ pub mod ui {
 
 //TODO: why pub
 pub mod callbacks;
+pub mod checksums;
 pub mod clipboard;
+pub mod columns;
+pub mod command_template;
 pub mod config;
 pub mod context_menus;
 pub mod core;
+pub mod custom_actions;
+pub mod desktop_entries;
+pub mod disk_usage;
 pub mod drives;
 pub mod file_properties;
 pub mod globals;
+pub mod icon_theme;
 pub mod keybinds;
 pub mod manage_open_with;
+pub mod mime;
+pub mod places;
+pub mod preview;
 pub mod progress_window;
 mod rename_window;
+pub mod run_command;
+pub mod search;
 pub mod sort;
+pub mod source_viewer;
+pub mod status_bar;
+pub mod thumbnails;
+pub mod udisks;
 pub mod utils;
+pub mod view_settings;
+pub mod xattr;
 
 //https://github.com/rust-lang/rfcs/issues/2407#issuecomment-385291238
 //Replace with https://github.com/rust-lang/rfcs/pull/3512
@@ -1,7 +1,10 @@
+use qdfm::callbacks::tabs;
 use qdfm::ui::*;
 use qdfm::ui;
 
 fn main() {
+    qdfm::utils::collation::init_locale();
+
     //Use winit
     let backend = i_slint_backend_winit::Backend::new().unwrap();
     slint::platform::set_platform(Box::new(backend)).unwrap();
@@ -17,6 +20,7 @@ fn main() {
             text: "".into(),
             selected: false,
             text_length: 1,
+            view_mode: tabs::VIEW_DETAILS,
         },
         false,
     ));
@@ -3,9 +3,11 @@ use std::rc::Rc;
 use slint::{ComponentHandle, Model, SharedString, VecModel, Weak};
 
 use crate::{
+    command_template::{launch, quote},
     config::Mapping,
-    core::run_command,
+    desktop_entries::{apps_for_mime, mimeapps_default, set_default_app},
     globals::{config_read, config_write},
+    mime::common_mime_type,
     ui::*,
     utils::{
         error_handling::{log_error_str, user_notice},
@@ -43,6 +45,70 @@ pub fn setup_manage_open_with(adp: ManageOpenWithAdapter, files: Rc<Vec<FileItem
             .into(),
         );
     }
+    //The desktop entries read the configuration too
+    drop(conf);
+
+    //System wide defaults need every file to have the same MIME type
+    if let Some(mime) = common_mime_type(&files) {
+        adp.set_mime_type(mime.into());
+        setup_system_apps(&adp);
+    }
+}
+
+///Lists the applications for the MIME type, with the current mimeapps.list default selected
+fn setup_system_apps(adp: &ManageOpenWithAdapter) {
+    let mime = adp.get_mime_type();
+    let apps = apps_for_mime(&mime);
+    let default = mimeapps_default(&mime);
+    adp.set_system_default_index(
+        default
+            .and_then(|d| apps.iter().position(|a| a.id == d.id))
+            .map_or(-1, |i| i as i32),
+    );
+    adp.set_system_app_index(adp.get_system_default_index().max(0));
+    adp.set_system_app_names(
+        Rc::new(VecModel::from(
+            apps.iter()
+                .map(|a| SharedString::from(&a.name))
+                .collect::<Vec<SharedString>>(),
+        ))
+        .into(),
+    );
+    adp.set_system_app_ids(
+        Rc::new(VecModel::from(
+            apps.iter()
+                .map(|a| SharedString::from(&a.id))
+                .collect::<Vec<SharedString>>(),
+        ))
+        .into(),
+    );
+    check_conflict(adp);
+}
+
+///Warns when qdfm's default mapping for the extension is not the mimeapps.list default,
+///since other programs will then open these files with something else.
+pub fn check_conflict(adp: &ManageOpenWithAdapter) {
+    let default_name = adp.get_default_mapping().name;
+    let mapping = adp.get_mappings().iter().find(|m| m.name == default_name);
+    let conflict = match (mapping, mimeapps_default(&adp.get_mime_type())) {
+        (Some(mapping), Some(app)) if mapping.cmd != app.command() && mapping.name != app.name => {
+            format!(
+                "qdfm opens these files with {} but the system default is {}",
+                mapping.name, app.name
+            )
+        }
+        _ => String::new(),
+    };
+    adp.set_conflict(conflict.into());
+}
+
+pub fn set_system_default(win: Rc<Weak<ManageOpenWithWindow>>, id: SharedString) {
+    let win = win.unwrap();
+    let adp = win.global::<ManageOpenWithAdapter>();
+    if let Err(e) = set_default_app(&adp.get_mime_type(), &id) {
+        log_error_str(&format!("Could not write mimeapps.list: {}", e));
+    }
+    setup_system_apps(&adp);
 }
 
 pub fn ok(win: Rc<Weak<ManageOpenWithWindow>>, ext: SharedString) {
@@ -71,19 +137,23 @@ pub fn cancel(win: Rc<Weak<ManageOpenWithWindow>>) {
 
 pub fn open_with(win: Rc<Weak<ManageOpenWithWindow>>, with_term: bool, files: Rc<Vec<FileItem>>) {
     if let Ok(file_chosen) = open_file_picker() {
-        for file in files.iter() {
-            let cmd = if !with_term {
-                file_chosen.to_owned() + " " + &file.file_name
+        let cmd = if !with_term {
+            quote(&file_chosen)
+        } else {
+            if let Some(term) = config_read().get::<String>("terminal") {
+                term + " " + &quote(&file_chosen)
             } else {
-                if let Some(term) = config_read().get::<String>("terminal") {
-                    term + " " + &file_chosen + " " + &file.file_name
-                } else {
-                    log_error_str("No valid terminal. Fix your config.");
-                    return;
-                }
-            };
-            run_command(&cmd);
-        }
+                log_error_str("No valid terminal. Fix your config.");
+                return;
+            }
+        };
+        launch(
+            &cmd,
+            &files
+                .iter()
+                .map(|f| f.path.to_string())
+                .collect::<Vec<String>>(),
+        );
         win.unwrap().hide().ok();
     } else {
         //TODO xdg not supported? Also parse error type
@@ -0,0 +1,317 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::OnceLock,
+};
+
+use crate::{core::get_file_mime_type, ui::FileItem, utils::xdg};
+
+/*
+ *  MIME type resolution (https://specifications.freedesktop.org/shared-mime-info-spec/latest/)
+ *
+ *  The file name is matched against the shared-mime-info globs first, which is cheap.
+ *  libmagic is only used to sniff the content when no glob matches.
+ * */
+
+pub const DIRECTORY: &str = "inode/directory";
+pub const UNKNOWN: &str = "application/octet-stream";
+
+pub struct Glob {
+    pub weight: u32,
+    pub mime: String,
+    pub pattern: String,
+    pub case_sensitive: bool,
+}
+
+#[derive(Default)]
+pub struct MimeDatabase {
+    globs: Vec<Glob>,
+    aliases: HashMap<String, String>,
+    parents: HashMap<String, Vec<String>>,
+    ///Icon names given by the "icons" and "generic-icons" files
+    icons: HashMap<String, String>,
+    generic_icons: HashMap<String, String>,
+}
+
+impl MimeDatabase {
+    ///Loads the database from the mime/ directory of every XDG data directory
+    pub fn load() -> Self {
+        let mut db = Self::default();
+        //Mime types with __NOGLOBS__ in a more important directory ignore the globs of the others
+        let mut no_globs: HashSet<String> = HashSet::new();
+        for dir in xdg::all_data_dirs() {
+            let dir = dir.join("mime");
+            if let Ok(content) = std::fs::read_to_string(dir.join("globs2")) {
+                let globs = parse_globs2(&content);
+                let cleared: Vec<String> = globs
+                    .iter()
+                    .filter(|g| is_no_globs(g))
+                    .map(|g| g.mime.clone())
+                    .collect();
+                db.globs.extend(
+                    globs
+                        .into_iter()
+                        .filter(|g| !is_no_globs(g) && !no_globs.contains(&g.mime)),
+                );
+                no_globs.extend(cleared);
+            }
+            if let Ok(content) = std::fs::read_to_string(dir.join("aliases")) {
+                for (alias, mime) in parse_pairs(&content) {
+                    db.aliases.entry(alias).or_insert(mime);
+                }
+            }
+            if let Ok(content) = std::fs::read_to_string(dir.join("subclasses")) {
+                for (mime, parent) in parse_pairs(&content) {
+                    let parents = db.parents.entry(mime).or_default();
+                    if !parents.contains(&parent) {
+                        parents.push(parent);
+                    }
+                }
+            }
+            if let Ok(content) = std::fs::read_to_string(dir.join("icons")) {
+                for (mime, icon) in parse_icons(&content) {
+                    db.icons.entry(mime).or_insert(icon);
+                }
+            }
+            if let Ok(content) = std::fs::read_to_string(dir.join("generic-icons")) {
+                for (mime, icon) in parse_icons(&content) {
+                    db.generic_icons.entry(mime).or_insert(icon);
+                }
+            }
+        }
+        db
+    }
+
+    pub fn from_globs(globs: Vec<Glob>) -> Self {
+        Self {
+            globs,
+            ..Default::default()
+        }
+    }
+
+    ///Returns the canonical name of a MIME type
+    pub fn unalias(&self, mime: &str) -> String {
+        self.aliases
+            .get(mime)
+            .cloned()
+            .unwrap_or_else(|| mime.to_string())
+    }
+
+    ///Finds the MIME type from the file name only.
+    ///The highest weight wins, then case sensitive patterns (*.C over *.c),
+    ///then the longest pattern (*.tar.gz over *.gz).
+    pub fn mime_for_name(&self, name: &str) -> Option<&str> {
+        let lower = name.to_lowercase();
+        self.globs
+            .iter()
+            .filter(|g| glob_match(&g.pattern, if g.case_sensitive { name } else { &lower }))
+            .max_by_key(|g| (g.weight, g.case_sensitive, g.pattern.len()))
+            .map(|g| g.mime.as_str())
+    }
+
+    ///Names of the icons for a MIME type, best first: the icon of the type, its name with
+    ///'-' instead of '/' ("text-plain"), then the generic icon ("text-x-generic")
+    pub fn icon_names(&self, mime: &str) -> Vec<String> {
+        let mime = self.unalias(mime);
+        let mut names: Vec<String> = self.icons.get(&mime).cloned().into_iter().collect();
+        names.push(mime.replace('/', "-"));
+        match self.generic_icons.get(&mime) {
+            Some(generic) => names.push(generic.clone()),
+            None => {
+                let media = mime.split('/').next().unwrap_or_default();
+                names.push(media.to_string() + "-x-generic");
+            }
+        }
+        names
+    }
+
+    ///The MIME type followed by all the types it is a subclass of, closest first.
+    ///All text/* types are implicitly text/plain.
+    pub fn with_parents(&self, mime: &str) -> Vec<String> {
+        let mut ret = vec![self.unalias(mime)];
+        let mut i = 0;
+        while i < ret.len() {
+            if let Some(parents) = self.parents.get(&ret[i]) {
+                for p in parents {
+                    let p = self.unalias(p);
+                    if !ret.contains(&p) {
+                        ret.push(p);
+                    }
+                }
+            }
+            i += 1;
+        }
+        if ret[0].starts_with("text/") && !ret.iter().any(|m| m == "text/plain") {
+            ret.push("text/plain".into());
+        }
+        ret
+    }
+}
+
+pub fn mime_db() -> &'static MimeDatabase {
+    static DB: OnceLock<MimeDatabase> = OnceLock::new();
+    DB.get_or_init(MimeDatabase::load)
+}
+
+///Lines look like "weight:mime/type:glob[:flags]".
+///Patterns that are not case sensitive are lowercased.
+pub fn parse_globs2(content: &str) -> Vec<Glob> {
+    content
+        .lines()
+        .filter(|l| !l.starts_with('#') && !l.trim().is_empty())
+        .filter_map(|l| {
+            let mut tokens = l.splitn(4, ':');
+            let weight = tokens.next()?.parse::<u32>().ok()?;
+            let mime = tokens.next()?.to_string();
+            let pattern = tokens.next()?;
+            let case_sensitive = tokens
+                .next()
+                .is_some_and(|flags| flags.split(',').any(|f| f == "cs"));
+            let pattern = if case_sensitive {
+                pattern.to_string()
+            } else {
+                pattern.to_lowercase()
+            };
+            Some(Glob {
+                weight,
+                mime,
+                pattern,
+                case_sensitive,
+            })
+        })
+        .collect()
+}
+
+//Patterns may have been lowercased, see parse_globs2
+fn is_no_globs(g: &Glob) -> bool {
+    g.pattern.eq_ignore_ascii_case("__NOGLOBS__")
+}
+
+///Lines of two space separated MIME types, used by the aliases and subclasses files
+fn parse_pairs(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|l| {
+            let (a, b) = l.trim().split_once(' ')?;
+            Some((a.to_string(), b.trim().to_string()))
+        })
+        .collect()
+}
+
+///Lines of the "icons" and "generic-icons" files look like "mime/type:icon-name"
+pub fn parse_icons(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|l| {
+            let (mime, icon) = l.trim().split_once(':')?;
+            Some((mime.to_string(), icon.to_string()))
+        })
+        .collect()
+}
+
+///Shell style glob matching with *, ? and [...] (with ranges and ! or ^ negation)
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    //Position of the last * and the name position it is currently matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        let step = match p.get(pi) {
+            Some('*') => {
+                star = Some((pi, ni));
+                pi += 1;
+                continue;
+            }
+            Some('?') => Some(pi + 1),
+            Some('[') => match_class(&p, pi, n[ni]),
+            Some(c) if *c == n[ni] => Some(pi + 1),
+            _ => None,
+        };
+        match (step, star) {
+            (Some(next), _) => {
+                pi = next;
+                ni += 1;
+            }
+            //Let the last * eat one more character and try again
+            (None, Some((star_pi, star_ni))) => {
+                star = Some((star_pi, star_ni + 1));
+                pi = star_pi + 1;
+                ni = star_ni + 1;
+            }
+            (None, None) => return false,
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+///Matches c against the [...] class starting at p[start].
+///Returns the position after the class if it matches.
+fn match_class(p: &[char], start: usize, c: char) -> Option<usize> {
+    let mut i = start + 1;
+    let negated = matches!(p.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        match p.get(i) {
+            //Unterminated, treat the [ literally
+            None => return if c == '[' { Some(start + 1) } else { None },
+            Some(']') if !first => break,
+            Some(lo) => {
+                if p.get(i + 1) == Some(&'-') && p.get(i + 2).is_some_and(|hi| *hi != ']') {
+                    matched |= (*lo..=p[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    matched |= *lo == c;
+                    i += 1;
+                }
+            }
+        }
+        first = false;
+    }
+    if matched != negated {
+        Some(i + 1)
+    } else {
+        None
+    }
+}
+
+///Resolves the MIME type of a file, from its name then its content
+pub fn mime_type_for_path(path: &Path, is_dir: bool) -> String {
+    if is_dir {
+        return DIRECTORY.into();
+    }
+    let db = mime_db();
+    if let Some(mime) = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| db.mime_for_name(n))
+    {
+        return db.unalias(mime);
+    }
+    match path.to_str().and_then(get_file_mime_type) {
+        //Parameters such as "; charset=..." are not part of the type
+        Some(mime) if mime.contains('/') => {
+            db.unalias(mime.split(';').next().unwrap_or(&mime).trim())
+        }
+        _ => UNKNOWN.into(),
+    }
+}
+
+pub fn mime_type_for_file(file: &FileItem) -> String {
+    mime_type_for_path(Path::new(file.path.as_str()), file.is_dir)
+}
+
+///Returns the MIME type shared by all the files, if any
+pub fn common_mime_type(files: &[FileItem]) -> Option<String> {
+    let mut iter = files.iter().map(mime_type_for_file);
+    let first = iter.next()?;
+    if iter.all(|m| m == first) {
+        Some(first)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::OnceLock,
+};
+
+use slint::{ComponentHandle, Image, Model, VecModel};
+
+use crate::{
+    command_template::file_uri,
+    context_menus::context_items::get_ci,
+    icon_theme,
+    ui::{main_window::run_with_main_window, *},
+    utils::{error_handling::log_error_str, percent_decode, xdg},
+};
+
+/*
+ *  The places of the sidebar: the home directory, the XDG user directories (user-dirs.dirs)
+ *  and the bookmarks shared with GTK applications (gtk-3.0/bookmarks).
+ *
+ *  Only the bookmarks can be edited. The file is read again before every change so that the
+ *  edits made by other applications are kept, and bookmarks that aren't local (sftp://...)
+ *  are written back as they were even though they aren't shown.
+ * */
+
+///The user directories shown, in order, with their icons
+const USER_DIRS: [(&str, &str); 6] = [
+    ("DESKTOP", "user-desktop"),
+    ("DOCUMENTS", "folder-documents"),
+    ("DOWNLOAD", "folder-download"),
+    ("MUSIC", "folder-music"),
+    ("PICTURES", "folder-pictures"),
+    ("VIDEOS", "folder-videos"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub uri: String,
+    pub label: Option<String>,
+}
+
+impl Bookmark {
+    pub fn from_path(path: &str) -> Self {
+        Bookmark {
+            uri: file_uri(path),
+            label: None,
+        }
+    }
+
+    ///The local path, None for remote locations
+    pub fn path(&self) -> Option<String> {
+        let encoded = self.uri.strip_prefix("file://")?;
+        String::from_utf8(percent_decode(encoded)).ok()
+    }
+
+    ///The label, or the name of the directory
+    pub fn display_name(&self) -> String {
+        if let Some(label) = &self.label {
+            return label.clone();
+        }
+        let path = self.path().unwrap_or_else(|| self.uri.clone());
+        match Path::new(&path).file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => path,
+        }
+    }
+}
+
+///Lines of the bookmarks file are "<uri>" or "<uri> <label>"
+pub fn parse_bookmarks(content: &str) -> Vec<Bookmark> {
+    content
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.is_empty())
+        .map(|l| match l.split_once(' ') {
+            Some((uri, label)) if !label.trim().is_empty() => Bookmark {
+                uri: uri.to_string(),
+                label: Some(label.trim().to_string()),
+            },
+            _ => Bookmark {
+                uri: l.trim().to_string(),
+                label: None,
+            },
+        })
+        .collect()
+}
+
+pub fn format_bookmarks(bookmarks: &[Bookmark]) -> String {
+    bookmarks
+        .iter()
+        .map(|b| match &b.label {
+            Some(label) => format!("{} {}\n", b.uri, label),
+            None => format!("{}\n", b.uri),
+        })
+        .collect()
+}
+
+///Parses user-dirs.dirs, eg. XDG_MUSIC_DIR="$HOME/Music", into "MUSIC" -> "/home/user/Music".
+///Directories set to the home directory are disabled and left out.
+pub fn parse_user_dirs(content: &str, home: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| {
+            let (key, value) = l.split_once('=')?;
+            let name = key.trim().strip_prefix("XDG_")?.strip_suffix("_DIR")?;
+            let value = value.trim().trim_matches('"');
+            let path = if value == "$HOME" {
+                home.to_string()
+            } else if let Some(rest) = value.strip_prefix("$HOME/") {
+                format!("{}/{}", home.trim_end_matches('/'), rest)
+            } else if value.starts_with('/') {
+                value.to_string()
+            } else {
+                return None;
+            };
+            let path = path.trim_end_matches('/').to_string();
+            (path != home.trim_end_matches('/')).then(|| (name.to_string(), path))
+        })
+        .collect()
+}
+
+fn home() -> String {
+    std::env::var("HOME").unwrap_or_default()
+}
+
+fn bookmarks_path() -> PathBuf {
+    xdg::config_home().join("gtk-3.0").join("bookmarks")
+}
+
+fn read_bookmarks() -> Vec<Bookmark> {
+    std::fs::read_to_string(bookmarks_path())
+        .map(|c| parse_bookmarks(&c))
+        .unwrap_or_default()
+}
+
+fn write_bookmarks(bookmarks: &[Bookmark]) -> Result<(), std::io::Error> {
+    let path = bookmarks_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    //Through a symlink the target is replaced, not the link
+    let path = path.canonicalize().unwrap_or(path);
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, format_bookmarks(bookmarks))?;
+    std::fs::rename(tmp, path)
+}
+
+///The user directories that exist, in the order of USER_DIRS, with their icons.
+///They are read once, they only change when the user logs in again.
+fn user_dirs() -> &'static Vec<(String, &'static str)> {
+    static USER_DIR_PATHS: OnceLock<Vec<(String, &'static str)>> = OnceLock::new();
+    USER_DIR_PATHS.get_or_init(|| {
+        let content =
+            std::fs::read_to_string(xdg::config_home().join("user-dirs.dirs")).unwrap_or_default();
+        let dirs = parse_user_dirs(&content, &home());
+        USER_DIRS
+            .iter()
+            .filter_map(|(name, icon)| {
+                let path = dirs.get(*name)?;
+                Path::new(path).is_dir().then(|| (path.clone(), *icon))
+            })
+            .collect()
+    })
+}
+
+pub fn get_places() -> Rc<VecModel<SidebarItem>> {
+    let places: Rc<VecModel<SidebarItem>> = Rc::new(VecModel::default());
+    places.push(SidebarItem {
+        text: "Home".into(),
+        internal_path: home().into(),
+        editable: false,
+        device: "".into(),
+        mounted: true,
+        usage_pct: -1,
+        usage_level: 0,
+        usage_text: "".into(),
+    });
+    for (path, _) in user_dirs() {
+        places.push(SidebarItem {
+            text: Bookmark::from_path(path).display_name().into(),
+            internal_path: path.into(),
+            editable: false,
+            device: "".into(),
+            mounted: true,
+            usage_pct: -1,
+            usage_level: 0,
+            usage_text: "".into(),
+        });
+    }
+    for bookmark in read_bookmarks() {
+        if let Some(path) = bookmark.path() {
+            places.push(SidebarItem {
+                text: bookmark.display_name().into(),
+                internal_path: path.into(),
+                editable: true,
+                device: "".into(),
+                mounted: true,
+                usage_pct: -1,
+                usage_level: 0,
+                usage_text: "".into(),
+            });
+        }
+    }
+    places
+}
+
+///The icon of a place from the icon theme (the home and user directories have their own)
+pub fn place_icon(path: &str, size: u32) -> Image {
+    let name = if path == home() {
+        "user-home"
+    } else {
+        user_dirs()
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, icon)| *icon)
+            .unwrap_or("folder")
+    };
+    icon_theme::named_icon(name, size)
+}
+
+///Highlights the place of the current directory
+pub fn select_current(mw: &MainWindow, path: &str) {
+    let sidebar_items = mw.global::<SidebarItems>();
+    let selected = sidebar_items
+        .get_place_list()
+        .iter()
+        .position(|p| p.internal_path == path);
+    sidebar_items.set_selected_place(selected.map(|i| i as i32).unwrap_or(-1));
+}
+
+fn refresh(mw: &MainWindow) {
+    let sidebar_items = mw.global::<SidebarItems>();
+    sidebar_items.set_place_list(get_places().into());
+    sidebar_items.set_renaming_place(-1);
+    let current = mw
+        .global::<TabsAdapter>()
+        .invoke_get_current_tab()
+        .internal_path;
+    select_current(mw, &current);
+}
+
+///Changes the bookmarks on disk, then shows them
+fn modify_bookmarks(mw: &MainWindow, f: impl FnOnce(&mut Vec<Bookmark>)) {
+    let mut bookmarks = read_bookmarks();
+    f(&mut bookmarks);
+    if let Err(e) = write_bookmarks(&bookmarks) {
+        log_error_str(&format!("Could not save the bookmarks: {}", e));
+    }
+    refresh(mw);
+}
+
+///The path of an editable place of the sidebar
+fn bookmark_path(mw: &MainWindow, index: i32) -> Option<String> {
+    let place = mw
+        .global::<SidebarItems>()
+        .get_place_list()
+        .row_data(usize::try_from(index).ok()?)?;
+    place.editable.then(|| place.internal_path.to_string())
+}
+
+fn position_of(bookmarks: &[Bookmark], path: &str) -> Option<usize> {
+    bookmarks
+        .iter()
+        .position(|b| b.path().as_deref() == Some(path))
+}
+
+///Bookmarks the directory of the current tab
+pub fn add_current() {
+    run_with_main_window(|mw| {
+        let path = mw
+            .global::<TabsAdapter>()
+            .invoke_get_current_tab()
+            .internal_path
+            .to_string();
+        modify_bookmarks(mw, |bookmarks| {
+            if position_of(bookmarks, &path).is_none() {
+                bookmarks.push(Bookmark::from_path(&path));
+            }
+        });
+    });
+}
+
+pub fn remove(index: i32) {
+    run_with_main_window(move |mw| {
+        let Some(path) = bookmark_path(mw, index) else {
+            return;
+        };
+        modify_bookmarks(mw, |bookmarks| {
+            bookmarks.retain(|b| b.path().as_deref() != Some(path.as_str()))
+        });
+    });
+}
+
+///Shows the place's name in a text field
+pub fn start_rename(index: i32) {
+    run_with_main_window(move |mw| {
+        if bookmark_path(mw, index).is_some() {
+            mw.global::<SidebarItems>().set_renaming_place(index);
+        }
+    });
+}
+
+///An empty name goes back to the name of the directory
+pub fn rename(index: i32, name: String) {
+    run_with_main_window(move |mw| {
+        let Some(path) = bookmark_path(mw, index) else {
+            return;
+        };
+        modify_bookmarks(mw, |bookmarks| {
+            if let Some(i) = position_of(bookmarks, &path) {
+                let name = name.trim();
+                bookmarks[i].label = (!name.is_empty()).then(|| name.to_string());
+            }
+        });
+    });
+}
+
+///Moves the bookmark at from to the position of the one at to
+pub fn move_place(from: i32, to: i32) {
+    run_with_main_window(move |mw| {
+        let (Some(from), Some(to)) = (bookmark_path(mw, from), bookmark_path(mw, to)) else {
+            return;
+        };
+        modify_bookmarks(mw, |bookmarks| {
+            let (Some(i), Some(j)) = (position_of(bookmarks, &from), position_of(bookmarks, &to))
+            else {
+                return;
+            };
+            let bookmark = bookmarks.remove(i);
+            bookmarks.insert(j, bookmark);
+        });
+    });
+}
+
+pub fn show_context_menu(index: i32, x: f32, y: f32) {
+    run_with_main_window(move |mw| {
+        let mut menu: Vec<ContextItem> = Vec::new();
+        if bookmark_path(mw, index).is_some() {
+            for key in ["rename_place", "remove_place"] {
+                let mut item = get_ci(key);
+                item.internal_id = index;
+                menu.push(item);
+            }
+        }
+        menu.push(get_ci("add_place"));
+
+        let ctx_adapter = mw.global::<ContextAdapter>();
+        ctx_adapter.set_items(Rc::new(VecModel::from(menu)).into());
+        ctx_adapter.set_x_pos(x + 1f32);
+        ctx_adapter.set_y_pos(y + 1f32);
+    });
+}
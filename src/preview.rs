@@ -0,0 +1,256 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+};
+
+use slint::{ComponentHandle, Image, Rgba8Pixel, SharedPixelBuffer, SharedString, VecModel};
+
+use crate::{
+    callbacks::filemanager::selection,
+    core::{get_file_encoding, get_file_magic_type},
+    globals::config_read,
+    sort::SortOptions,
+    thumbnails,
+    ui::{main_window::run_with_main_window, *},
+    utils::types::format_size,
+};
+
+/*
+ *  The preview pane shows the selected file: images, the start of text files, the children of
+ *  directories, or what libmagic says the file is for everything else.
+ *
+ *  Previews are loaded in a thread. Each selection change starts a new generation, a loader
+ *  stops as soon as it sees that its generation is old and its result is dropped.
+ * */
+
+///Images are scaled down to fit in this size
+const IMAGE_SIZE: u32 = 512;
+///Larger images aren't decoded
+const MAX_IMAGE_FILE_SIZE: u64 = 50 * 1024 * 1024;
+const MAX_CHILDREN: usize = 1000;
+
+//Kinds of PreviewAdapter
+const KIND_NONE: i32 = 0;
+const KIND_LOADING: i32 = 1;
+const KIND_IMAGE: i32 = 2;
+const KIND_TEXT: i32 = 3;
+const KIND_DIRECTORY: i32 = 4;
+const KIND_OTHER: i32 = 5;
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn is_current(generation: u64) -> bool {
+    GENERATION.load(Ordering::SeqCst) == generation
+}
+
+pub enum Preview {
+    Image {
+        pixels: SharedPixelBuffer<Rgba8Pixel>,
+        details: String,
+    },
+    Text {
+        text: String,
+        details: String,
+    },
+    Directory {
+        children: Vec<String>,
+        details: String,
+    },
+    ///The libmagic description
+    Other(String),
+}
+
+///Decodes the start of a text file. libmagic names the encoding (eg. "utf-8", "utf-16le",
+///"iso-8859-1"), unknown encodings are read as UTF-8.
+///A character cut at the end of the bytes is dropped.
+pub fn decode_text(bytes: &[u8], encoding: &str) -> String {
+    let text = match encoding {
+        "utf-16le" | "utf-16be" => {
+            let units = bytes.chunks_exact(2).map(|c| {
+                if encoding == "utf-16le" {
+                    u16::from_le_bytes([c[0], c[1]])
+                } else {
+                    u16::from_be_bytes([c[0], c[1]])
+                }
+            });
+            char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+        "iso-8859-1" => bytes.iter().map(|b| *b as char).collect(),
+        _ => match std::str::from_utf8(bytes) {
+            Ok(s) => s.to_string(),
+            Err(e) if e.error_len().is_none() => {
+                String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned()
+            }
+            Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+        },
+    };
+    match text.strip_prefix('\u{feff}') {
+        Some(t) => t.to_string(),
+        None => text,
+    }
+}
+
+fn load_image(path: &str, size: u64) -> Option<Preview> {
+    let img = image::open(path).ok()?;
+    let (width, height) = (img.width(), img.height());
+    let img = if width > IMAGE_SIZE || height > IMAGE_SIZE {
+        img.thumbnail(IMAGE_SIZE, IMAGE_SIZE)
+    } else {
+        img
+    };
+    let rgba = img.to_rgba8();
+    Some(Preview::Image {
+        pixels: SharedPixelBuffer::clone_from_slice(rgba.as_raw(), rgba.width(), rgba.height()),
+        details: format!("{}×{}, {}", width, height, format_size(size, false)),
+    })
+}
+
+fn load_text(path: &str, encoding: &str, size: u64) -> Option<Preview> {
+    let max_kb: u64 = config_read().get("preview_text_kb").unwrap_or(16);
+    let mut bytes = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(max_kb * 1024)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    let mut text = decode_text(&bytes, encoding);
+    if size > bytes.len() as u64 {
+        text.push_str("\n…");
+    }
+    Some(Preview::Text {
+        text,
+        details: format!("Text ({}), {}", encoding, format_size(size, false)),
+    })
+}
+
+fn load_directory(path: &str, generation: u64) -> Option<Preview> {
+    let mut children: Vec<(bool, String)> = Vec::new();
+    for entry in fs::read_dir(path).ok()?.flatten() {
+        if !is_current(generation) {
+            return None;
+        }
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        children.push((is_dir, entry.file_name().to_string_lossy().into_owned()));
+    }
+    let total = children.len();
+    let options = SortOptions::from_config();
+    children.sort_by(|(l_dir, l), (r_dir, r)| {
+        r_dir.cmp(l_dir).then_with(|| options.compare_names(l, r))
+    });
+    Some(Preview::Directory {
+        children: children
+            .into_iter()
+            .take(MAX_CHILDREN)
+            .map(|(is_dir, name)| if is_dir { name + "/" } else { name })
+            .collect(),
+        details: match total {
+            0 => "Empty directory".into(),
+            1 => "1 item".into(),
+            n if n > MAX_CHILDREN => format!("{} items, showing the first {}", n, MAX_CHILDREN),
+            n => format!("{} items", n),
+        },
+    })
+}
+
+///Loads the preview of the file, None if the selection changed in the meantime
+pub fn load(path: &str, generation: u64) -> Option<Preview> {
+    let metadata = fs::metadata(path).ok();
+    if metadata.as_ref().is_some_and(|m| m.is_dir()) {
+        return load_directory(path, generation);
+    }
+    let size = metadata.map(|m| m.len()).unwrap_or(0);
+    if size <= MAX_IMAGE_FILE_SIZE && thumbnails::can_thumbnail(path) {
+        if let Some(preview) = load_image(path, size) {
+            return Some(preview);
+        }
+    }
+    if !is_current(generation) {
+        return None;
+    }
+    let encoding = get_file_encoding(path);
+    if encoding != "binary" && !encoding.starts_with("Unknown") {
+        if let Some(preview) = load_text(path, &encoding, size) {
+            return Some(preview);
+        }
+    }
+    if !is_current(generation) {
+        return None;
+    }
+    Some(Preview::Other(get_file_magic_type(path)))
+}
+
+fn show(mw: &MainWindow, preview: Preview) {
+    let adp = mw.global::<PreviewAdapter>();
+    match preview {
+        Preview::Image { pixels, details } => {
+            adp.set_image(Image::from_rgba8(pixels));
+            adp.set_details(details.into());
+            adp.set_kind(KIND_IMAGE);
+        }
+        Preview::Text { text, details } => {
+            adp.set_text(text.into());
+            adp.set_details(details.into());
+            adp.set_kind(KIND_TEXT);
+        }
+        Preview::Directory { children, details } => {
+            let children: Vec<SharedString> = children.into_iter().map(Into::into).collect();
+            adp.set_children(Rc::new(VecModel::from(children)).into());
+            adp.set_details(details.into());
+            adp.set_kind(KIND_DIRECTORY);
+        }
+        Preview::Other(description) => {
+            adp.set_details(description.into());
+            adp.set_kind(KIND_OTHER);
+        }
+    }
+}
+
+///Shows the preview of the selected file, call whenever the selection changes
+pub fn selection_changed() {
+    run_with_main_window(|mw| {
+        let adp = mw.global::<PreviewAdapter>();
+        if !adp.get_shown() {
+            return;
+        }
+        //Stops the previous loader
+        let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        let Some(file) = selection::get_selected_file() else {
+            adp.set_kind(KIND_NONE);
+            return;
+        };
+        adp.set_title(file.file_name.clone());
+        adp.set_kind(KIND_LOADING);
+        let path = file.path.to_string();
+        thread::spawn(move || {
+            let Some(preview) = load(&path, generation) else {
+                return;
+            };
+            run_with_main_window(move |mw| {
+                if is_current(generation) {
+                    show(mw, preview);
+                }
+            });
+        });
+    });
+}
+
+pub fn toggle() {
+    run_with_main_window(|mw| {
+        let adp = mw.global::<PreviewAdapter>();
+        adp.set_shown(!adp.get_shown());
+        if adp.get_shown() {
+            selection_changed();
+        } else {
+            //Drops the image and text of the last preview
+            GENERATION.fetch_add(1, Ordering::SeqCst);
+            adp.set_kind(KIND_NONE);
+            adp.set_image(Image::default());
+            adp.set_text("".into());
+        }
+    });
+}
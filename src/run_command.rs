@@ -0,0 +1,193 @@
+use std::{
+    io::Read,
+    os::unix::process::{CommandExt, ExitStatusExt},
+    path::PathBuf,
+    process::{Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use slint::ComponentHandle;
+use syscalls::{syscall2, Sysno};
+
+use crate::{
+    callbacks::filemanager::selection,
+    ui::{self, main_window::run_with_main_window, run_command_window::*, *},
+};
+
+/*
+ *  "Run command…": runs a shell command in the current directory, with the selected files
+ *  as its arguments ("$@"), and shows its output.
+ * */
+
+const MAX_OUTPUT: usize = 256 * 1024;
+const SIGTERM: usize = 15;
+
+struct RunState {
+    cwd: PathBuf,
+    files: Vec<String>,
+}
+
+static STATE: Mutex<Option<RunState>> = Mutex::new(None);
+//Incremented for every run, older runs stop updating the window
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+//Process group of the running command, 0 if none
+static RUNNING_PID: AtomicU32 = AtomicU32::new(0);
+
+///Shows the window for the current tab's directory and the selected files
+pub fn show() {
+    let files: Vec<String> = selection::selected_files_clone()
+        .iter()
+        .map(|f| f.path.to_string())
+        .collect();
+    run_with_main_window(move |mw| {
+        let cwd = mw
+            .global::<TabsAdapter>()
+            .invoke_get_current_tab()
+            .internal_path
+            .to_string();
+        let file_count = files.len() as i32;
+        if let Ok(mut state) = STATE.lock() {
+            *state = Some(RunState {
+                cwd: PathBuf::from(&cwd),
+                files,
+            });
+        }
+        run_with_run_command_window(move |w| {
+            let adp = w.global::<RunCommandAdapter>();
+            adp.set_cwd(cwd.into());
+            adp.set_file_count(file_count);
+            w.show().ok();
+        });
+    });
+}
+
+fn format_status(status: &ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("Exited with status {}", code),
+        (None, Some(signal)) => format!("Killed by signal {}", signal),
+        _ => "Exited".into(),
+    }
+}
+
+//Appends to the output, dropping the oldest text past MAX_OUTPUT
+fn append_output(output: &Mutex<String>, data: &[u8]) {
+    if let Ok(mut output) = output.lock() {
+        output.push_str(&String::from_utf8_lossy(data));
+        if output.len() > MAX_OUTPUT {
+            let mut cut = output.len() - MAX_OUTPUT;
+            while !output.is_char_boundary(cut) {
+                cut += 1;
+            }
+            output.drain(..cut);
+        }
+    }
+}
+
+fn read_to_output(mut source: impl Read + Send + 'static, output: Arc<Mutex<String>>) {
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = source.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            append_output(&output, &buf[..n]);
+        }
+    });
+}
+
+fn update_window(generation: u64, output: String, status: String, running: bool) {
+    run_with_run_command_window(move |w| {
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let adp = w.global::<RunCommandAdapter>();
+        adp.set_output(output.into());
+        adp.set_status(status.into());
+        adp.set_running(running);
+    });
+}
+
+pub fn run(cmd: String) {
+    if RUNNING_PID.load(Ordering::SeqCst) != 0 {
+        return;
+    }
+    let Some((cwd, files)) = STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.as_ref().map(|s| (s.cwd.clone(), s.files.clone())))
+    else {
+        return;
+    };
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let output = Arc::new(Mutex::new(format!("$ {}\n", cmd)));
+
+    //Its own process group, so that stopping it also stops pipelines and children
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(&cmd)
+        .arg("sh")
+        .args(&files)
+        .current_dir(&cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            let output = output.lock().map(|o| o.clone()).unwrap_or_default();
+            update_window(generation, output, format!("Could not run: {}", e), false);
+            return;
+        }
+    };
+    RUNNING_PID.store(child.id(), Ordering::SeqCst);
+    if let Some(stdout) = child.stdout.take() {
+        read_to_output(stdout, output.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        read_to_output(stderr, output.clone());
+    }
+
+    thread::spawn(move || {
+        let snapshot = || output.lock().map(|o| o.clone()).unwrap_or_default();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break format_status(&status),
+                Ok(None) => {
+                    update_window(generation, snapshot(), "Running…".into(), true);
+                    thread::sleep(Duration::from_millis(200));
+                }
+                Err(e) => break format!("Could not wait for the command: {}", e),
+            }
+        };
+        RUNNING_PID.store(0, Ordering::SeqCst);
+        //Let the readers drain what is left in the pipes
+        thread::sleep(Duration::from_millis(100));
+        update_window(generation, snapshot(), status, false);
+        //The command may have changed files in the directory
+        ui::send_message(ui::UIMessage::Refresh);
+    });
+}
+
+pub fn stop() {
+    let pid = RUNNING_PID.load(Ordering::SeqCst);
+    if pid != 0 {
+        //A negative pid signals the whole process group
+        unsafe {
+            syscall2(Sysno::kill, (-(pid as isize)) as usize, SIGTERM).ok();
+        }
+    }
+}
+
+pub fn close() {
+    stop();
+    run_with_run_command_window(|w| {
+        w.hide().ok();
+    });
+}
@@ -0,0 +1,608 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, Metadata},
+    io::Read,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc::{channel, RecvTimeoutError},
+        Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{Local, NaiveDate};
+use regex::{bytes, Regex, RegexBuilder};
+use slint::{ComponentHandle, Model, VecModel};
+
+use crate::{
+    callbacks::{filemanager::selection, tabs},
+    columns,
+    core::{file_item, get_all_users},
+    globals::config_read,
+    mime::glob_match,
+    sort::{self, call_current_sort},
+    status_bar,
+    ui::{self, main_window::run_with_main_window, *},
+    view_settings,
+};
+
+/*
+ *  Recursive search under the directory of the current tab. The results replace the files of
+ *  the tab as they are found, so that they can be opened, copied, dragged... like any file, and
+ *  a Location column shows where they are. Going to another directory ends the search.
+ *
+ *  Directories are walked by several threads taking them from a shared queue. The matches are
+ *  sent to a thread adding them to the view in batches, a new search or closing the bar starts
+ *  a new generation and the older walk stops.
+ * */
+
+///How often the results found are added to the view
+const BATCH_INTERVAL: Duration = Duration::from_millis(150);
+///Content is read in chunks of this size, a file stops being searched at the first chunk
+///with a NUL byte (binary)
+const CONTENT_CHUNK_LEN: usize = 64 * 1024;
+///The end of a line longer than a chunk kept for the next one, so that a match across the
+///chunks is found if it's shorter than this
+const CONTENT_OVERLAP: usize = 4096;
+
+pub const KIND_ANY: i32 = 0;
+pub const KIND_FILE: i32 = 1;
+pub const KIND_DIR: i32 = 2;
+pub const KIND_LINK: i32 = 3;
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+//The query of the results shown, None when the tab shows its directory
+static ACTIVE: Mutex<Option<SearchQuery>> = Mutex::new(None);
+//A file to select once its directory is shown, see reveal_selected
+static REVEAL: Mutex<Option<String>> = Mutex::new(None);
+
+fn is_current(generation: u64) -> bool {
+    GENERATION.load(Ordering::SeqCst) == generation
+}
+
+pub enum NamePattern {
+    ///Matched against the whole name, without case
+    Glob(String),
+    Regex(Regex),
+}
+
+impl NamePattern {
+    ///A glob without wildcards matches the names containing it
+    pub fn new(pattern: &str, regex: bool) -> Result<Self, String> {
+        if regex {
+            return RegexBuilder::new(pattern)
+                .case_insensitive(!pattern.chars().any(char::is_uppercase))
+                .build()
+                .map(NamePattern::Regex)
+                .map_err(|e| format!("Invalid name pattern: {}", e));
+        }
+        let pattern = pattern.to_lowercase();
+        if pattern.contains(['*', '?', '[']) {
+            Ok(NamePattern::Glob(pattern))
+        } else {
+            Ok(NamePattern::Glob(format!("*{}*", pattern)))
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            NamePattern::Glob(glob) => glob_match(glob, &name.to_lowercase()),
+            NamePattern::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+///What the files found have to match, the fields left empty match everything
+pub struct Criteria {
+    pub name: Option<NamePattern>,
+    pub content: Option<bytes::Regex>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    ///Seconds since the epoch
+    pub modified_after: Option<i64>,
+    pub modified_before: Option<i64>,
+    ///One of KIND_*
+    pub kind: i32,
+    pub owner: Option<u32>,
+    ///Whether names starting with a dot are searched, and walked into
+    pub hidden: bool,
+}
+
+///Parses a size like "512", "10K", "1.5 MiB" or "2GB" (powers of 1024, like the sizes shown)
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
+
+///Parses a date, "2024-01-31" (the start of the day, local time), or a time before now like
+///"12h", "7d" or "2w"
+pub fn parse_date(s: &str, now: i64) -> Option<i64> {
+    let s = s.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)?
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|d| d.timestamp());
+    }
+    let unit = match s.chars().last()? {
+        'h' => 3600,
+        'd' => 86400,
+        'w' => 7 * 86400,
+        _ => return None,
+    };
+    let count: i64 = s[..s.len() - 1].trim().parse().ok()?;
+    Some(now - count * unit)
+}
+
+///A user name or a uid
+pub fn parse_owner(s: &str, users: &HashMap<u32, String>) -> Option<u32> {
+    let s = s.trim();
+    s.parse().ok().or_else(|| {
+        users
+            .iter()
+            .find(|(_, name)| name.as_str() == s)
+            .map(|(uid, _)| *uid)
+    })
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+impl Criteria {
+    ///The criteria of the search bar, or the message to show for the first invalid field.
+    ///The content is searched as text unless regex is set, like the name.
+    pub fn from_query(query: &SearchQuery, hidden: bool) -> Result<Self, String> {
+        let optional = |s: &str| (!s.trim().is_empty()).then(|| s.trim().to_string());
+        let name = optional(&query.name)
+            .map(|n| NamePattern::new(&n, query.regex))
+            .transpose()?;
+        let content = optional(&query.content)
+            .map(|c| {
+                let pattern = if query.regex {
+                    c.clone()
+                } else {
+                    regex::escape(&c)
+                };
+                bytes::RegexBuilder::new(&pattern)
+                    .case_insensitive(!c.chars().any(char::is_uppercase))
+                    .build()
+                    .map_err(|e| format!("Invalid content pattern: {}", e))
+            })
+            .transpose()?;
+        let size = |s: &str, field: &str| {
+            optional(s)
+                .map(|s| parse_size(&s).ok_or(format!("Invalid {} size: {}", field, s)))
+                .transpose()
+        };
+        let date = |s: &str, field: &str| {
+            optional(s)
+                .map(|s| parse_date(&s, now()).ok_or(format!("Invalid {} date: {}", field, s)))
+                .transpose()
+        };
+        let owner = optional(&query.owner)
+            .map(|o| {
+                parse_owner(&o, &get_all_users().unwrap_or_default())
+                    .ok_or(format!("Unknown owner: {}", o))
+            })
+            .transpose()?;
+        Ok(Criteria {
+            name,
+            content,
+            min_size: size(&query.min_size, "minimum")?,
+            max_size: size(&query.max_size, "maximum")?,
+            modified_after: date(&query.modified_after, "after")?,
+            modified_before: date(&query.modified_before, "before")?,
+            kind: query.kind,
+            owner,
+            hidden,
+        })
+    }
+
+    ///Whether the entry matches, meta is its metadata without the links followed.
+    ///The content is read last, only if everything else matches.
+    pub fn matches(
+        &self,
+        path: &Path,
+        name: &str,
+        meta: &Metadata,
+        cancelled: &dyn Fn() -> bool,
+    ) -> bool {
+        let file_type = meta.file_type();
+        let kind_matches = match self.kind {
+            KIND_FILE => file_type.is_file(),
+            KIND_DIR => file_type.is_dir(),
+            KIND_LINK => file_type.is_symlink(),
+            _ => true,
+        };
+        let has_size = self.min_size.is_some() || self.max_size.is_some();
+        let modified = meta.mtime();
+        kind_matches
+            && self.name.as_ref().is_none_or(|n| n.matches(name))
+            //Directories have no size of their own
+            && (!has_size || !file_type.is_dir())
+            && self.min_size.is_none_or(|min| meta.len() >= min)
+            && self.max_size.is_none_or(|max| meta.len() <= max)
+            && self.modified_after.is_none_or(|after| modified >= after)
+            && self.modified_before.is_none_or(|before| modified < before)
+            && self.owner.is_none_or(|uid| meta.uid() == uid)
+            && self
+                .content
+                .as_ref()
+                .is_none_or(|c| file_type.is_file() && file_contains(path, c, cancelled))
+    }
+}
+
+///Whether a line of the file matches, binary files never do.
+///Lines longer than a chunk are searched in windows overlapping by CONTENT_OVERLAP.
+pub fn file_contains(path: &Path, pattern: &bytes::Regex, cancelled: &dyn Fn() -> bool) -> bool {
+    let Ok(mut file) = File::open(path) else {
+        return false;
+    };
+    let mut buf = vec![0u8; CONTENT_CHUNK_LEN];
+    //The line continued in the next chunk
+    let mut line = Vec::new();
+    loop {
+        let read = match file.read(&mut buf) {
+            Ok(0) => return !line.is_empty() && pattern.is_match(&line),
+            Ok(read) => read,
+            Err(_) => return false,
+        };
+        let mut chunk = &buf[..read];
+        if chunk.contains(&0) || cancelled() {
+            return false;
+        }
+        while let Some(end) = chunk.iter().position(|b| *b == b'\n') {
+            line.extend_from_slice(&chunk[..=end]);
+            if pattern.is_match(&line) {
+                return true;
+            }
+            line.clear();
+            chunk = &chunk[end + 1..];
+        }
+        line.extend_from_slice(chunk);
+        if line.len() > CONTENT_CHUNK_LEN {
+            if pattern.is_match(&line) {
+                return true;
+            }
+            line.drain(..line.len() - CONTENT_OVERLAP);
+        }
+    }
+}
+
+///Directories left to read, and the number of threads reading one
+struct WalkQueue {
+    dirs: Mutex<(Vec<PathBuf>, usize)>,
+    changed: Condvar,
+}
+
+impl WalkQueue {
+    ///The next directory to read, None once all of them were read
+    fn next(&self, cancelled: &dyn Fn() -> bool) -> Option<PathBuf> {
+        let mut state = self.dirs.lock().ok()?;
+        loop {
+            if cancelled() {
+                return None;
+            }
+            if let Some(dir) = state.0.pop() {
+                state.1 += 1;
+                return Some(dir);
+            }
+            //Nothing left, and no thread can add more
+            if state.1 == 0 {
+                return None;
+            }
+            state = self.changed.wait(state).ok()?;
+        }
+    }
+
+    fn push(&self, dir: PathBuf) {
+        if let Ok(mut state) = self.dirs.lock() {
+            state.0.push(dir);
+            self.changed.notify_one();
+        }
+    }
+
+    fn done(&self) {
+        if let Ok(mut state) = self.dirs.lock() {
+            state.1 -= 1;
+            if state.1 == 0 {
+                self.changed.notify_all();
+            }
+        }
+    }
+}
+
+///Walks the directories under root in parallel, calling found with the paths matching.
+///Links to directories aren't followed.
+pub fn walk(
+    root: &Path,
+    criteria: &Criteria,
+    cancelled: &(dyn Fn() -> bool + Sync),
+    found: &(dyn Fn(&Path) + Sync),
+) {
+    let queue = WalkQueue {
+        dirs: Mutex::new((vec![root.to_path_buf()], 0)),
+        changed: Condvar::new(),
+    };
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                while let Some(dir) = queue.next(cancelled) {
+                    for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                        if cancelled() {
+                            break;
+                        }
+                        let name = entry.file_name();
+                        let name = name.to_string_lossy();
+                        let Ok(meta) = entry.metadata() else {
+                            continue;
+                        };
+                        if !criteria.hidden && name.starts_with('.') {
+                            continue;
+                        }
+                        let path = entry.path();
+                        if meta.is_dir() {
+                            queue.push(path.clone());
+                        }
+                        if criteria.matches(&path, &name, &meta, cancelled) {
+                            found(&path);
+                        }
+                    }
+                    queue.done();
+                }
+                //Wakes the threads waiting for directories that won't come
+                queue.changed.notify_all();
+            });
+        }
+    });
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.lock().is_ok_and(|a| a.is_some())
+}
+
+pub fn toggle() {
+    run_with_main_window(|mw| {
+        let adp = mw.global::<SearchAdapter>();
+        if adp.get_shown() {
+            close();
+        } else {
+            adp.set_shown(true);
+        }
+    });
+}
+
+///Shows the results in the file list, with the Location column
+fn show_results_view(mw: &MainWindow) {
+    let mut settings = view_settings::current(mw);
+    if !settings.has_column("location") {
+        settings.toggle_column("location");
+    }
+    view_settings::apply_headers(mw, &settings);
+    let fm = mw.global::<FileManager>();
+    fm.set_files(Rc::new(VecModel::<FileItem>::default()).into());
+    fm.set_files_len(0);
+    selection::clear_selection();
+}
+
+fn add_results(mw: &MainWindow, results: Vec<FileItem>) {
+    //A sort of the files shown so far would replace them without these, the results are
+    //sorted once the search ends
+    sort::cancel_pending();
+    let fm = mw.global::<FileManager>();
+    let files = fm.get_files();
+    match files.as_any().downcast_ref::<VecModel<FileItem>>() {
+        Some(model) => model.extend(results),
+        None => {
+            let all: Vec<FileItem> = files.iter().chain(results).collect();
+            fm.set_files(Rc::new(VecModel::from(all)).into());
+        }
+    }
+    fm.set_files_len(fm.get_files().row_count() as i32);
+}
+
+fn found_text(count: usize) -> String {
+    format!("{} found", count)
+}
+
+///Searches the directory of the current tab
+pub fn start(query: SearchQuery) {
+    run_with_main_window(move |mw| {
+        let root = mw
+            .global::<TabsAdapter>()
+            .invoke_get_current_tab()
+            .internal_path
+            .to_string();
+        let adp = mw.global::<SearchAdapter>();
+        let criteria = match Criteria::from_query(&query, view_settings::current(mw).show_hidden) {
+            Ok(c) => c,
+            Err(e) => {
+                adp.set_status(e.into());
+                return;
+            }
+        };
+        let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Ok(mut active) = ACTIVE.lock() {
+            *active = Some(query);
+        }
+        show_results_view(mw);
+        adp.set_searching(true);
+        adp.set_status("Searching…".into());
+        let max_results = config_read()
+            .get::<usize>("search_max_results")
+            .unwrap_or(10000);
+
+        thread::spawn(move || {
+            let (send, recv) = channel::<FileItem>();
+            //Adds the results to the view every BATCH_INTERVAL
+            let batcher = thread::spawn(move || {
+                let mut batch = Vec::new();
+                let mut count = 0;
+                loop {
+                    let received = recv.recv_timeout(BATCH_INTERVAL);
+                    if let Ok(item) = received {
+                        batch.push(item);
+                        continue;
+                    }
+                    if !batch.is_empty() {
+                        count += batch.len();
+                        let results = std::mem::take(&mut batch);
+                        run_with_main_window(move |mw| {
+                            if is_current(generation) {
+                                add_results(mw, results);
+                                mw.global::<SearchAdapter>()
+                                    .set_status(format!("Searching… {}", found_text(count)).into());
+                            }
+                        });
+                    }
+                    if received == Err(RecvTimeoutError::Disconnected) {
+                        return;
+                    }
+                }
+            });
+
+            let count = AtomicUsize::new(0);
+            let limit_reached = || count.load(Ordering::SeqCst) >= max_results;
+            walk(
+                Path::new(&root),
+                &criteria,
+                &|| !is_current(generation) || limit_reached(),
+                &|path| {
+                    let is_link = fs::symlink_metadata(path).is_ok_and(|m| m.is_symlink());
+                    let meta = fs::metadata(path).or_else(|_| fs::symlink_metadata(path));
+                    if let (Ok(meta), false) = (meta, limit_reached()) {
+                        count.fetch_add(1, Ordering::SeqCst);
+                        send.send(file_item(path, &meta, is_link)).ok();
+                    }
+                },
+            );
+            drop(send);
+            batcher.join().ok();
+
+            let count = count.into_inner();
+            let mut status = found_text(count);
+            if count >= max_results {
+                status += &format!(", stopped at the maximum of {}", max_results);
+            }
+            run_with_main_window(move |mw| {
+                if !is_current(generation) {
+                    return;
+                }
+                let adp = mw.global::<SearchAdapter>();
+                adp.set_searching(false);
+                adp.set_status(status.into());
+                let files: Vec<FileItem> = mw.global::<FileManager>().get_files().iter().collect();
+                status_bar::set_counts(mw, &files, 0);
+                call_current_sort(mw);
+                columns::fill_mime_types(mw, &view_settings::current(mw));
+            });
+        });
+    });
+}
+
+///Stops the walk, the results found so far stay
+pub fn stop() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    run_with_main_window(|mw| {
+        let adp = mw.global::<SearchAdapter>();
+        adp.set_searching(false);
+        adp.set_status("Stopped".into());
+        let files: Vec<FileItem> = mw.global::<FileManager>().get_files().iter().collect();
+        status_bar::set_counts(mw, &files, 0);
+    });
+}
+
+///The tab shows its directory again, when going to another one
+pub fn end(mw: &MainWindow) {
+    if !is_active() {
+        return;
+    }
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = None;
+    }
+    let adp = mw.global::<SearchAdapter>();
+    adp.set_searching(false);
+    adp.set_status("".into());
+}
+
+///Hides the search bar and shows the directory again
+pub fn close() {
+    run_with_main_window(|mw| {
+        mw.global::<SearchAdapter>().set_shown(false);
+        let was_active = is_active();
+        end(mw);
+        if was_active {
+            ui::send_message(UIMessage::Refresh);
+        }
+    });
+}
+
+///Searches again, after files were changed
+pub fn restart() {
+    let query = ACTIVE.lock().ok().and_then(|a| a.clone());
+    if let Some(query) = query {
+        start(query);
+    }
+}
+
+///Goes to the directory of the selected result, and selects it there
+pub fn reveal_selected() {
+    let Some(file) = selection::get_selected_file() else {
+        return;
+    };
+    let Some(parent) = Path::new(file.path.as_str()).parent() else {
+        return;
+    };
+    let name = parent
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "/".into());
+    if let Ok(mut reveal) = REVEAL.lock() {
+        *reveal = Some(file.path.to_string());
+    }
+    ui::send_message(UIMessage::SetCurrentTabFile(
+        TabItem {
+            internal_path: parent.to_string_lossy().to_string().into(),
+            selected: true,
+            text_length: name.len() as i32,
+            text: name.into(),
+            view_mode: tabs::VIEW_DETAILS,
+        },
+        true,
+    ));
+}
+
+///Selects the file given to reveal_selected, once its directory is shown
+pub fn select_revealed(mw: &MainWindow) {
+    let Some(path) = REVEAL.lock().ok().and_then(|mut r| r.take()) else {
+        return;
+    };
+    let files = mw.global::<FileManager>().get_files();
+    if let Some((i, file)) = files.iter().enumerate().find(|(_, f)| f.path == path) {
+        selection::set_single_selected(i as i32, file);
+    }
+}
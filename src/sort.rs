@@ -1,158 +1,210 @@
-use std::{cmp::Ordering, rc::Rc};
+use std::{
+    cmp::Ordering,
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering as AtomicOrdering},
+    thread,
+};
 
-use crate::{ui::*, utils::types::i32_to_i64};
-use slint::SortModel;
+use crate::{
+    callbacks::filemanager::selection,
+    columns,
+    globals::config_read,
+    status_bar,
+    ui::{main_window::run_with_main_window, *},
+    utils::collation::{collation_key, CollationKey},
+    view_settings,
+};
+use slint::{Model, VecModel};
 
-enum SortBy {
-    Name,
-    Size,
-    Date,
-}
+/*
+ *  The sort of each directory is kept in its view settings (see view_settings.rs).
+ *  How names are compared and grouped is set in the configuration, see SortOptions.
+ *
+ *  Every file gets a key per sorted column (SortKey) before sorting, so that expensive values
+ *  (collation keys, owner names...) are computed once per file instead of once per comparison.
+ *  Large directories are sorted in a worker thread and shown once sorted.
+ * */
 
-static mut CURRENT_SORT: SortBy = SortBy::Name;
-static mut CURRENT_ASC: bool = true;
+///Directories with at least this many files are sorted outside the UI thread
+const ASYNC_SORT_THRESHOLD: usize = 5000;
 
-//These are mostly safe since we only ever touch them in the main thread
-fn set_current_sort(new_sort: SortBy, asc: bool) {
-    unsafe {
-        CURRENT_SORT = new_sort;
-        CURRENT_ASC = asc;
-    }
+//Incremented for every sort, results of older sorts are dropped
+static SORT_GENERATION: AtomicU64 = AtomicU64::new(0);
+//Generation of the last sort shown, a sort is running in a thread while they differ
+static SORT_SHOWN: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy)]
+pub struct SortOptions {
+    ///"file2" before "file10"
+    pub natural: bool,
+    pub case_sensitive: bool,
+    ///Hidden files before the others
+    pub dotfiles_first: bool,
+    ///Directories before files
+    pub folders_first: bool,
 }
-pub fn call_current_sort(mw: &MainWindow) {
-    unsafe {
-        match CURRENT_SORT {
-            SortBy::Name => sort_by_name(mw, CURRENT_ASC, false),
-            SortBy::Date => sort_by_date(mw, CURRENT_ASC, false),
-            SortBy::Size => sort_by_size(mw, CURRENT_ASC, false),
+
+impl SortOptions {
+    pub fn from_config() -> Self {
+        let conf = config_read();
+        Self {
+            natural: conf.get("sort_natural").unwrap_or(true),
+            case_sensitive: conf.get("sort_case_sensitive").unwrap_or(false),
+            dotfiles_first: conf.get("sort_dotfiles_first").unwrap_or(false),
+            folders_first: conf.get("sort_folders_first").unwrap_or(true),
         }
     }
-}
 
-//save: whether we need to modify the statics or not
-//It's a small optimization, we don't need do it if calling from call_current_sort
-pub fn sort_by_name(mw: &MainWindow, ascending: bool, save: bool) {
-    if save {
-        set_current_sort(SortBy::Name, ascending);
+    ///Orders the groups (directories, hidden files), files in the same group are Equal.
+    ///The groups are kept in the same order in both directions.
+    pub fn group_order(&self, lhs: &FileItem, rhs: &FileItem) -> Ordering {
+        let dirs = if self.folders_first {
+            rhs.is_dir.cmp(&lhs.is_dir)
+        } else {
+            Ordering::Equal
+        };
+        dirs.then_with(|| {
+            if self.dotfiles_first {
+                rhs.file_name
+                    .starts_with('.')
+                    .cmp(&lhs.file_name.starts_with('.'))
+            } else {
+                Ordering::Equal
+            }
+        })
     }
-    let fm = mw.global::<FileManager>();
-    if ascending {
-        fm.set_files(
-            Rc::new(SortModel::new(fm.get_files(), |lhs, rhs| {
-                if lhs.is_dir && !rhs.is_dir {
-                    Ordering::Less
-                } else if rhs.is_dir && !lhs.is_dir {
-                    Ordering::Greater
-                } else {
-                    lhs.file_name
-                        .to_lowercase()
-                        .cmp(&rhs.file_name.to_lowercase())
-                }
-            }))
-            .into(),
-        );
-    } else {
-        fm.set_files(
-            Rc::new(SortModel::new(fm.get_files(), |lhs, rhs| {
-                if lhs.is_dir && !rhs.is_dir {
-                    Ordering::Less
-                } else if rhs.is_dir && !lhs.is_dir {
-                    Ordering::Greater
-                } else {
-                    rhs.file_name
-                        .to_lowercase()
-                        .cmp(&lhs.file_name.to_lowercase())
-                }
-            }))
-            .into(),
-        );
+
+    pub fn name_key(&self, name: &str) -> SortKey {
+        let key = if self.case_sensitive {
+            collation_key(name, self.natural)
+        } else {
+            collation_key(&name.to_lowercase(), self.natural)
+        };
+        //Names that only differ by case or leading zeros still get a stable order
+        SortKey::Name(key, name.to_string())
     }
+
+    pub fn compare_names(&self, lhs: &str, rhs: &str) -> Ordering {
+        self.name_key(lhs).cmp(&self.name_key(rhs))
+    }
+}
+
+///The value of a file for a column, see columns::sort_key
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub enum SortKey {
+    Number(i64),
+    Text(String),
+    Name(CollationKey, String),
 }
-pub fn sort_by_date(mw: &MainWindow, ascending: bool, save: bool) {
-    if save {
-        set_current_sort(SortBy::Date, ascending);
+
+///What to sort by: the columns in order of importance, each with its direction
+#[derive(Clone)]
+pub struct SortSpec {
+    pub keys: Vec<(i32, bool)>,
+    pub options: SortOptions,
+}
+
+impl SortSpec {
+    ///Sorts by the column, then by the secondary columns of the configuration
+    ///("sort_secondary_keys") for the files that are equal
+    pub fn new(sort: &str, ascending: bool, options: SortOptions) -> Self {
+        let primary = columns::column_index(sort).unwrap_or(columns::NAME);
+        let secondary: String = config_read()
+            .get("sort_secondary_keys")
+            .unwrap_or_else(|| "name".into());
+        let mut keys = vec![(primary, ascending)];
+        for column in secondary
+            .split(',')
+            .filter_map(|c| columns::column_index(c.trim()))
+        {
+            if keys.iter().all(|(k, _)| *k != column) {
+                keys.push((column, true));
+            }
+        }
+        Self { keys, options }
     }
-    let fm = mw.global::<FileManager>();
-    if ascending {
-        fm.set_files(
-            Rc::new(SortModel::new(fm.get_files(), |lhs, rhs| {
-                if lhs.is_dir && !rhs.is_dir {
-                    Ordering::Less
-                } else if rhs.is_dir && !lhs.is_dir {
-                    Ordering::Greater
-                } else {
-                    if lhs.date.a == 0 && rhs.date.a == 0 {
-                        // No need to convert to 64 bits if date is stored entirely in 32 bits.
-                        // This should always be true until 2038
-                        lhs.date.b.cmp(&rhs.date.b)
-                    } else {
-                        i32_to_i64((lhs.date.a, lhs.date.b))
-                            .cmp(&i32_to_i64((rhs.date.a, rhs.date.b)))
-                    }
-                }
-            }))
-            .into(),
-        );
-    } else {
-        fm.set_files(
-            Rc::new(SortModel::new(fm.get_files(), |lhs, rhs| {
-                if lhs.is_dir && !rhs.is_dir {
-                    Ordering::Less
-                } else if rhs.is_dir && !lhs.is_dir {
-                    Ordering::Greater
-                } else {
-                    if lhs.date.a == 0 && rhs.date.a == 0 {
-                        rhs.date.b.cmp(&lhs.date.b)
-                    } else {
-                        i32_to_i64((rhs.date.a, rhs.date.b))
-                            .cmp(&i32_to_i64((lhs.date.a, lhs.date.b)))
-                    }
-                }
-            }))
-            .into(),
-        );
+
+    fn keys_for(&self, file: &FileItem) -> Vec<SortKey> {
+        self.keys
+            .iter()
+            .map(|(column, _)| columns::sort_key(file, *column, &self.options))
+            .collect()
     }
-}
-pub fn sort_by_size(mw: &MainWindow, ascending: bool, save: bool) {
-    if save {
-        set_current_sort(SortBy::Size, ascending);
+
+    fn compare(&self, lhs: &(Vec<SortKey>, FileItem), rhs: &(Vec<SortKey>, FileItem)) -> Ordering {
+        self.options.group_order(&lhs.1, &rhs.1).then_with(|| {
+            self.keys
+                .iter()
+                .zip(lhs.0.iter().zip(rhs.0.iter()))
+                .map(
+                    |((_, ascending), (l, r))| {
+                        if *ascending {
+                            l.cmp(r)
+                        } else {
+                            r.cmp(l)
+                        }
+                    },
+                )
+                .find(|o| *o != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        })
     }
+
+    pub fn sort(&self, files: Vec<FileItem>) -> Vec<FileItem> {
+        let mut keyed: Vec<(Vec<SortKey>, FileItem)> =
+            files.into_iter().map(|f| (self.keys_for(&f), f)).collect();
+        keyed.sort_by(|l, r| self.compare(l, r));
+        keyed.into_iter().map(|(_, f)| f).collect()
+    }
+}
+
+///Sorts the files shown by the column name stored in the view settings
+pub fn sort_files(mw: &MainWindow, sort: &str, ascending: bool) {
+    let spec = SortSpec::new(sort, ascending, SortOptions::from_config());
     let fm = mw.global::<FileManager>();
-    if ascending {
-        fm.set_files(
-            Rc::new(SortModel::new(fm.get_files(), |lhs, rhs| {
-                if lhs.is_dir && !rhs.is_dir {
-                    Ordering::Less
-                } else if rhs.is_dir && !lhs.is_dir {
-                    Ordering::Greater
-                } else {
-                    if lhs.size.a == 0 && rhs.size.a == 0 {
-                        lhs.size.b.cmp(&rhs.size.b)
-                    } else {
-                        i32_to_i64((lhs.size.a, lhs.size.b))
-                            .cmp(&i32_to_i64((rhs.size.a, rhs.size.b)))
-                    }
-                }
-            }))
-            .into(),
-        );
-    } else {
-        fm.set_files(
-            Rc::new(SortModel::new(fm.get_files(), |lhs, rhs| {
-                if lhs.is_dir && !rhs.is_dir {
-                    Ordering::Less
-                } else if rhs.is_dir && !lhs.is_dir {
-                    Ordering::Greater
-                } else {
-                    if lhs.size.a == 0 && rhs.size.a == 0 {
-                        rhs.size.b.cmp(&lhs.size.b)
-                    } else {
-                        i32_to_i64((rhs.size.a, rhs.size.b))
-                            .cmp(&i32_to_i64((lhs.size.a, lhs.size.b)))
-                    }
-                }
-            }))
-            .into(),
-        );
+    let files: Vec<FileItem> = fm.get_files().iter().collect();
+    let generation = SORT_GENERATION.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+
+    if files.len() < ASYNC_SORT_THRESHOLD {
+        fm.set_files(Rc::new(VecModel::from(spec.sort(files))).into());
+        SORT_SHOWN.store(generation, AtomicOrdering::SeqCst);
+        return;
     }
+    thread::spawn(move || {
+        let sorted = spec.sort(files);
+        run_with_main_window(move |mw| {
+            //The directory or the sort changed in the meantime
+            if SORT_GENERATION.load(AtomicOrdering::SeqCst) != generation {
+                return;
+            }
+            mw.global::<FileManager>()
+                .set_files(Rc::new(VecModel::from(sorted)).into());
+            SORT_SHOWN.store(generation, AtomicOrdering::SeqCst);
+            //The selection is stored by index
+            selection::clear_selection();
+        });
+    });
+}
+
+///Drops the result of a sort running in a thread, for when the files shown change before it
+///ends. Returns whether one was running, the files then need to be sorted again.
+pub fn cancel_pending() -> bool {
+    let generation = SORT_GENERATION.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+    SORT_SHOWN.swap(generation, AtomicOrdering::SeqCst) != generation - 1
+}
+
+///Sorts by the column and remembers it for the current directory
+pub fn set_sort(mw: &MainWindow, sort: &str, ascending: bool) {
+    view_settings::update_current(mw, |s| {
+        s.sort = sort.to_string();
+        s.ascending = ascending;
+    });
+    status_bar::update_view(mw, &view_settings::current(mw));
+    sort_files(mw, sort, ascending);
+}
+
+///Sorts the files the way the current directory is set to
+pub fn call_current_sort(mw: &MainWindow) {
+    let settings = view_settings::current(mw);
+    sort_files(mw, &settings.sort, settings.ascending);
 }
@@ -0,0 +1,400 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    mem,
+    path::Path,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+};
+
+use slint::{Color, ComponentHandle, Model, VecModel};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::{SyntaxReference, SyntaxSet},
+};
+
+use crate::{
+    callbacks::filemanager::selection,
+    core::get_file_encoding,
+    globals::config_read,
+    mime::mime_type_for_path,
+    preview::decode_text,
+    ui::{source_viewer_window::run_with_source_viewer_window, *},
+    utils::{error_handling::log_error_str, types::format_size},
+};
+
+/*
+ *  Shows the content of a file: text with syntax highlighting, or a hex dump for binaries.
+ *
+ *  The file is read in a thread and sent to the window in batches of lines. At most
+ *  "viewer_max_kb" of it are read and long lines are cut, so that huge files (logs, minified
+ *  files, disk images...) show their start quickly without being loaded in memory.
+ * */
+
+const BATCH_LINES: usize = 500;
+///Bytes of a line that are kept, the rest is skipped
+const MAX_LINE_LEN: usize = 2000;
+const HEX_WIDTH: usize = 16;
+const THEME: &str = "base16-ocean.dark";
+
+//Incremented for every load, older loads stop
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static CURRENT_PATH: Mutex<Option<String>> = Mutex::new(None);
+
+fn is_current(generation: u64) -> bool {
+    GENERATION.load(Ordering::SeqCst) == generation
+}
+
+///A line as read by the loader, turned into a SourceLine in the UI thread
+pub struct Line {
+    pub number: String,
+    pub spans: Vec<(String, Color)>,
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME_: OnceLock<Theme> = OnceLock::new();
+    THEME_.get_or_init(|| {
+        ThemeSet::load_defaults()
+            .themes
+            .remove(THEME)
+            .unwrap_or_default()
+    })
+}
+
+fn to_color(c: syntect::highlighting::Color) -> Color {
+    Color::from_argb_u8(c.a, c.r, c.g, c.b)
+}
+
+fn foreground() -> Color {
+    theme()
+        .settings
+        .foreground
+        .map(to_color)
+        .unwrap_or(Color::from_rgb_u8(0xc0, 0xc5, 0xce))
+}
+
+fn background() -> Color {
+    theme()
+        .settings
+        .background
+        .map(to_color)
+        .unwrap_or(Color::from_rgb_u8(0x2b, 0x30, 0x3b))
+}
+
+//MIME types whose subtype isn't a name or extension syntect knows
+fn syntax_token_for_mime(mime: &str) -> Option<&str> {
+    let token = match mime {
+        "text/x-csrc" | "text/x-chdr" => "c",
+        "text/x-c++src" | "text/x-c++hdr" => "cpp",
+        "text/x-shellscript" | "application/x-shellscript" => "sh",
+        "text/javascript" | "application/javascript" => "js",
+        "text/x-makefile" => "Makefile",
+        _ => mime.split('/').nth(1)?,
+    };
+    Some(token.strip_prefix("x-").unwrap_or(token))
+}
+
+///The syntax of a file, from its extension, then its MIME type, then its first line (#!...)
+pub fn find_syntax(path: &str, first_line: &str) -> Option<&'static SyntaxReference> {
+    let ss = syntax_set();
+    let path = Path::new(path);
+    let extension = path.extension().and_then(|e| e.to_str());
+    let name = path.file_name().and_then(|n| n.to_str());
+    extension
+        .and_then(|e| ss.find_syntax_by_extension(e))
+        //Files known by their whole name, eg. "Makefile"
+        .or_else(|| name.and_then(|n| ss.find_syntax_by_extension(n)))
+        .or_else(|| {
+            let mime = mime_type_for_path(path, false);
+            syntax_token_for_mime(&mime).and_then(|t| ss.find_syntax_by_token(t))
+        })
+        .or_else(|| ss.find_syntax_by_first_line(first_line))
+        .filter(|s| s.name != "Plain Text")
+}
+
+///Reads a line of at most max bytes, without the newline. The rest of a longer line is skipped
+///without being kept in memory.
+///Returns the number of bytes read, 0 at the end of the file.
+pub fn read_line_capped(
+    reader: &mut impl BufRead,
+    line: &mut Vec<u8>,
+    max: usize,
+) -> io::Result<usize> {
+    line.clear();
+    let mut read = 0;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(read);
+        }
+        let (chunk, used, done) = match buf.iter().position(|b| *b == b'\n') {
+            Some(i) => (&buf[..i], i + 1, true),
+            None => (buf, buf.len(), false),
+        };
+        let room = max.saturating_sub(line.len());
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        reader.consume(used);
+        read += used;
+        if done {
+            return Ok(read);
+        }
+    }
+}
+
+///A line of hex dump, like hexdump -C
+pub fn hex_line(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(HEX_WIDTH * 4 + 4);
+    for i in 0..HEX_WIDTH {
+        if i == HEX_WIDTH / 2 {
+            s.push(' ');
+        }
+        match bytes.get(i) {
+            Some(b) => s.push_str(&format!("{:02x} ", b)),
+            None => s.push_str("   "),
+        }
+    }
+    s.push('|');
+    s.extend(bytes.iter().map(|b| {
+        if b.is_ascii_graphic() || *b == b' ' {
+            *b as char
+        } else {
+            '.'
+        }
+    }));
+    s.push('|');
+    s
+}
+
+fn max_bytes() -> u64 {
+    config_read().get::<u64>("viewer_max_kb").unwrap_or(1024) * 1024
+}
+
+//Tells what part of the file is shown
+fn shown_status(count: String, read: u64, size: u64) -> String {
+    if read < size {
+        format!(
+            "{}, showing the first {} of {}",
+            count,
+            format_size(read, false),
+            format_size(size, false)
+        )
+    } else {
+        count
+    }
+}
+
+fn push_lines(generation: u64, lines: Vec<Line>) {
+    run_with_source_viewer_window(move |w| {
+        if !is_current(generation) {
+            return;
+        }
+        let model = w.global::<SourceViewerAdapter>().get_lines();
+        let Some(model) = model.as_any().downcast_ref::<VecModel<SourceLine>>() else {
+            return;
+        };
+        model.extend(lines.into_iter().map(|l| {
+            let spans: Vec<SourceSpan> = l
+                .spans
+                .into_iter()
+                .map(|(text, color)| SourceSpan {
+                    text: text.into(),
+                    color,
+                })
+                .collect();
+            SourceLine {
+                number: l.number.into(),
+                spans: Rc::new(VecModel::from(spans)).into(),
+            }
+        }));
+    });
+}
+
+fn stream_text(path: &str, encoding: &str, generation: u64) -> io::Result<String> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let max = max_bytes();
+    let mut reader = BufReader::new(file.take(max));
+    let mut buf = Vec::new();
+    let mut lines = Vec::with_capacity(BATCH_LINES);
+    let mut highlighter: Option<HighlightLines> = None;
+    let foreground = foreground();
+    let (mut count, mut read, mut cut) = (0u64, 0u64, false);
+
+    loop {
+        if !is_current(generation) {
+            return Ok(String::new());
+        }
+        let n = read_line_capped(&mut reader, &mut buf, MAX_LINE_LEN)?;
+        if n == 0 {
+            break;
+        }
+        read += n as u64;
+        cut |= n > buf.len() + 1;
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+        let text = decode_text(&buf, encoding).replace('\t', "    ");
+        if count == 0 {
+            highlighter = find_syntax(path, &text).map(|s| HighlightLines::new(s, theme()));
+        }
+        count += 1;
+
+        let spans = match highlighter.as_mut() {
+            //The syntaxes expect the newline
+            Some(h) => match h.highlight_line(&(text.clone() + "\n"), syntax_set()) {
+                Ok(ranges) => ranges
+                    .into_iter()
+                    .map(|(style, s)| {
+                        (
+                            s.trim_end_matches('\n').to_string(),
+                            to_color(style.foreground),
+                        )
+                    })
+                    .filter(|(s, _)| !s.is_empty())
+                    .collect(),
+                Err(_) => vec![(text, foreground)],
+            },
+            None => vec![(text, foreground)],
+        };
+        lines.push(Line {
+            number: count.to_string(),
+            spans,
+        });
+        if lines.len() == BATCH_LINES {
+            push_lines(generation, mem::take(&mut lines));
+        }
+    }
+    push_lines(generation, lines);
+
+    let mut status = shown_status(format!("{} lines", count), read, size);
+    if cut {
+        status += &format!(", lines longer than {} bytes are cut", MAX_LINE_LEN);
+    }
+    Ok(status)
+}
+
+fn stream_hex(path: &str, generation: u64) -> io::Result<String> {
+    let foreground = foreground();
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut reader = BufReader::new(file.take(max_bytes()));
+    let mut buf = [0u8; HEX_WIDTH];
+    let mut lines = Vec::with_capacity(BATCH_LINES);
+    let mut offset = 0u64;
+
+    loop {
+        if !is_current(generation) {
+            return Ok(String::new());
+        }
+        //Fills the whole row unless the file ends
+        let mut n = 0;
+        while n < HEX_WIDTH {
+            match reader.read(&mut buf[n..])? {
+                0 => break,
+                read => n += read,
+            }
+        }
+        if n == 0 {
+            break;
+        }
+        lines.push(Line {
+            number: format!("{:08x}", offset),
+            spans: vec![(hex_line(&buf[..n]), foreground)],
+        });
+        offset += n as u64;
+        if lines.len() == BATCH_LINES {
+            push_lines(generation, mem::take(&mut lines));
+        }
+    }
+    push_lines(generation, lines);
+    Ok(shown_status(format!("{} bytes", size), offset, size))
+}
+
+///Loads the file in the window. hex is decided from the content when None.
+fn load(path: String, hex: Option<bool>) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Ok(mut current) = CURRENT_PATH.lock() {
+        *current = Some(path.clone());
+    }
+    let window_path = path.clone();
+    run_with_source_viewer_window(move |w| {
+        let adp = w.global::<SourceViewerAdapter>();
+        adp.set_path(window_path.into());
+        adp.set_lines(Rc::new(VecModel::<SourceLine>::default()).into());
+        adp.set_status("".into());
+        adp.set_loading(true);
+        w.show().ok();
+    });
+
+    thread::spawn(move || {
+        let encoding = get_file_encoding(&path);
+        //Lines of UTF-16 can't be split on '\n' bytes
+        let hex = hex.unwrap_or(encoding == "binary" || encoding.starts_with("utf-16"));
+        run_with_source_viewer_window(move |w| {
+            let adp = w.global::<SourceViewerAdapter>();
+            adp.set_hex(hex);
+            adp.set_foreground(foreground());
+            adp.set_background(background());
+        });
+
+        let result = if hex {
+            stream_hex(&path, generation)
+        } else {
+            stream_text(&path, &encoding, generation)
+        };
+        let status = match result {
+            Ok(status) => status,
+            Err(e) => {
+                log_error_str(&format!("Could not read {}: {}", path, e));
+                format!("Could not read the file: {}", e)
+            }
+        };
+        run_with_source_viewer_window(move |w| {
+            if !is_current(generation) {
+                return;
+            }
+            let adp = w.global::<SourceViewerAdapter>();
+            adp.set_status(status.into());
+            adp.set_loading(false);
+        });
+    });
+}
+
+///Opens the viewer for the selected file
+pub fn view_selected() {
+    match selection::get_selected_file() {
+        Some(file) if !file.is_dir => load(file.path.to_string(), None),
+        _ => (),
+    }
+}
+
+///Switches between the text and the hex dump of the file
+pub fn toggle_hex() {
+    let Some(path) = CURRENT_PATH.lock().ok().and_then(|p| p.clone()) else {
+        return;
+    };
+    run_with_source_viewer_window(move |w| {
+        let hex = w.global::<SourceViewerAdapter>().get_hex();
+        load(path, Some(!hex));
+    });
+}
+
+pub fn close() {
+    //Stops the loader
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    run_with_source_viewer_window(|w| {
+        w.global::<SourceViewerAdapter>()
+            .set_lines(Rc::new(VecModel::<SourceLine>::default()).into());
+        w.hide().ok();
+    });
+}
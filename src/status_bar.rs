@@ -0,0 +1,184 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::Duration,
+};
+
+use slint::ComponentHandle;
+use walkdir::WalkDir;
+
+use crate::{
+    callbacks::filemanager::selection,
+    columns, drives,
+    ui::{main_window::run_with_main_window, FileItem, MainWindow, StatusBarAdapter, TabsAdapter},
+    utils::types::{format_size, i32_to_i64},
+    view_settings::ViewSettings,
+};
+
+/*
+ *  The bar at the bottom of the main window: the number of items, the selection and its size,
+ *  the errors for the user, the sort, and the free space.
+ *
+ *  The size of selected directories is computed in a thread, once the selection stopped
+ *  changing for a moment. Like the preview, each selection change starts a new generation and
+ *  older computations stop.
+ * */
+
+///Wait before computing the size, the selection often changes quickly (eg. holding a key)
+const SIZE_DELAY: Duration = Duration::from_millis(250);
+///How long an error stays shown
+const NOTICE_DURATION: Duration = Duration::from_secs(10);
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static NOTICE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn is_current(generation: u64) -> bool {
+    GENERATION.load(Ordering::SeqCst) == generation
+}
+
+fn plural(n: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", n, if n == 1 { singular } else { plural })
+}
+
+///eg. "3 folders, 1 file (2 hidden)"
+pub fn format_counts(dirs: usize, files: usize, hidden: usize) -> String {
+    let mut counts = match (dirs, files) {
+        (0, 0) => "Empty".to_string(),
+        (0, _) => plural(files, "file", "files"),
+        (_, 0) => plural(dirs, "folder", "folders"),
+        _ => format!(
+            "{}, {}",
+            plural(dirs, "folder", "folders"),
+            plural(files, "file", "files")
+        ),
+    };
+    if hidden > 0 {
+        counts += &format!(" ({} hidden)", hidden);
+    }
+    counts
+}
+
+///The total size of the files under the path, None if cancelled
+pub fn dir_size(path: &str, cancelled: impl Fn() -> bool) -> Option<u64> {
+    let mut total = 0;
+    for entry in WalkDir::new(path).follow_links(false).into_iter().flatten() {
+        if cancelled() {
+            return None;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            if !metadata.is_dir() {
+                total += metadata.len();
+            }
+        }
+    }
+    Some(total)
+}
+
+///Shows the number of files of the current directory, hidden is the number left out
+pub fn set_counts(mw: &MainWindow, files: &[FileItem], hidden: usize) {
+    let dirs = files.iter().filter(|f| f.is_dir).count();
+    mw.global::<StatusBarAdapter>()
+        .set_items(format_counts(dirs, files.len() - dirs, hidden).into());
+}
+
+///Shows the sort, and whether hidden files are shown
+pub fn update_view(mw: &MainWindow, settings: &ViewSettings) {
+    let column = columns::COLUMNS
+        .iter()
+        .find(|c| c.name == settings.sort)
+        .map(|c| c.display)
+        .unwrap_or("Name");
+    let mut view = format!(
+        "Sorted by {}, {}",
+        column,
+        if settings.ascending {
+            "ascending"
+        } else {
+            "descending"
+        }
+    );
+    if settings.show_hidden {
+        view += " · Hidden files shown";
+    }
+    mw.global::<StatusBarAdapter>().set_view(view.into());
+}
+
+///Shows the free space of the filesystem of the current directory
+pub fn update_free_space(mw: &MainWindow) {
+    let path = mw
+        .global::<TabsAdapter>()
+        .invoke_get_current_tab()
+        .internal_path;
+    let text = match drives::filesystem_space(&path) {
+        Some((free, total)) => format!(
+            "{} free of {}",
+            format_size(free, false),
+            format_size(total, false)
+        ),
+        None => String::new(),
+    };
+    mw.global::<StatusBarAdapter>().set_free_space(text.into());
+}
+
+///Shows the number of selected files and their size
+pub fn selection_changed() {
+    run_with_main_window(|mw| {
+        let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        let adp = mw.global::<StatusBarAdapter>();
+        let files = selection::selected_files_clone();
+        if files.is_empty() {
+            adp.set_selection("".into());
+            return;
+        }
+        let count = format!("{} selected", files.len());
+        let files_size: u64 = files
+            .iter()
+            .filter(|f| !f.is_dir)
+            .map(|f| i32_to_i64((f.size.a, f.size.b)).max(0) as u64)
+            .sum();
+        let dirs: Vec<String> = files
+            .iter()
+            .filter(|f| f.is_dir)
+            .map(|f| f.path.to_string())
+            .collect();
+        if dirs.is_empty() {
+            adp.set_selection(format!("{} ({})", count, format_size(files_size, false)).into());
+            return;
+        }
+        adp.set_selection(format!("{} (computing size…)", count).into());
+        thread::spawn(move || {
+            thread::sleep(SIZE_DELAY);
+            let mut total = files_size;
+            for dir in dirs {
+                match dir_size(&dir, || !is_current(generation)) {
+                    Some(size) => total += size,
+                    None => return,
+                }
+            }
+            run_with_main_window(move |mw| {
+                if is_current(generation) {
+                    mw.global::<StatusBarAdapter>()
+                        .set_selection(format!("{} ({})", count, format_size(total, false)).into());
+                }
+            });
+        });
+    });
+}
+
+///Shows an error to the user until a newer one, a click on it, or NOTICE_DURATION
+pub fn show_notice(msg: &str) {
+    let generation = NOTICE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    //The bar has a single line
+    let text = msg.lines().collect::<Vec<_>>().join(" · ");
+    run_with_main_window(move |mw| {
+        mw.global::<StatusBarAdapter>().set_notice(text.into());
+    });
+    thread::spawn(move || {
+        thread::sleep(NOTICE_DURATION);
+        run_with_main_window(move |mw| {
+            if NOTICE_GENERATION.load(Ordering::SeqCst) == generation {
+                mw.global::<StatusBarAdapter>().set_notice("".into());
+            }
+        });
+    });
+}
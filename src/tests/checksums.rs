@@ -0,0 +1,40 @@
+use crate::checksums::{find_expected_hash, match_hash, Hashers};
+
+#[test]
+pub fn test_hashers() {
+    let mut hashers = Hashers::default();
+    hashers.update(b"abc");
+    let hashes = hashers.finalize();
+    assert_eq!(hashes[0], "900150983cd24fb0d6963f7d28e17f72");
+    assert_eq!(hashes[1], "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(
+        hashes[2],
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(
+        hashes[3],
+        "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+    );
+    assert_eq!(
+        match_hash(&hashes, &hashes[2].to_uppercase()),
+        Some("SHA-256")
+    );
+    assert_eq!(match_hash(&hashes, "abcd"), None);
+}
+
+#[test]
+pub fn test_find_expected_hash() {
+    assert_eq!(
+        find_expected_hash(" ABCDEF01 ", "file.iso"),
+        Some("abcdef01".into())
+    );
+    let sums = "# comment\n1111  other.iso\n2222 *dir/file.iso\n";
+    assert_eq!(find_expected_hash(sums, "file.iso"), Some("2222".into()));
+    assert_eq!(find_expected_hash(sums, "missing.iso"), None);
+    //A single line is used even if the name differs
+    assert_eq!(
+        find_expected_hash("3333  renamed.iso", "file.iso"),
+        Some("3333".into())
+    );
+    assert_eq!(find_expected_hash("not a hash", "file.iso"), None);
+}
@@ -31,6 +31,13 @@ pub fn test_copy_file() {
             size: _i64 { a: 0, b: 0 },
             path: path1.into(),
             selected: false,
+            mode: 0,
+            uid: 0,
+            gid: 0,
+            created: _i64 { a: 0, b: 0 },
+            accessed: _i64 { a: 0, b: 0 },
+            link_target: "".into(),
+            mime_type: "".into(),
         },
         FileItem {
             date: _i64 { a: 0, b: 0 },
@@ -42,6 +49,13 @@ pub fn test_copy_file() {
             size: _i64 { a: 0, b: 0 },
             path: path2.into(),
             selected: false,
+            mode: 0,
+            uid: 0,
+            gid: 0,
+            created: _i64 { a: 0, b: 0 },
+            accessed: _i64 { a: 0, b: 0 },
+            link_target: "".into(),
+            mime_type: "".into(),
         },
     ];
     copy_file(files, false);
@@ -21,6 +21,13 @@ pub fn test_cut() {
         size: _i64 { a: 0, b: 0 },
         path: path1.into(),
         selected: false,
+        mode: 0,
+        uid: 0,
+        gid: 0,
+        created: _i64 { a: 0, b: 0 },
+        accessed: _i64 { a: 0, b: 0 },
+        link_target: "".into(),
+        mime_type: "".into(),
     };
     let item2 = FileItem {
         date: _i64 { a: 0, b: 0 },
@@ -32,6 +39,13 @@ pub fn test_cut() {
         size: _i64 { a: 0, b: 0 },
         path: path2.into(),
         selected: false,
+        mode: 0,
+        uid: 0,
+        gid: 0,
+        created: _i64 { a: 0, b: 0 },
+        accessed: _i64 { a: 0, b: 0 },
+        link_target: "".into(),
+        mime_type: "".into(),
     };
     let files = vec![item1.clone(), item2.clone()];
 
@@ -0,0 +1,34 @@
+use std::cmp::Ordering;
+
+use crate::{
+    sort::SortOptions,
+    utils::collation::{collation_key, natural_cmp},
+};
+
+#[test]
+pub fn test_natural_cmp() {
+    assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+    assert_eq!(natural_cmp("file10", "file9"), Ordering::Greater);
+    assert_eq!(natural_cmp("file", "file1"), Ordering::Less);
+    assert_eq!(natural_cmp("v1.10.2", "v1.9.12"), Ordering::Greater);
+    assert_eq!(natural_cmp("a007", "a7"), Ordering::Equal);
+    assert_eq!(collation_key("a007", true), collation_key("a7", true));
+    assert_eq!(
+        natural_cmp("img99999999999999999999", "img100000000000000000000"),
+        Ordering::Less
+    );
+
+    let mut names = vec!["b10", "B2", "a", "b1", ".z", "A1"];
+    let opts = SortOptions {
+        natural: true,
+        case_sensitive: false,
+        dotfiles_first: false,
+        folders_first: true,
+    };
+    names.sort_by(|a, b| opts.compare_names(a, b));
+    assert_eq!(names, vec![".z", "a", "A1", "b1", "B2", "b10"]);
+
+    //Case only breaks ties when insensitive
+    assert_eq!(opts.compare_names("A", "a"), Ordering::Less);
+    assert_eq!(opts.compare_names("a007", "a7"), "a007".cmp("a7"));
+}
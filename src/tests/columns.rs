@@ -0,0 +1,96 @@
+use std::cmp::Ordering;
+
+use crate::{
+    columns::{self, format_mode_string, header_for},
+    config::Config,
+    core::empty_file_with_path,
+    sort::{SortOptions, SortSpec},
+    ui::{_i64, FileItem},
+    view_settings::ViewSettings,
+};
+
+#[test]
+pub fn test_format_mode_string() {
+    assert_eq!(format_mode_string(0o40755, true, false), "drwxr-xr-x");
+    assert_eq!(format_mode_string(0o100644, false, false), "-rw-r--r--");
+    assert_eq!(format_mode_string(0o104755, false, false), "-rwsr-xr-x");
+    assert_eq!(format_mode_string(0o41777, true, false), "drwxrwxrwt");
+    assert_eq!(format_mode_string(0o102640, false, false), "-rw-r-S---");
+    assert_eq!(format_mode_string(0o120777, false, true), "lrwxrwxrwx");
+}
+
+#[test]
+pub fn test_columns() {
+    assert_eq!(
+        header_for("owner", 12.0, 0).unwrap().inner_value,
+        columns::OWNER
+    );
+    assert!(header_for("nonexistent", 12.0, 0).is_none());
+
+    let mut a = empty_file_with_path("/a");
+    let mut b = empty_file_with_path("/b");
+    a.created = _i64 { a: 0, b: 20 };
+    b.created = _i64 { a: 1, b: 0 };
+    let options = SortOptions::from_config();
+    let cmp = |a: &FileItem, b: &FileItem, column| {
+        columns::sort_key(a, column, &options).cmp(&columns::sort_key(b, column, &options))
+    };
+    assert_eq!(cmp(&a, &b, columns::CREATED), Ordering::Less);
+    a.mode = 0o100755;
+    b.mode = 0o40644;
+    assert_eq!(cmp(&a, &b, columns::MODE), Ordering::Greater);
+}
+
+#[test]
+pub fn test_toggle_column() {
+    let mut settings = ViewSettings::from_config(&Config::new());
+    settings.toggle_column("owner");
+    assert!(settings.has_column("owner"));
+    settings.sort = "owner".into();
+    settings.toggle_column("owner");
+    assert!(!settings.has_column("owner"));
+    assert_eq!(settings.sort, "name");
+    settings.toggle_column("name");
+    assert!(settings.has_column("name"));
+}
+
+#[test]
+pub fn test_sort_spec() {
+    let file = |name: &str, ext: &str, size: i32, is_dir: bool| {
+        let mut f = empty_file_with_path(&("/".to_owned() + name));
+        f.file_name = name.into();
+        f.extension = ext.into();
+        f.size = _i64 { a: 0, b: size };
+        f.is_dir = is_dir;
+        f
+    };
+    let files = vec![
+        file("b.txt", "txt", 10, false),
+        file("a.rs", "rs", 10, false),
+        file("c.rs", "rs", 5, false),
+        file("dir", "", 0, true),
+    ];
+    let names = |files: Vec<FileItem>| -> Vec<String> {
+        files.iter().map(|f| f.file_name.to_string()).collect()
+    };
+
+    //Equal sizes fall back to the name, ascending, and directories stay first
+    let spec = SortSpec::new("size", false, SortOptions::from_config());
+    assert_eq!(
+        spec.keys,
+        vec![(columns::SIZE, false), (columns::NAME, true)]
+    );
+    assert_eq!(
+        names(spec.sort(files.clone())),
+        vec!["dir", "a.rs", "b.txt", "c.rs"]
+    );
+
+    let spec = SortSpec {
+        keys: vec![(columns::EXTENSION, true), (columns::SIZE, true)],
+        options: SortOptions::from_config(),
+    };
+    assert_eq!(
+        names(spec.sort(files)),
+        vec!["dir", "c.rs", "a.rs", "b.txt"]
+    );
+}
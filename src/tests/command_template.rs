@@ -0,0 +1,62 @@
+use crate::command_template::{expand, file_uri, quote, split_args};
+
+fn strings(v: &[&str]) -> Vec<String> {
+    v.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+pub fn test_split_args() {
+    assert_eq!(split_args("vim %F"), vec!["vim", "%F"]);
+    assert_eq!(
+        split_args(r#"sh -c "echo \"a  b\" \$HOME" %u"#),
+        vec!["sh", "-c", r#"echo "a  b" $HOME"#, "%u"]
+    );
+    assert_eq!(split_args(r#"  "" x  "#), vec!["", "x"]);
+    for arg in ["plain", "with space", "q\"uo`te$\\", "100%", ""] {
+        assert_eq!(split_args(&quote(arg)), vec![arg.replace('%', "%%")]);
+    }
+}
+
+#[test]
+pub fn test_expand() {
+    let files = strings(&["/tmp/a b.txt", "/home/c.txt"]);
+    //No field code, all the files are appended
+    assert_eq!(
+        expand("nvim", &files),
+        vec![strings(&["nvim", "/tmp/a b.txt", "/home/c.txt"])]
+    );
+    //One command per file
+    assert_eq!(
+        expand("st -d %d -e vim --name=%n %f", &files),
+        vec![
+            strings(&[
+                "st",
+                "-d",
+                "/tmp",
+                "-e",
+                "vim",
+                "--name=a b.txt",
+                "/tmp/a b.txt"
+            ]),
+            strings(&[
+                "st",
+                "-d",
+                "/home",
+                "-e",
+                "vim",
+                "--name=c.txt",
+                "/home/c.txt"
+            ]),
+        ]
+    );
+    assert_eq!(
+        expand("app %i %U 100%%", &files[..1]),
+        vec![strings(&["app", "file:///tmp/a%20b.txt", "100%"])]
+    );
+    assert_eq!(
+        expand(&(quote("/opt/My App/run") + " %F"), &files[1..]),
+        vec![strings(&["/opt/My App/run", "/home/c.txt"])]
+    );
+    assert!(expand("  ", &files).is_empty());
+    assert_eq!(file_uri("/a%b"), "file:///a%25b");
+}
@@ -0,0 +1,37 @@
+use crate::{
+    config::CustomAction, core::empty_file_with_path, custom_actions::action_matches, ui::FileItem,
+};
+
+fn file(path: &str, extension: &str, is_dir: bool) -> FileItem {
+    let mut f = empty_file_with_path(path);
+    f.extension = extension.into();
+    f.is_dir = is_dir;
+    f
+}
+
+#[test]
+pub fn test_action_matches() {
+    let mut action: CustomAction = serde_json::from_str(
+        r#"{"name": "Lint", "command": "lint %F", "extensions": [".rs", "toml"]}"#,
+    )
+    .unwrap();
+    assert_eq!(action.min_selection, 1);
+    let rs = file("/src/main.rs", "rs", false);
+    let toml = file("/Cargo.toml", "TOML", false);
+    let dir = file("/src", "", true);
+
+    assert!(action_matches(&action, &[rs.clone(), toml.clone()]));
+    assert!(!action_matches(&action, &[]));
+    assert!(!action_matches(&action, &[rs.clone(), dir.clone()]));
+
+    action.max_selection = Some(1);
+    assert!(!action_matches(&action, &[rs.clone(), toml.clone()]));
+
+    action.mime_types = vec!["inode/*".into()];
+    assert!(action_matches(&action, &[dir.clone()]));
+
+    action.extensions.clear();
+    action.mime_types.clear();
+    action.min_selection = 0;
+    assert!(action_matches(&action, &[]));
+}
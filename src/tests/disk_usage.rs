@@ -0,0 +1,20 @@
+use crate::disk_usage::squarify;
+
+#[test]
+pub fn test_squarify() {
+    let sizes = [6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0];
+    let cells = squarify(&sizes, 6.0, 4.0);
+    assert_eq!(cells.len(), sizes.len());
+
+    //Every cell has the right area and stays inside the rectangle
+    for (cell, size) in cells.iter().zip(sizes) {
+        assert!((cell.w * cell.h - size).abs() < 1e-9);
+        assert!(cell.x >= -1e-9 && cell.y >= -1e-9);
+        assert!(cell.x + cell.w <= 6.0 + 1e-9 && cell.y + cell.h <= 4.0 + 1e-9);
+    }
+    //The example from the paper starts with a 3x2 cell on the left
+    assert!((cells[0].w - 3.0).abs() < 1e-9 && (cells[0].h - 2.0).abs() < 1e-9);
+
+    assert!(squarify(&[], 1.0, 1.0).is_empty());
+    assert_eq!(squarify(&[0.0, 0.0], 1.0, 1.0)[1].w, 0.0);
+}
@@ -0,0 +1,39 @@
+use crate::{
+    config::MountRule,
+    drives::{filesystem_space, unescape_udev},
+};
+
+#[test]
+pub fn test_filesystem_space() {
+    let (free, total) = filesystem_space("/").unwrap();
+    assert!(total > 0 && free <= total);
+    assert_eq!(filesystem_space("/nonexistent/qdfm"), None);
+}
+
+#[test]
+pub fn test_mount_rules() {
+    let snap = MountRule {
+        mount: Some("/snap/*".into()),
+        ..Default::default()
+    };
+    assert!(snap.matches("/snap/core/123", "squashfs", "/dev/loop0"));
+    assert!(!snap.matches("/home", "ext4", "/dev/sda2"));
+
+    let loop_ext4 = MountRule {
+        fs_type: Some("ext*".into()),
+        device: Some("/dev/loop*".into()),
+        ..Default::default()
+    };
+    assert!(loop_ext4.matches("/mnt/image", "ext4", "/dev/loop3"));
+    assert!(!loop_ext4.matches("/mnt/image", "vfat", "/dev/loop3"));
+
+    //A rule without fields would hide everything
+    assert!(!MountRule::default().matches("/", "ext4", "/dev/sda1"));
+}
+
+#[test]
+pub fn test_unescape_udev() {
+    assert_eq!(unescape_udev("My\\x20Disk"), "My Disk");
+    assert_eq!(unescape_udev("caf\\xc3\\xa9"), "café");
+    assert_eq!(unescape_udev("back\\slash\\x2"), "back\\slash\\x2");
+}
@@ -0,0 +1,53 @@
+use crate::{
+    icon_theme::{parse_index_theme, DirKind},
+    mime::{parse_icons, MimeDatabase},
+};
+
+#[test]
+pub fn test_parse_index_theme() {
+    let theme = parse_index_theme(
+        "Test",
+        "[Icon Theme]\nName=Test\nInherits=Parent, hicolor\nDirectories=16x16/apps,scalable/apps\n\
+         ScaledDirectories=16x16@2/apps\n\n[16x16/apps]\nSize=16\nType=Fixed\n\n\
+         # comment\n[scalable/apps]\nSize=64\nType=Scalable\nMinSize=8\nMaxSize=512\n\n\
+         [16x16@2/apps]\nSize=16\nScale=2\n\n[unlisted]\nSize=32\n",
+    );
+    assert_eq!(theme.inherits, vec!["Parent", "hicolor"]);
+    assert_eq!(theme.dirs.len(), 3);
+
+    let fixed = &theme.dirs[0];
+    assert_eq!(fixed.kind, DirKind::Fixed);
+    assert!(fixed.matches_size(16, 1));
+    assert!(!fixed.matches_size(16, 2));
+    assert!(!fixed.matches_size(18, 1));
+    assert_eq!(fixed.size_distance(24, 1), 8);
+
+    let scalable = &theme.dirs[1];
+    assert_eq!(scalable.kind, DirKind::Scalable);
+    assert!(scalable.matches_size(256, 1));
+    assert!(!scalable.matches_size(1024, 1));
+    assert_eq!(scalable.size_distance(4, 1), 4);
+
+    let scaled = &theme.dirs[2];
+    assert_eq!(scaled.kind, DirKind::Threshold);
+    assert_eq!(scaled.threshold, 2);
+    assert!(scaled.matches_size(17, 2));
+    assert_eq!(scaled.size_distance(16, 2), 0);
+}
+
+#[test]
+pub fn test_icon_names() {
+    assert_eq!(
+        parse_icons("application/x-foo:foo-icon\nbroken\n"),
+        vec![("application/x-foo".to_string(), "foo-icon".to_string())]
+    );
+    let db = MimeDatabase::from_globs(Vec::new());
+    assert_eq!(
+        db.icon_names("text/plain"),
+        vec!["text-plain", "text-x-generic"]
+    );
+    assert_eq!(
+        db.icon_names("image/png"),
+        vec!["image-png", "image-x-generic"]
+    );
+}
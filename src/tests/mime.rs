@@ -0,0 +1,101 @@
+use crate::{
+    desktop_entries::{parse_desktop_entry, parse_mimeapps, set_default_in_mimeapps, MimeApps},
+    mime::{glob_match, parse_globs2, MimeDatabase},
+};
+
+#[test]
+pub fn test_glob_match() {
+    assert!(glob_match("*.txt", "notes.txt"));
+    assert!(!glob_match("*.txt", "notes.txt.bak"));
+    assert!(glob_match("*.tar.*", "a.tar.gz"));
+    assert!(glob_match("README*", "README.md"));
+    assert!(glob_match("?akefile", "Makefile"));
+    assert!(glob_match("*.[ch]", "main.c"));
+    assert!(!glob_match("*.[!ch]", "main.c"));
+    assert!(glob_match("*.[a-c]pp", "x.bpp"));
+    assert!(glob_match("*", ""));
+    assert!(!glob_match("core", "cores"));
+}
+
+#[test]
+pub fn test_mime_for_name() {
+    let db = MimeDatabase::from_globs(parse_globs2(
+        "# comment\n50:application/gzip:*.gz\n50:application/x-compressed-tar:*.tar.gz\n\
+         50:text/x-c++src:*.C:cs\n50:text/x-csrc:*.c\n10:text/x-readme:readme*\n",
+    ));
+    assert_eq!(
+        db.mime_for_name("a.tar.gz"),
+        Some("application/x-compressed-tar")
+    );
+    assert_eq!(db.mime_for_name("a.GZ"), Some("application/gzip"));
+    assert_eq!(db.mime_for_name("main.C"), Some("text/x-c++src"));
+    assert_eq!(db.mime_for_name("main.c"), Some("text/x-csrc"));
+    assert_eq!(db.mime_for_name("README.md"), Some("text/x-readme"));
+    assert_eq!(db.mime_for_name("image.png"), None);
+    assert_eq!(
+        db.with_parents("text/x-csrc"),
+        vec!["text/x-csrc", "text/plain"]
+    );
+}
+
+#[test]
+pub fn test_parse_desktop_entry() {
+    let content = "[Desktop Entry]\nName=Vim\nName[fr]=Vim FR\nExec=vim %F\nTerminal=true\n\
+                   Type=Application\nMimeType=text/plain;text/x-c;\n[Desktop Action new]\nName=Other\n";
+    let entry = parse_desktop_entry("vim.desktop", content).unwrap();
+    assert_eq!(entry.name, "Vim");
+    assert_eq!(entry.exec, "vim %F");
+    assert!(entry.terminal);
+    assert_eq!(entry.mime_types, vec!["text/plain", "text/x-c"]);
+
+    assert!(parse_desktop_entry(
+        "a.desktop",
+        &(content.to_owned() + "[Desktop Entry]\nHidden=true\n")
+    )
+    .is_none());
+    assert!(
+        parse_desktop_entry("l.desktop", "[Desktop Entry]\nName=L\nType=Link\nURL=x\n").is_none()
+    );
+}
+
+#[test]
+pub fn test_parse_mimeapps() {
+    let mut apps = MimeApps::default();
+    parse_mimeapps(
+        "[Default Applications]\ntext/plain=nvim.desktop;\n[Added Associations]\ntext/plain=a.desktop;b.desktop;\n",
+        &mut apps,
+    );
+    parse_mimeapps(
+        "[Default Applications]\ntext/plain=vim.desktop\n[Removed Associations]\nimage/png=gimp.desktop;\n",
+        &mut apps,
+    );
+    assert_eq!(
+        apps.defaults["text/plain"],
+        vec!["nvim.desktop", "vim.desktop"]
+    );
+    assert_eq!(apps.added["text/plain"], vec!["a.desktop", "b.desktop"]);
+    assert_eq!(apps.removed["image/png"], vec!["gimp.desktop"]);
+}
+
+#[test]
+pub fn test_set_default_in_mimeapps() {
+    assert_eq!(
+        set_default_in_mimeapps("", "text/plain", "vim.desktop"),
+        "[Default Applications]\ntext/plain=vim.desktop;\n"
+    );
+    let content = "[Added Associations]\nimage/png=gimp.desktop;\n\n[Default Applications]\n\
+                   text/plain=nvim.desktop;vim.desktop;\n[Removed Associations]\n\
+                   text/plain=vim.desktop;\nimage/png=vim.desktop;a.desktop\n";
+    assert_eq!(
+        set_default_in_mimeapps(content, "text/plain", "vim.desktop"),
+        "[Added Associations]\nimage/png=gimp.desktop;\n\n[Default Applications]\n\
+         text/plain=vim.desktop;nvim.desktop;\n[Removed Associations]\n\
+         image/png=vim.desktop;a.desktop\n"
+    );
+    assert_eq!(
+        set_default_in_mimeapps(content, "image/png", "a.desktop"),
+        "[Added Associations]\nimage/png=gimp.desktop;\n\n[Default Applications]\n\
+         image/png=a.desktop;\ntext/plain=nvim.desktop;vim.desktop;\n[Removed Associations]\n\
+         text/plain=vim.desktop;\nimage/png=vim.desktop;\n"
+    );
+}
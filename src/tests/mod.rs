@@ -1,2 +1,21 @@
+pub mod checksums;
 pub mod clipboard;
+pub mod collation;
+pub mod columns;
+pub mod command_template;
+pub mod custom_actions;
+pub mod disk_usage;
+pub mod drives;
+pub mod icon_theme;
+pub mod mime;
+pub mod places;
+pub mod preview;
+pub mod properties;
+pub mod search;
+pub mod source_viewer;
+pub mod status_bar;
+pub mod thumbnails;
 pub mod types;
+pub mod udisks;
+pub mod view_settings;
+pub mod xattr;
@@ -0,0 +1,48 @@
+use crate::{
+    places::{format_bookmarks, parse_bookmarks, parse_user_dirs, Bookmark},
+    utils::percent_decode,
+};
+
+#[test]
+pub fn test_bookmarks() {
+    let content = "file:///home/user/My%20Projects\nfile:///mnt/data Data disk\n\
+                   sftp://example.com/srv Server\n\n";
+    let bookmarks = parse_bookmarks(content);
+    assert_eq!(bookmarks.len(), 3);
+    assert_eq!(
+        bookmarks[0].path().as_deref(),
+        Some("/home/user/My Projects")
+    );
+    assert_eq!(bookmarks[0].display_name(), "My Projects");
+    assert_eq!(bookmarks[1].label.as_deref(), Some("Data disk"));
+    assert_eq!(bookmarks[1].display_name(), "Data disk");
+    assert_eq!(bookmarks[2].path(), None);
+    //Remote bookmarks are written back as they were
+    assert_eq!(
+        format_bookmarks(&bookmarks),
+        content.trim_end().to_string() + "\n"
+    );
+
+    let bookmark = Bookmark::from_path("/tmp/a b");
+    assert_eq!(bookmark.uri, "file:///tmp/a%20b");
+    assert_eq!(bookmark.path().as_deref(), Some("/tmp/a b"));
+}
+
+#[test]
+pub fn test_percent_decode() {
+    assert_eq!(percent_decode("a%20b%C3%A9"), "a bé".as_bytes());
+    assert_eq!(percent_decode("100%"), b"100%");
+    assert_eq!(percent_decode("%zz%+1"), b"%zz%+1");
+}
+
+#[test]
+pub fn test_user_dirs() {
+    let dirs = parse_user_dirs(
+        "# comment\nXDG_DESKTOP_DIR=\"$HOME/Desktop\"\nXDG_MUSIC_DIR=\"/srv/music/\"\n\
+         XDG_TEMPLATES_DIR=\"$HOME/\"\nXDG_VIDEOS_DIR=\"Videos\"\n",
+        "/home/user",
+    );
+    assert_eq!(dirs.len(), 2);
+    assert_eq!(dirs["DESKTOP"], "/home/user/Desktop");
+    assert_eq!(dirs["MUSIC"], "/srv/music");
+}
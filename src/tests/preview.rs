@@ -0,0 +1,15 @@
+use crate::preview::decode_text;
+
+#[test]
+pub fn test_decode_text() {
+    assert_eq!(decode_text(b"hello", "us-ascii"), "hello");
+    //"é" cut in the middle by the end of the read
+    assert_eq!(decode_text(&[b'a', 0xc3], "utf-8"), "a");
+    assert_eq!(decode_text(&[b'a', 0xff, b'b'], "utf-8"), "a\u{fffd}b");
+    assert_eq!(decode_text(&[0xe9, b't', 0xe9], "iso-8859-1"), "été");
+    assert_eq!(
+        decode_text(&[0xff, 0xfe, b'h', 0, b'i', 0], "utf-16le"),
+        "hi"
+    );
+    assert_eq!(decode_text(&[0, b'h', 0, b'i', 0], "utf-16be"), "hi");
+}
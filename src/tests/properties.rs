@@ -0,0 +1,96 @@
+use crate::{
+    callbacks::properties::{format_mode, parse_mode},
+    file_properties::{directory_usage, DirectoryUsage},
+};
+use std::{os::unix::fs::symlink, path::PathBuf};
+
+#[test]
+pub fn test_format_mode() {
+    assert_eq!(format_mode(0o755, 0), "755");
+    assert_eq!(format_mode(0o644, 0), "644");
+    assert_eq!(format_mode(0o4755, 0), "4755");
+    assert_eq!(format_mode(0o1777, 0), "1777");
+    assert_eq!(format_mode(0o000, 0), "000");
+    //Mixed digits
+    assert_eq!(format_mode(0o644, 0o011), "6??");
+    assert_eq!(format_mode(0o755, 0o2000), "?755");
+}
+
+#[test]
+pub fn test_parse_mode() {
+    assert_eq!(parse_mode("755"), Some(0o755));
+    assert_eq!(parse_mode(" 0644 "), Some(0o644));
+    assert_eq!(parse_mode("4755"), Some(0o4755));
+    assert_eq!(parse_mode("7"), Some(0o7));
+    assert_eq!(parse_mode(""), None);
+    assert_eq!(parse_mode("789"), None);
+    assert_eq!(parse_mode("17777"), None);
+    assert_eq!(parse_mode("6??"), None);
+    assert_eq!(parse_mode("rwx"), None);
+}
+
+#[test]
+pub fn test_directory_usage() {
+    let dir = "/tmp/qdfm_tests/usage";
+    std::fs::remove_dir_all(dir).ok();
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(dir.to_owned() + "/big", vec![1u8; 10000]).unwrap();
+    std::fs::write(dir.to_owned() + "/small", vec![1u8; 100]).unwrap();
+    std::fs::hard_link(dir.to_owned() + "/big", dir.to_owned() + "/big_link").unwrap();
+
+    let mut usage = DirectoryUsage::default();
+    let mut seen = std::collections::HashSet::new();
+    for name in ["big", "small", "big_link"] {
+        let meta = std::fs::metadata(dir.to_owned() + "/" + name).unwrap();
+        usage.children.push((name.to_string(), 0));
+        let ino = std::os::unix::fs::MetadataExt::ino(&meta);
+        usage.add(&meta, seen.insert(ino));
+    }
+    assert_eq!(usage.apparent, 20100);
+    assert_eq!(usage.unique_apparent, 10100);
+    assert!(usage.unique_on_disk < usage.on_disk);
+
+    let top = usage.top_children(2);
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].1, 10000);
+    assert_eq!(top[1].1, 10000);
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+pub fn test_directory_usage_walk() {
+    let dir = PathBuf::from("/tmp/qdfm_tests/usage_walk");
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("big"), vec![1u8; 10000]).unwrap();
+    std::fs::write(dir.join("sub/small"), vec![1u8; 100]).unwrap();
+    std::fs::hard_link(dir.join("big"), dir.join("sub/big_link")).unwrap();
+    symlink("big", dir.join("link")).unwrap();
+
+    let mut calls = 0;
+    let usage = directory_usage(&[dir.clone()], |_| calls += 1);
+    //big, small, big_link and link, and sub
+    assert_eq!(usage.files, 4);
+    assert_eq!(usage.dirs, 1);
+    //The root and its 5 entries
+    assert_eq!(calls, 6);
+    //The hardlink is only counted once in the unique size
+    assert_eq!(usage.unique_apparent, usage.apparent - 10000);
+    //The symlink isn't followed
+    let link_size = std::fs::symlink_metadata(dir.join("link")).unwrap().len() as i64;
+    let link = usage.children.iter().find(|c| c.0 == "link").unwrap();
+    assert_eq!(link.1, link_size);
+
+    let mut children: Vec<&str> = usage.children.iter().map(|c| c.0.as_str()).collect();
+    children.sort();
+    assert_eq!(children, ["big", "link", "sub"]);
+    let sub = usage.children.iter().find(|c| c.0 == "sub").unwrap();
+    assert!(sub.1 >= 10100);
+
+    //Several paths are the children themselves
+    let usage = directory_usage(&[dir.join("big"), dir.join("sub")], |_| ());
+    assert_eq!((usage.files, usage.dirs), (3, 1));
+    assert_eq!(usage.children.len(), 2);
+    assert_eq!(usage.unique_apparent, usage.apparent - 10000);
+    std::fs::remove_dir_all(&dir).ok();
+}
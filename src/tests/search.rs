@@ -0,0 +1,129 @@
+use std::{collections::HashMap, path::Path, sync::Mutex};
+
+use crate::{
+    search::{
+        file_contains, parse_date, parse_owner, parse_size, walk, Criteria, NamePattern, KIND_DIR,
+        KIND_FILE,
+    },
+    ui::SearchQuery,
+};
+
+#[test]
+pub fn test_parse_size() {
+    assert_eq!(parse_size("512"), Some(512));
+    assert_eq!(parse_size("10K"), Some(10 * 1024));
+    assert_eq!(parse_size("1.5 MiB"), Some(3 * 512 * 1024));
+    assert_eq!(parse_size("2gb"), Some(2 << 30));
+    assert_eq!(parse_size("10 apples"), None);
+    assert_eq!(parse_size("M"), None);
+}
+
+#[test]
+pub fn test_parse_date() {
+    let now = 1_000_000;
+    assert_eq!(parse_date("2h", now), Some(now - 7200));
+    assert_eq!(parse_date("7d", now), Some(now - 7 * 86400));
+    assert_eq!(parse_date("1w", now), Some(now - 7 * 86400));
+    assert!(parse_date("2024-01-31", now).is_some());
+    assert_eq!(parse_date("2024-02-31", now), None);
+    assert_eq!(parse_date("yesterday", now), None);
+}
+
+#[test]
+pub fn test_parse_owner() {
+    let users = HashMap::from([(0, "root".to_string()), (1000, "user".to_string())]);
+    assert_eq!(parse_owner("user", &users), Some(1000));
+    assert_eq!(parse_owner("42", &users), Some(42));
+    assert_eq!(parse_owner("nobody", &users), None);
+}
+
+#[test]
+pub fn test_name_pattern() {
+    let glob = NamePattern::new("*.TXT", false).unwrap();
+    assert!(glob.matches("notes.txt"));
+    assert!(!glob.matches("notes.txt.bak"));
+    let text = NamePattern::new("port", false).unwrap();
+    assert!(text.matches("Report.pdf"));
+    let regex = NamePattern::new(r"^\d+\.log$", true).unwrap();
+    assert!(regex.matches("2024.log"));
+    assert!(!regex.matches("a2024.log"));
+    assert!(NamePattern::new("(", true).is_err());
+}
+
+fn temp_tree(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("qdfm_search_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(dir.join("sub").join(".hidden")).unwrap();
+    std::fs::write(dir.join("a.txt"), "hello\nneedle here\n").unwrap();
+    std::fs::write(dir.join("sub").join("b.txt"), "no match\n").unwrap();
+    std::fs::write(dir.join("sub").join("c.bin"), b"needle\0binary").unwrap();
+    std::fs::write(dir.join("sub").join(".hidden").join("d.txt"), "needle").unwrap();
+    dir
+}
+
+fn search(root: &Path, query: SearchQuery, hidden: bool) -> Vec<String> {
+    let criteria = Criteria::from_query(&query, hidden).unwrap();
+    let found = Mutex::new(Vec::new());
+    walk(root, &criteria, &|| false, &|path| {
+        let relative = path
+            .strip_prefix(root)
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        found.lock().unwrap().push(relative);
+    });
+    let mut found = found.into_inner().unwrap();
+    found.sort();
+    found
+}
+
+#[test]
+pub fn test_file_contains() {
+    let dir = temp_tree("contains");
+    let pattern = regex::bytes::Regex::new("needle").unwrap();
+    assert!(file_contains(&dir.join("a.txt"), &pattern, &|| false));
+    assert!(!file_contains(&dir.join("sub/b.txt"), &pattern, &|| false));
+    //Binary files are skipped
+    assert!(!file_contains(&dir.join("sub/c.bin"), &pattern, &|| false));
+    //Found in a line longer than the chunks, across two of them
+    let long = dir.join("long.txt");
+    let mut content = vec![b'x'; 64 * 1024 - 3];
+    content.extend_from_slice(b"needle");
+    content.extend(vec![b'x'; 200 * 1024]);
+    std::fs::write(&long, content).unwrap();
+    assert!(file_contains(&long, &pattern, &|| false));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+pub fn test_walk() {
+    let dir = temp_tree("walk");
+    let query = |name: &str, content: &str, kind: i32| SearchQuery {
+        name: name.into(),
+        content: content.into(),
+        kind,
+        ..Default::default()
+    };
+    assert_eq!(
+        search(&dir, query("*.txt", "", KIND_FILE), false),
+        ["a.txt", "sub/b.txt"]
+    );
+    assert_eq!(
+        search(&dir, query("*.txt", "", KIND_FILE), true),
+        ["a.txt", "sub/.hidden/d.txt", "sub/b.txt"]
+    );
+    assert_eq!(search(&dir, query("", "", KIND_DIR), false), ["sub"]);
+    assert_eq!(
+        search(&dir, query("", "NEEDLE", 0), true),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        search(&dir, query("", "needle", 0), true),
+        ["a.txt", "sub/.hidden/d.txt"]
+    );
+    let large = SearchQuery {
+        min_size: "15".into(),
+        ..Default::default()
+    };
+    assert_eq!(search(&dir, large, false), ["a.txt"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
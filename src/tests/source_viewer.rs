@@ -0,0 +1,42 @@
+use std::io::BufReader;
+
+use crate::source_viewer::{find_syntax, hex_line, read_line_capped};
+
+#[test]
+pub fn test_read_line_capped() {
+    let data = b"short\r\n0123456789\nlast";
+    //A tiny buffer so that lines span several reads
+    let mut reader = BufReader::with_capacity(4, &data[..]);
+    let mut line = Vec::new();
+    assert_eq!(read_line_capped(&mut reader, &mut line, 6).unwrap(), 7);
+    assert_eq!(line, b"short\r");
+    assert_eq!(read_line_capped(&mut reader, &mut line, 6).unwrap(), 11);
+    assert_eq!(line, b"012345");
+    assert_eq!(read_line_capped(&mut reader, &mut line, 6).unwrap(), 4);
+    assert_eq!(line, b"last");
+    assert_eq!(read_line_capped(&mut reader, &mut line, 6).unwrap(), 0);
+}
+
+#[test]
+pub fn test_hex_line() {
+    assert_eq!(
+        hex_line(b"Hello, world!\n\x00\xff"),
+        "48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 ff |Hello, world!...|"
+    );
+    assert_eq!(
+        hex_line(b"ab"),
+        "61 62                                            |ab|"
+    );
+}
+
+#[test]
+pub fn test_find_syntax() {
+    assert_eq!(find_syntax("/src/main.rs", "").unwrap().name, "Rust");
+    assert_eq!(find_syntax("/src/Makefile", "").unwrap().name, "Makefile");
+    assert_eq!(
+        find_syntax("/bin/script", "#!/usr/bin/env python3")
+            .unwrap()
+            .name,
+        "Python"
+    );
+}
@@ -0,0 +1,22 @@
+use crate::status_bar::{dir_size, format_counts};
+
+#[test]
+pub fn test_format_counts() {
+    assert_eq!(format_counts(0, 0, 0), "Empty");
+    assert_eq!(format_counts(0, 0, 2), "Empty (2 hidden)");
+    assert_eq!(format_counts(1, 0, 0), "1 folder");
+    assert_eq!(format_counts(0, 3, 0), "3 files");
+    assert_eq!(format_counts(2, 1, 4), "2 folders, 1 file (4 hidden)");
+}
+
+#[test]
+pub fn test_dir_size() {
+    let dir = std::env::temp_dir().join(format!("qdfm_dir_size_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("a"), [0u8; 10]).unwrap();
+    std::fs::write(dir.join("sub").join("b"), [0u8; 5]).unwrap();
+    let path = dir.to_string_lossy();
+    assert_eq!(dir_size(&path, || false), Some(15));
+    assert_eq!(dir_size(&path, || true), None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
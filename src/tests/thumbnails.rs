@@ -0,0 +1,31 @@
+use std::{fs, path::Path};
+
+use crate::thumbnails::{is_up_to_date, thumbnail_name, write_thumbnail};
+
+#[test]
+pub fn test_thumbnail_name() {
+    //The example of the freedesktop thumbnail specification
+    assert_eq!(
+        thumbnail_name("file:///home/jens/photos/me.png"),
+        "c6ee772d9e49320e97ec29a7eb5b1697.png"
+    );
+}
+
+#[test]
+pub fn test_thumbnail_up_to_date() {
+    let dir = "/tmp/qdfm_tests/thumbnails";
+    fs::create_dir_all(dir).unwrap();
+    let thumbnail = Path::new(dir).join(thumbnail_name("file:///tmp/a.png"));
+    write_thumbnail(&thumbnail, "file:///tmp/a.png", 1700000000, 2, 1, &[255; 8]).unwrap();
+
+    assert!(is_up_to_date(&thumbnail, "file:///tmp/a.png", 1700000000));
+    //The image changed since
+    assert!(!is_up_to_date(&thumbnail, "file:///tmp/a.png", 1700000001));
+    assert!(!is_up_to_date(&thumbnail, "file:///tmp/b.png", 1700000000));
+    assert!(!is_up_to_date(
+        &Path::new(dir).join("missing.png"),
+        "file:///tmp/a.png",
+        0
+    ));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -14,3 +14,22 @@ pub fn test_int_converts() {
     assert_eq!(564456456465, i32_to_i64(i64_to_i32(564456456465)));
     assert_eq!(-95135745, i32_to_i64(i64_to_i32(-95135745)));
 }
+
+#[test]
+pub fn test_parse_date() {
+    use crate::utils::types::{format_date, parse_date};
+    //Minute precision, since that's what format_date shows
+    let t = 1700000040;
+    assert_eq!(parse_date(&format_date(t)), Some(t));
+    assert_eq!(
+        parse_date(" 2024-05-01 05:30 PM "),
+        parse_date("2024-05-01 17:30")
+    );
+    assert_eq!(
+        parse_date("2024-05-01 17:30:00"),
+        parse_date("2024-05-01 17:30")
+    );
+    assert_eq!(parse_date("2024-13-01 17:30"), None);
+    assert_eq!(parse_date("yesterday"), None);
+    assert_eq!(parse_date(""), None);
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use dbus::arg::{PropMap, RefArg, Variant};
+
+use crate::udisks::{busy_processes, volumes_from_objects};
+
+fn props(values: Vec<(&str, Box<dyn RefArg>)>) -> PropMap {
+    values
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), Variant(v)))
+        .collect()
+}
+
+#[test]
+pub fn test_volumes_from_objects() {
+    let drive = "/org/freedesktop/UDisks2/drives/USB_Stick";
+    let mut objects: HashMap<String, HashMap<String, PropMap>> = HashMap::new();
+    objects.insert(
+        drive.into(),
+        HashMap::from([(
+            "org.freedesktop.UDisks2.Drive".to_string(),
+            props(vec![
+                ("Removable", Box::new(true)),
+                ("CanPowerOff", Box::new(true)),
+            ]),
+        )]),
+    );
+    objects.insert(
+        "/org/freedesktop/UDisks2/block_devices/sdb1".into(),
+        HashMap::from([
+            (
+                "org.freedesktop.UDisks2.Block".to_string(),
+                props(vec![
+                    ("Device", Box::new(b"/dev/sdb1\0".to_vec())),
+                    ("IdLabel", Box::new("STICK".to_string())),
+                    ("IdType", Box::new("vfat".to_string())),
+                    ("Size", Box::new(8_000_000_000u64)),
+                    ("Drive", Box::new(dbus::Path::from(drive))),
+                ]),
+            ),
+            (
+                "org.freedesktop.UDisks2.Filesystem".to_string(),
+                props(vec![(
+                    "MountPoints",
+                    Box::new(vec![b"/run/media/user/STICK\0".to_vec()]),
+                )]),
+            ),
+        ]),
+    );
+    //Swap has no filesystem to mount
+    objects.insert(
+        "/org/freedesktop/UDisks2/block_devices/sda2".into(),
+        HashMap::from([(
+            "org.freedesktop.UDisks2.Block".to_string(),
+            props(vec![("Device", Box::new(b"/dev/sda2\0".to_vec()))]),
+        )]),
+    );
+
+    let volumes = volumes_from_objects(&objects);
+    assert_eq!(volumes.len(), 1);
+    let stick = &volumes[0];
+    assert_eq!(stick.device, "/dev/sdb1");
+    assert_eq!(stick.display_name(), "STICK");
+    assert_eq!(stick.fs_type, "vfat");
+    assert_eq!(stick.mount_points, vec!["/run/media/user/STICK"]);
+    assert_eq!(stick.drive, drive);
+    assert!(stick.removable && stick.can_power_off && !stick.ignored);
+}
+
+#[test]
+pub fn test_busy_processes() {
+    let cwd = std::env::current_dir().unwrap();
+    let own = format!("({})", std::process::id());
+    assert!(busy_processes(&cwd.to_string_lossy())
+        .iter()
+        .any(|p| p.ends_with(&own)));
+}
@@ -0,0 +1,35 @@
+use crate::{
+    config::Config,
+    view_settings::{ViewSettings, ViewSettingsDb},
+};
+
+#[test]
+pub fn test_view_settings_db() {
+    let config_default = || ViewSettings::from_config(&Config::new());
+    let defaults = config_default();
+    assert_eq!(defaults.sort, "name");
+    assert!(defaults.ascending);
+    assert_eq!(defaults.columns.len(), 3);
+    assert_eq!(defaults.headers()[0].sort, 1);
+
+    let mut db = ViewSettingsDb::default();
+    db.update("/tmp", config_default, |s| {
+        s.sort = "size".into();
+        s.ascending = false;
+    });
+    let tmp = db.get("/tmp", config_default);
+    assert_eq!(tmp.sort, "size");
+    assert_eq!(tmp.headers()[1].sort, 2);
+    assert_eq!(tmp.headers()[0].sort, 0);
+    assert_eq!(db.get("/home", config_default), defaults);
+
+    //The directory's view becomes the default and it loses its own
+    db.set_default_from("/tmp", config_default);
+    assert!(db.directories.is_empty());
+    assert_eq!(db.get("/home", config_default), tmp);
+
+    //Survives a round trip
+    let json = serde_json::to_string(&db).unwrap();
+    let db: ViewSettingsDb = serde_json::from_str(&json).unwrap();
+    assert_eq!(db.default, Some(tmp));
+}
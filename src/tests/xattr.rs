@@ -0,0 +1,78 @@
+use crate::xattr::{
+    acl_from_mode, decode_acl, encode_acl, format_xattr_value, parse_xattr_value, AclEntry,
+    XattrText, ACL_GROUP, ACL_GROUP_OBJ, ACL_MASK, ACL_OTHER, ACL_UNDEFINED_ID, ACL_USER,
+    ACL_USER_OBJ,
+};
+
+#[test]
+pub fn test_acl_roundtrip() {
+    let mode_acl = acl_from_mode(0o750);
+    assert_eq!(decode_acl(&encode_acl(&mode_acl)), Some(mode_acl.clone()));
+
+    //A named entry gets a mask, and everything ends up sorted
+    let mut entries = vec![AclEntry {
+        tag: ACL_GROUP,
+        perm: 0o6,
+        id: 100,
+    }];
+    entries.extend(mode_acl);
+    let decoded = decode_acl(&encode_acl(&entries)).unwrap();
+    let tags: Vec<u16> = decoded.iter().map(|e| e.tag).collect();
+    assert_eq!(
+        tags,
+        vec![ACL_USER_OBJ, ACL_GROUP_OBJ, ACL_GROUP, ACL_MASK, ACL_OTHER]
+    );
+    assert_eq!(
+        decoded[3],
+        AclEntry {
+            tag: ACL_MASK,
+            perm: 0o7,
+            id: ACL_UNDEFINED_ID
+        }
+    );
+    assert!(!decoded.iter().any(|e| e.tag == ACL_USER));
+
+    //Bad version / truncated
+    assert_eq!(decode_acl(&[1, 0, 0, 0]), None);
+    assert_eq!(decode_acl(&[2, 0, 0, 0, 1]), None);
+    assert_eq!(decode_acl(&[2, 0, 0, 0]), Some(vec![]));
+}
+
+#[test]
+pub fn test_xattr_value() {
+    let text = |text: &str, hex, nul_terminated| XattrText {
+        text: text.into(),
+        hex,
+        nul_terminated,
+    };
+    assert_eq!(format_xattr_value(b"hello"), text("hello", false, false));
+    assert_eq!(format_xattr_value(b"hello\0"), text("hello", false, true));
+    assert_eq!(format_xattr_value(b"0x12"), text("0x12", false, false));
+    assert_eq!(
+        format_xattr_value(&[0xde, 0xad, 0x01]),
+        text("0xdead01", true, false)
+    );
+    assert_eq!(
+        parse_xattr_value("hello", false, false),
+        Some(b"hello".to_vec())
+    );
+    assert_eq!(
+        parse_xattr_value("0x12", false, false),
+        Some(b"0x12".to_vec())
+    );
+    assert_eq!(
+        parse_xattr_value("0xdead01", true, false),
+        Some(vec![0xde, 0xad, 0x01])
+    );
+    assert_eq!(parse_xattr_value("0xabc", true, false), None);
+    assert_eq!(parse_xattr_value("0xzz", true, false), None);
+
+    //Shown and saved back unchanged, including the trailing NUL
+    for value in [&b"hello\0"[..], b"0x12", &[0xde, 0xad, 0x01]] {
+        let shown = format_xattr_value(value);
+        assert_eq!(
+            parse_xattr_value(&shown.text, shown.hex, shown.nul_terminated).as_deref(),
+            Some(value)
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    sync::{Condvar, Mutex, MutexGuard, OnceLock},
+    thread,
+    time::UNIX_EPOCH,
+};
+
+use md5::Md5;
+use sha2::Digest;
+use slint::{ComponentHandle, Image};
+
+use crate::{
+    command_template::file_uri,
+    mime::mime_db,
+    ui::{main_window::run_with_main_window, FileManager},
+    utils::{error_handling::log_error_str, xdg},
+};
+
+/*
+ *  Image thumbnails for the icon view, cached as the freedesktop thumbnail spec says:
+ *  $XDG_CACHE_HOME/thumbnails/normal/<md5 of the file URI>.png, with the URI and the
+ *  modification time of the image in the PNG, so that other file managers share them.
+ *  Images that can't be read get a marker in thumbnails/fail/qdfm-<version>/.
+ *
+ *  Thumbnails are made by one worker thread. The UI asks for a thumbnail (get), gets an empty
+ *  image until it is ready, then FileManager.thumbnails_generation is incremented so that it
+ *  asks again.
+ * */
+
+///Size of the "normal" thumbnails
+const SIZE: u32 = 128;
+///Larger images aren't thumbnailed
+const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
+const FAIL_DIR: &str = concat!("qdfm-", env!("CARGO_PKG_VERSION"));
+const MIME_TYPES: [&str; 5] = [
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/bmp",
+];
+
+pub fn thumbnails_dir() -> PathBuf {
+    xdg::cache_home().join("thumbnails")
+}
+
+///The file name of the thumbnail of a URI
+pub fn thumbnail_name(uri: &str) -> String {
+    format!("{:x}.png", Md5::digest(uri.as_bytes()))
+}
+
+pub fn thumbnail_path(uri: &str) -> PathBuf {
+    thumbnails_dir().join("normal").join(thumbnail_name(uri))
+}
+
+fn fail_path(uri: &str) -> PathBuf {
+    thumbnails_dir()
+        .join("fail")
+        .join(FAIL_DIR)
+        .join(thumbnail_name(uri))
+}
+
+///Whether the type of the file (from its name) is one that can be thumbnailed
+pub fn can_thumbnail(path: &str) -> bool {
+    let db = mime_db();
+    db.mime_for_name(path)
+        .is_some_and(|mime| MIME_TYPES.contains(&db.unalias(mime).as_str()))
+}
+
+///Whether a thumbnail was made from this version of the file
+pub fn is_up_to_date(thumbnail: &Path, uri: &str, mtime: u64) -> bool {
+    let Ok(file) = File::open(thumbnail) else {
+        return false;
+    };
+    let Ok(reader) = png::Decoder::new(BufReader::new(file)).read_info() else {
+        return false;
+    };
+    let text = &reader.info().uncompressed_latin1_text;
+    let value = |keyword: &str| {
+        text.iter()
+            .find(|t| t.keyword == keyword)
+            .map(|t| t.text.as_str())
+    };
+    value("Thumb::URI") == Some(uri) && value("Thumb::MTime") == Some(&mtime.to_string())
+}
+
+///Writes an RGBA image as a thumbnail of the URI. Written to a temporary file first so that
+///other programs never read half a thumbnail.
+pub fn write_thumbnail(
+    dest: &Path,
+    uri: &str,
+    mtime: u64,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+) -> Result<(), String> {
+    let parent = dest.parent().ok_or("No parent directory")?;
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    //The directories and the thumbnails are private, they can show what the user looked at
+    fs::set_permissions(parent, fs::Permissions::from_mode(0o700)).ok();
+    let tmp = dest.with_extension(format!("png.{}.tmp", std::process::id()));
+    let file = File::create(&tmp).map_err(|e| e.to_string())?;
+    file.set_permissions(fs::Permissions::from_mode(0o600))
+        .map_err(|e| e.to_string())?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let written = (|| {
+        encoder.add_text_chunk("Thumb::URI".into(), uri.into())?;
+        encoder.add_text_chunk("Thumb::MTime".into(), mtime.to_string())?;
+        encoder.add_text_chunk("Software".into(), "qdfm".into())?;
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(rgba)?;
+        writer.finish()
+    })();
+    if let Err(e) = written {
+        fs::remove_file(&tmp).ok();
+        return Err(e.to_string());
+    }
+    fs::rename(&tmp, dest).map_err(|e| e.to_string())
+}
+
+///Returns the path of an up to date thumbnail of the file, making it if needed.
+///None if the file can't be thumbnailed.
+pub fn generate(path: &str) -> Option<PathBuf> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file()
+        || metadata.len() > MAX_FILE_SIZE
+        || Path::new(path).starts_with(thumbnails_dir())
+    {
+        return None;
+    }
+    let mtime = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    let uri = file_uri(path);
+    let dest = thumbnail_path(&uri);
+    if is_up_to_date(&dest, &uri, mtime) {
+        return Some(dest);
+    }
+    let failed = fail_path(&uri);
+    if is_up_to_date(&failed, &uri, mtime) {
+        return None;
+    }
+
+    match image::open(path) {
+        Ok(img) => {
+            let img = if img.width() > SIZE || img.height() > SIZE {
+                img.thumbnail(SIZE, SIZE)
+            } else {
+                img
+            };
+            let rgba = img.to_rgba8();
+            match write_thumbnail(&dest, &uri, mtime, rgba.width(), rgba.height(), &rgba) {
+                Ok(()) => Some(dest),
+                Err(e) => {
+                    log_error_str(&format!("Could not save the thumbnail of {}: {}", path, e));
+                    None
+                }
+            }
+        }
+        Err(_) => {
+            //A transparent pixel, only the text chunks matter
+            if let Err(e) = write_thumbnail(&failed, &uri, mtime, 1, 1, &[0; 4]) {
+                log_error_str(&format!("Could not save the thumbnail of {}: {}", path, e));
+            }
+            None
+        }
+    }
+}
+
+/*
+ *  The worker thread and its queue
+ * */
+
+#[derive(Default)]
+struct Queue {
+    pending: VecDeque<String>,
+    ///Paths asked for since the last clear, so that each is only made once
+    requested: HashSet<String>,
+}
+
+fn queue() -> MutexGuard<'static, Queue> {
+    match queue_state().0.lock() {
+        Ok(e) => e,
+        Err(_) => {
+            panic!("Could not get thumbnail queue lock.");
+        }
+    }
+}
+
+fn queue_state() -> &'static (Mutex<Queue>, Condvar) {
+    static QUEUE: OnceLock<(Mutex<Queue>, Condvar)> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        thread::spawn(worker);
+        (Mutex::new(Queue::default()), Condvar::new())
+    })
+}
+
+fn worker() {
+    loop {
+        let path = {
+            let (_, wake) = queue_state();
+            let mut q = queue();
+            loop {
+                if let Some(path) = q.pending.pop_front() {
+                    break path;
+                }
+                q = match wake.wait(q) {
+                    Ok(q) => q,
+                    Err(_) => return,
+                };
+            }
+        };
+        let Some(thumbnail) = generate(&path) else {
+            continue;
+        };
+        run_with_main_window(move |mw| {
+            let Ok(image) = Image::load_from_path(&thumbnail) else {
+                return;
+            };
+            CACHE.with(|c| c.borrow_mut().insert(path, image));
+            let fm = mw.global::<FileManager>();
+            fm.set_thumbnails_generation(fm.get_thumbnails_generation().wrapping_add(1));
+        });
+    }
+}
+
+thread_local! {
+    //Loaded thumbnails, only used from the UI thread
+    static CACHE: RefCell<HashMap<String, Image>> = RefCell::new(HashMap::new());
+}
+
+///The thumbnail of the file, or an empty image if it has none (yet)
+pub fn get(path: &str) -> Image {
+    if let Some(image) = CACHE.with(|c| c.borrow().get(path).cloned()) {
+        return image;
+    }
+    if can_thumbnail(path) {
+        let mut q = queue();
+        if q.requested.insert(path.to_string()) {
+            q.pending.push_back(path.to_string());
+            queue_state().1.notify_one();
+        }
+    }
+    Image::default()
+}
+
+///Forgets the thumbnails of the previous directory, call when the directory changes
+pub fn clear() {
+    CACHE.with(|c| c.borrow_mut().clear());
+    let mut q = queue();
+    q.pending.clear();
+    q.requested.clear();
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Mutex, MutexGuard, OnceLock},
+    time::Duration,
+};
+
+use dbus::{
+    arg::{PropMap, RefArg},
+    blocking::{stdintf::org_freedesktop_dbus::ObjectManager, Connection},
+};
+
+use crate::utils::{error_handling::log_debug, types::format_size};
+
+/*
+ *  Block devices through UDisks2 (https://storaged.org/doc/udisks2-api/latest/) on the system
+ *  bus: the filesystems that can be mounted, and mounting, unmounting and powering off drives.
+ *
+ *  The calls block, the ones changing something can wait for the user to authenticate, so they
+ *  are made outside of the UI thread.
+ * */
+
+const UDISKS: &str = "org.freedesktop.UDisks2";
+const UDISKS_PATH: &str = "/org/freedesktop/UDisks2";
+const BLOCK: &str = "org.freedesktop.UDisks2.Block";
+const FILESYSTEM: &str = "org.freedesktop.UDisks2.Filesystem";
+const DRIVE: &str = "org.freedesktop.UDisks2.Drive";
+const BUSY_ERROR: &str = "org.freedesktop.UDisks2.Error.DeviceBusy";
+const LIST_TIMEOUT: Duration = Duration::from_secs(5);
+///Long enough for the user to authenticate
+const ACTION_TIMEOUT: Duration = Duration::from_secs(120);
+
+///A block device with a filesystem
+#[derive(Debug, Clone, Default)]
+pub struct Volume {
+    ///The UDisks2 object path, eg. /org/freedesktop/UDisks2/block_devices/sdb1
+    pub object: String,
+    ///eg. /dev/sdb1
+    pub device: String,
+    pub label: String,
+    pub fs_type: String,
+    pub size: u64,
+    pub mount_points: Vec<String>,
+    ///The object path of the drive, empty for devices without one (loop devices...)
+    pub drive: String,
+    pub removable: bool,
+    pub can_power_off: bool,
+    ///Set for devices the user shouldn't see (UDisks2's HintIgnore)
+    pub ignored: bool,
+}
+
+impl Volume {
+    pub fn is_mounted(&self) -> bool {
+        !self.mount_points.is_empty()
+    }
+
+    ///The label, or the size like "16 GB Volume"
+    pub fn display_name(&self) -> String {
+        if !self.label.is_empty() {
+            self.label.clone()
+        } else if self.size > 0 {
+            format!("{} Volume", format_size(self.size, false))
+        } else {
+            self.device.clone()
+        }
+    }
+}
+
+fn string(props: &PropMap, key: &str) -> String {
+    props
+        .get(key)
+        .and_then(|v| v.0.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+fn boolean(props: &PropMap, key: &str) -> bool {
+    props.get(key).and_then(|v| v.0.as_u64()).unwrap_or(0) != 0
+}
+
+///Byte strings ("ay") end with a NUL
+fn byte_string(arg: &dyn RefArg) -> String {
+    let bytes: Vec<u8> = arg
+        .as_iter()
+        .map(|i| i.filter_map(|b| b.as_u64().map(|b| b as u8)).collect())
+        .unwrap_or_default();
+    String::from_utf8_lossy(bytes.split(|b| *b == 0).next().unwrap_or_default()).into_owned()
+}
+
+///Builds the volumes from the result of GetManagedObjects
+pub fn volumes_from_objects(objects: &HashMap<String, HashMap<String, PropMap>>) -> Vec<Volume> {
+    let mut volumes: Vec<Volume> = objects
+        .iter()
+        .filter_map(|(object, interfaces)| {
+            let block = interfaces.get(BLOCK)?;
+            let filesystem = interfaces.get(FILESYSTEM)?;
+            let drive = string(block, "Drive");
+            let drive_props = objects.get(&drive).and_then(|i| i.get(DRIVE));
+            Some(Volume {
+                object: object.clone(),
+                device: block
+                    .get("PreferredDevice")
+                    .or_else(|| block.get("Device"))
+                    .map(|d| byte_string(&d.0))
+                    .unwrap_or_default(),
+                label: string(block, "IdLabel"),
+                fs_type: string(block, "IdType"),
+                size: block.get("Size").and_then(|v| v.0.as_u64()).unwrap_or(0),
+                mount_points: filesystem
+                    .get("MountPoints")
+                    .and_then(|m| m.0.as_iter())
+                    .map(|points| points.map(byte_string).collect())
+                    .unwrap_or_default(),
+                removable: drive_props.is_some_and(|d| boolean(d, "Removable")),
+                can_power_off: drive_props.is_some_and(|d| boolean(d, "CanPowerOff")),
+                ignored: boolean(block, "HintIgnore"),
+                drive: if drive == "/" { String::new() } else { drive },
+            })
+        })
+        .collect();
+    volumes.sort_by(|a, b| a.device.cmp(&b.device));
+    volumes
+}
+
+fn cache() -> MutexGuard<'static, HashMap<String, Volume>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Volume>>> = OnceLock::new();
+    match CACHE.get_or_init(Default::default).lock() {
+        Ok(e) => e,
+        Err(_) => {
+            panic!("Could not get the UDisks2 cache lock.");
+        }
+    }
+}
+
+///All the volumes, empty if UDisks2 isn't running
+pub fn volumes() -> Vec<Volume> {
+    let objects = Connection::new_system().and_then(|conn| {
+        conn.with_proxy(UDISKS, UDISKS_PATH, LIST_TIMEOUT)
+            .get_managed_objects()
+    });
+    let volumes = match objects {
+        Ok(objects) => volumes_from_objects(
+            &objects
+                .into_iter()
+                .map(|(path, interfaces)| (path.to_string(), interfaces))
+                .collect(),
+        ),
+        Err(e) => {
+            log_debug(&format!("Could not list the UDisks2 devices: {}", e));
+            Vec::new()
+        }
+    };
+    let mut cache = cache();
+    cache.clear();
+    cache.extend(volumes.iter().map(|v| (v.object.clone(), v.clone())));
+    volumes
+}
+
+///The volume as it was the last time they were listed
+pub fn cached(object: &str) -> Option<Volume> {
+    cache().get(object).cloned()
+}
+
+///The processes using files under the path (open files, working directory...), as
+///"name (pid)". Only the processes of the user can be seen.
+pub fn busy_processes(path: &str) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let prefix = path.trim_end_matches('/').to_string() + "/";
+    let is_under = |p: &Path| {
+        let p = p.to_string_lossy();
+        p == path || p.starts_with(&prefix)
+    };
+    let mut processes: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let dir = entry.path();
+            let links = ["cwd", "root", "exe"].map(|l| dir.join(l));
+            let fds = std::fs::read_dir(dir.join("fd"))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|fd| fd.path());
+            let busy = links
+                .into_iter()
+                .chain(fds)
+                .any(|l| std::fs::read_link(l).is_ok_and(|target| is_under(&target)));
+            if !busy {
+                return None;
+            }
+            let name = std::fs::read_to_string(dir.join("comm")).unwrap_or_default();
+            Some(format!("{} ({})", name.trim(), pid))
+        })
+        .collect();
+    processes.sort();
+    processes
+}
+
+///Turns a D-Bus error into a message for the user, with the processes keeping the volume busy
+fn error_message(action: &str, volume: &Volume, e: dbus::Error) -> String {
+    let mut message = format!(
+        "Could not {} {}: {}",
+        action,
+        volume.display_name(),
+        e.message().unwrap_or("unknown error")
+    );
+    let busy =
+        e.name() == Some(BUSY_ERROR) || e.message().is_some_and(|m| m.contains("target is busy"));
+    if busy {
+        let processes: Vec<String> = volume
+            .mount_points
+            .iter()
+            .flat_map(|m| busy_processes(m))
+            .collect();
+        if !processes.is_empty() {
+            message += &format!("\nIn use by: {}", processes.join(", "));
+        }
+    }
+    message
+}
+
+fn no_options() -> PropMap {
+    PropMap::new()
+}
+
+///Mounts the volume and returns where
+pub fn mount(volume: &Volume) -> Result<String, String> {
+    let conn = Connection::new_system().map_err(|e| error_message("mount", volume, e))?;
+    let (path,): (String,) = conn
+        .with_proxy(UDISKS, volume.object.as_str(), ACTION_TIMEOUT)
+        .method_call(FILESYSTEM, "Mount", (no_options(),))
+        .map_err(|e| error_message("mount", volume, e))?;
+    Ok(path)
+}
+
+pub fn unmount(volume: &Volume) -> Result<(), String> {
+    let conn = Connection::new_system().map_err(|e| error_message("unmount", volume, e))?;
+    conn.with_proxy(UDISKS, volume.object.as_str(), ACTION_TIMEOUT)
+        .method_call::<(), _, _, _>(FILESYSTEM, "Unmount", (no_options(),))
+        .map_err(|e| error_message("unmount", volume, e))
+}
+
+///Unmounts all the volumes of the drive, then powers it off so that it can be unplugged
+pub fn power_off(volume: &Volume) -> Result<(), String> {
+    for v in volumes() {
+        if v.drive == volume.drive && v.is_mounted() {
+            unmount(&v)?;
+        }
+    }
+    let conn = Connection::new_system().map_err(|e| error_message("power off", volume, e))?;
+    conn.with_proxy(UDISKS, volume.drive.as_str(), ACTION_TIMEOUT)
+        .method_call::<(), _, _, _>(DRIVE, "PowerOff", (no_options(),))
+        .map_err(|e| error_message("power off", volume, e))
+}
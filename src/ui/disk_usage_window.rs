@@ -0,0 +1,44 @@
+use slint::invoke_from_event_loop;
+
+use crate::{
+    callbacks::filemanager,
+    disk_usage,
+    ui::{DiskUsageAdapter, DiskUsageWindow, FileManager},
+};
+use slint::ComponentHandle;
+use std::sync::OnceLock;
+
+///Disk usage analyzer window
+static DISK_USAGE_WINDOW: OnceLock<DiskUsageWindow> = OnceLock::new();
+
+unsafe impl Send for DiskUsageWindow {}
+unsafe impl Sync for DiskUsageWindow {}
+
+fn get_or_init_disk_usage_window() -> &'static DiskUsageWindow {
+    DISK_USAGE_WINDOW.get_or_init(|| {
+        let win = DiskUsageWindow::new().unwrap();
+        win.global::<FileManager>()
+            .on_format_size(move |i| filemanager::format_size(i));
+        let adp = win.global::<DiskUsageAdapter>();
+        adp.on_scan_drive(|i| disk_usage::scan_drive(i));
+        adp.on_enter(|i| disk_usage::enter(i));
+        adp.on_go_up(|| disk_usage::go_up());
+        adp.on_open(|entry| disk_usage::open(entry));
+        adp.on_trash(|_, entry| disk_usage::remove(entry, false));
+        adp.on_delete(|_, entry| disk_usage::remove(entry, true));
+        adp.on_close(|| disk_usage::close());
+        win.window().on_close_requested(|| {
+            disk_usage::close();
+            slint::CloseRequestResponse::HideWindow
+        });
+        win
+    })
+}
+
+///Runs the given closure in the event loop with the DiskUsageWindow instance.
+pub fn run_with_disk_usage_window(func: impl FnOnce(&DiskUsageWindow) + Send + 'static) {
+    invoke_from_event_loop(|| {
+        func(get_or_init_disk_usage_window());
+    })
+    .ok();
+}
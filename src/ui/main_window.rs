@@ -1,14 +1,18 @@
 use crate::callbacks::{context_menu, filemanager, headers, sidebar, tabs};
 use crate::clipboard::move_file;
 use crate::keybinds::handle_key_press;
-use crate::sort::sort_by_name;
+use crate::sort::sort_files;
 use crate::utils::drag_and_drop::{dnd_move, dnd_press, dnd_release, xdnd_init};
 use crate::utils::error_handling::log_error_str;
-use crate::{core::generate_files_for_path, drives, globals::config_write, ui::*};
+use crate::{
+    columns, core::generate_files_for_path, drives, globals::config_write, icon_theme, places,
+    search, thumbnails, ui::*, view_settings,
+};
 use i_slint_backend_winit::{EventResult, WinitWindowAccessor};
 use slint::ComponentHandle;
 use slint::{invoke_from_event_loop, VecModel};
 use std::sync::{Once, RwLock};
+use std::thread;
 use std::{rc::Rc, sync::OnceLock};
 use winit::event::WindowEvent;
 use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
@@ -33,18 +37,26 @@ fn get_or_init_main_window() -> &'static MainWindow {
         let w: MainWindow = MainWindow::new().unwrap();
 
         let conf = config_write();
-        let drives = drives::get_drives();
-        w.global::<SidebarItems>().set_drive_list(drives.into());
+        let default_path = conf.get::<String>("default_path").unwrap();
+        let theme = conf.get::<String>("theme").unwrap();
+        let show_preview = conf.get::<bool>("show_preview").unwrap_or(false);
+        //The drives, places and view settings read the configuration
+        drop(conf);
+        //Listing the drives asks UDisks2, they are added once known
+        thread::spawn(drives::refresh);
+        drives::watch_usage();
+        w.global::<SidebarItems>()
+            .set_place_list(places::get_places().into());
         w.global::<FileManager>().set_files(
             Rc::new(VecModel::from(generate_files_for_path(
-                conf.get::<String>("default_path").unwrap().as_str(),
+                default_path.as_str(),
             )))
             .into(),
         );
-        w.global::<Theme>()
-            .invoke_setup(conf.get::<String>("theme").unwrap().into(), 3840, 2160); //Change these
-        w.global::<ColumnHeadersAdapter>()
-            .set_headers(Rc::new(conf.get_headers()).into());
+        w.global::<Theme>().invoke_setup(theme.into(), 3840, 2160); //Change these
+        w.global::<PreviewAdapter>().set_shown(show_preview);
+        let settings = view_settings::get(&default_path);
+        view_settings::apply_headers(&w, &settings);
         // Listen to window events
 
         w.window()
@@ -106,6 +118,12 @@ fn get_or_init_main_window() -> &'static MainWindow {
             sidebaritems.on_drive_clicked(|i| sidebar::sidebar_item_clicked(i));
             sidebaritems.on_left_arrow_clicked(|| sidebar::left_arrow_clicked());
             sidebaritems.on_right_arrow_clicked(|| sidebar::right_arrow_clicked());
+            sidebaritems.on_drive_icon(|drive, size| sidebar::drive_icon(&drive, size as u32));
+            sidebaritems.on_show_drive_context_menu(|i, x, y| drives::show_context_menu(i, x, y));
+            sidebaritems.on_place_icon(|path, size| places::place_icon(&path, size as u32));
+            sidebaritems.on_show_place_context_menu(|i, x, y| places::show_context_menu(i, x, y));
+            sidebaritems.on_place_renamed(|i, name| places::rename(i, name.to_string()));
+            sidebaritems.on_place_moved(|from, to| places::move_place(from, to));
             w.global::<FileManager>()
                 .on_fileitem_doubleclicked(|file, _| filemanager::fileitem_doubleclicked(file));
             let tabs_adapter = w.global::<TabsAdapter>();
@@ -113,6 +131,8 @@ fn get_or_init_main_window() -> &'static MainWindow {
             tabs_adapter.on_breadcrumb_accepted(|s| tabs::breadcrumb_accepted(s));
             w.global::<ColumnHeadersAdapter>()
                 .on_header_clicked(|header| headers::on_header_click(header));
+            w.global::<ColumnHeadersAdapter>()
+                .on_show_context_menu(|x, y| headers::show_header_context_menu(x, y));
             w.global::<ColumnHeadersAdapter>()
                 .on_adjust_size(|header, offset, original| {
                     headers::on_header_resize(header, offset, original)
@@ -124,6 +144,9 @@ fn get_or_init_main_window() -> &'static MainWindow {
             file_manager.on_released(|| dnd_release());
             file_manager.on_moved(|x, y| dnd_move(x, y));
             file_manager.on_format_date(move |i| filemanager::format_date(i));
+            file_manager.on_format_column(|file, column| columns::format_column(file, column));
+            file_manager.on_thumbnail(|path, _| thumbnails::get(&path));
+            file_manager.on_file_icon(|file, size| icon_theme::file_icon(&file, size as u32));
             file_manager.on_add_to_selected(|i, f| filemanager::selection::add_to_selected(i, f));
             file_manager.on_is_index_selected(|i| filemanager::selection::is_index_selected(i));
             file_manager
@@ -136,6 +159,10 @@ fn get_or_init_main_window() -> &'static MainWindow {
                 .on_is_nothing_selected(move || filemanager::selection::is_nothing_selected());
             file_manager.on_clear_selection(|| filemanager::selection::clear_selection());
 
+            let search_adp = w.global::<SearchAdapter>();
+            search_adp.on_search(|query| search::start(query));
+            search_adp.on_stop(|| search::stop());
+            search_adp.on_close(|| search::close());
             let ctx_adp = w.global::<ContextAdapter>();
             ctx_adp.on_show_context_menu(|x, y| context_menu::show_context_menu(x, y));
             ctx_adp.on_menuitem_click(move |callback_item, index| {
@@ -144,9 +171,8 @@ fn get_or_init_main_window() -> &'static MainWindow {
             ctx_adp.on_menuitem_hover(|callback_item| context_menu::menuitem_hover(callback_item));
         }
 
-        //Default sort
-        //TODO use config
-        sort_by_name(&w, true, true);
+        //Sort of the starting directory
+        sort_files(&w, &settings.sort, settings.ascending);
 
         w
     })
@@ -22,6 +22,9 @@ fn get_or_init_prop_window() -> &'static PropertiesWindow {
         prop_win
             .global::<FileManager>()
             .on_format_date(move |i| filemanager::format_date(i));
+        prop_win
+            .global::<FileManager>()
+            .on_format_size(move |i| filemanager::format_size(i));
         prop_win
             .global::<PropertiesAdapter>()
             .on_ok(|| properties::ok());
@@ -30,7 +33,49 @@ fn get_or_init_prop_window() -> &'static PropertiesWindow {
             .on_cancel(|| properties::cancel());
         prop_win
             .global::<PropertiesAdapter>()
-            .on_recalculate_bitmask(|| properties::recalculate_bitmask());
+            .on_recalculate_bitmask(|mask| properties::recalculate_bitmask(mask));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_octal_edited(|s| properties::octal_edited(s));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_time_edited(|which, s| properties::time_edited(which, s));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_time_now(|which| properties::time_now(which));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_time_picked(|which, parts| properties::time_picked(which, parts));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_get_date_time_parts(|s| properties::get_date_time_parts(s));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_calculate_checksums(|| properties::calculate_checksums());
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_cancel_checksums(|| properties::cancel_checksums());
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_verify_checksum(|| properties::verify_checksum());
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_copy_checksum(|hash| properties::copy_checksum(hash));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_browse_checksum_file(|| properties::browse_checksum_file());
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_format_acl_tag(|tag| properties::format_acl_tag(tag));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_acl_add(|kind, name, is_default| properties::acl_add(kind, name, is_default));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_acl_remove(|i| properties::acl_remove(i));
+        prop_win
+            .global::<PropertiesAdapter>()
+            .on_xattr_add(|name, value| properties::xattr_add(name, value));
         prop_win
     })
 }
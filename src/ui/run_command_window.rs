@@ -0,0 +1,37 @@
+use slint::invoke_from_event_loop;
+
+use crate::{
+    run_command,
+    ui::{RunCommandAdapter, RunCommandWindow},
+};
+use slint::ComponentHandle;
+use std::sync::OnceLock;
+
+///Window to run a shell command in the current directory
+static RUN_COMMAND_WINDOW: OnceLock<RunCommandWindow> = OnceLock::new();
+
+unsafe impl Send for RunCommandWindow {}
+unsafe impl Sync for RunCommandWindow {}
+
+fn get_or_init_run_command_window() -> &'static RunCommandWindow {
+    RUN_COMMAND_WINDOW.get_or_init(|| {
+        let win = RunCommandWindow::new().unwrap();
+        let adp = win.global::<RunCommandAdapter>();
+        adp.on_run(|cmd| run_command::run(cmd.to_string()));
+        adp.on_stop(|| run_command::stop());
+        adp.on_close(|| run_command::close());
+        win.window().on_close_requested(|| {
+            run_command::close();
+            slint::CloseRequestResponse::HideWindow
+        });
+        win
+    })
+}
+
+///Runs the given closure in the event loop with the RunCommandWindow instance.
+pub fn run_with_run_command_window(func: impl FnOnce(&RunCommandWindow) + Send + 'static) {
+    invoke_from_event_loop(|| {
+        func(get_or_init_run_command_window());
+    })
+    .ok();
+}
@@ -0,0 +1,36 @@
+use slint::invoke_from_event_loop;
+
+use crate::{
+    source_viewer,
+    ui::{SourceViewerAdapter, SourceViewerWindow},
+};
+use slint::ComponentHandle;
+use std::sync::OnceLock;
+
+///Window showing the content of a file, highlighted or as a hex dump
+static SOURCE_VIEWER_WINDOW: OnceLock<SourceViewerWindow> = OnceLock::new();
+
+unsafe impl Send for SourceViewerWindow {}
+unsafe impl Sync for SourceViewerWindow {}
+
+fn get_or_init_source_viewer_window() -> &'static SourceViewerWindow {
+    SOURCE_VIEWER_WINDOW.get_or_init(|| {
+        let win = SourceViewerWindow::new().unwrap();
+        let adp = win.global::<SourceViewerAdapter>();
+        adp.on_toggle_hex(|| source_viewer::toggle_hex());
+        adp.on_close(|| source_viewer::close());
+        win.window().on_close_requested(|| {
+            source_viewer::close();
+            slint::CloseRequestResponse::HideWindow
+        });
+        win
+    })
+}
+
+///Runs the given closure in the event loop with the SourceViewerWindow instance.
+pub fn run_with_source_viewer_window(func: impl FnOnce(&SourceViewerWindow) + Send + 'static) {
+    invoke_from_event_loop(|| {
+        func(get_or_init_source_viewer_window());
+    })
+    .ok();
+}
@@ -1,15 +1,18 @@
 use std::{rc::Rc, sync::RwLock};
 
 use main_window::SELECTED_TABITEM;
-use slint::{Model, VecModel};
+use slint::VecModel;
 
 use crate::{
     callbacks::{
         filemanager::{add_to_history, selection},
         tabs::get_breadcrumbs_for,
     },
-    sort::call_current_sort,
+    columns, drives, places, search,
+    sort::sort_files,
+    status_bar, thumbnails,
     ui::*,
+    view_settings,
 };
 
 ///This function is used to set what directory the current tab is showing.
@@ -20,6 +23,7 @@ pub fn set_current_tab_file(mut item: Option<TabItem>, remember: bool, mw: &Main
         item = Some(mw.global::<TabsAdapter>().invoke_get_current_tab());
     }
     let mut item = item.unwrap();
+    search::end(mw);
     if item.internal_path == "/" {
         item.text = "/".into();
     }
@@ -43,29 +47,45 @@ pub fn set_current_tab_file(mut item: Option<TabItem>, remember: bool, mw: &Main
         .unwrap();
     *rust_tabitem = Some(item.clone());
 
+    //View settings of the directory
+    let settings = view_settings::get(item.internal_path.as_str());
+    view_settings::apply_headers(mw, &settings);
+
+    //Thumbnails are made again if their image changed
+    thumbnails::clear();
+
     //Set files
     let filemanager = mw.global::<FileManager>();
-    let files = crate::core::generate_files_for_path(item.internal_path.as_str());
+    let mut files = crate::core::generate_files_for_path(item.internal_path.as_str());
+    let total = files.len();
+    if !settings.show_hidden {
+        files.retain(|f| !f.file_name.starts_with('.'));
+    }
+    status_bar::set_counts(mw, &files, total - files.len());
+    status_bar::update_view(mw, &settings);
     filemanager.set_files_len(files.len() as i32);
     filemanager.set_files(Rc::new(VecModel::from(files)).into());
 
     //Sort
-    call_current_sort(&mw);
+    sort_files(mw, &settings.sort, settings.ascending);
+    columns::fill_mime_types(mw, &settings);
 
     //Selection
     selection::clear_selection();
 
     //If the current path is also a drive in the sidebar, make it appear selected
-    let sidebar_items = mw.global::<SidebarItems>();
-    sidebar_items.set_selected_drive(-1);
-    for (i, drive) in sidebar_items.get_drive_list().iter().enumerate() {
-        if drive.internal_path == item.internal_path {
-            sidebar_items.set_selected_drive(i as i32);
-        }
-    }
+    drives::select_current(mw, &item.internal_path);
+    places::select_current(mw, &item.internal_path);
+    status_bar::update_free_space(mw);
+    search::select_revealed(mw);
 }
 
 ///Refreshes the UI (immediately)
+///Search results are searched for again
 pub fn refresh_ui(mw: &MainWindow) {
+    if search::is_active() {
+        search::restart();
+        return;
+    }
     set_current_tab_file(None, false, mw);
 }
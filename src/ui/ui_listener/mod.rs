@@ -1,4 +1,4 @@
-use crate::ui::*;
+use crate::{drives, ui::*};
 use internal::{refresh_ui, set_current_tab_file};
 use main_window::run_with_main_window;
 use std::{
@@ -40,7 +40,11 @@ pub fn start_ui_listener() {
     thread::spawn(move || {
         while let Ok(msg) = recv.recv() {
             match msg {
-                UIMessage::Refresh => run_with_main_window(|mw| refresh_ui(mw)),
+                UIMessage::Refresh => {
+                    run_with_main_window(|mw| refresh_ui(mw));
+                    //Files were probably written or deleted
+                    drives::refresh_usage();
+                }
                 UIMessage::SetCurrentTabFile(item, remember) => run_with_main_window(move |mw| {
                     set_current_tab_file(Some(item.clone()), remember, mw)
                 }),
@@ -0,0 +1,106 @@
+use std::{
+    cmp::Ordering,
+    ffi::{c_char, CString},
+};
+
+/*
+ *  String comparisons for sorting file names.
+ *  Strings are turned into keys once (collation_key) and the keys are compared while sorting.
+ *  Text follows the collation rules of the user's locale (LC_COLLATE / LANG), natural keys
+ *  also order the numbers in names by value ("file2" < "file10").
+ * */
+
+///Applies the locale of the environment for collation. Call once at startup.
+pub fn init_locale() {
+    unsafe {
+        libc::setlocale(libc::LC_COLLATE, c"".as_ptr());
+    }
+}
+
+///Transforms s so that comparing the bytes gives the locale's collation order (strxfrm)
+fn transform(s: &str) -> Vec<u8> {
+    let Ok(cs) = CString::new(s) else {
+        return s.as_bytes().to_vec();
+    };
+    let len = unsafe { libc::strxfrm(std::ptr::null_mut(), cs.as_ptr(), 0) };
+    let mut buf = vec![0u8; len + 1];
+    unsafe {
+        libc::strxfrm(buf.as_mut_ptr() as *mut c_char, cs.as_ptr(), len + 1);
+    }
+    buf.truncate(len);
+    buf
+}
+
+#[derive(Clone, Debug)]
+struct Segment {
+    ///The digits without leading zeros, for the runs of digits of natural keys
+    number: Option<String>,
+    collated: Vec<u8>,
+}
+
+impl Ord for Segment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.number, &other.number) {
+            //By value, without parsing so that any length works
+            (Some(a), Some(b)) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+            _ => self.collated.cmp(&other.collated),
+        }
+    }
+}
+
+//Equal when the order is, numbers with leading zeros are equal to the ones without
+impl PartialEq for Segment {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Segment {}
+
+impl PartialOrd for Segment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct CollationKey(Vec<Segment>);
+
+//Splits into runs of digits and runs of everything else
+fn chunks(s: &str) -> impl Iterator<Item = (bool, &str)> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let is_digit = first.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != is_digit)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some((is_digit, chunk))
+    })
+}
+
+///The key to sort s by. With natural, numbers are compared by value and strings only
+///differing by leading zeros ("01" and "1") have equal keys.
+pub fn collation_key(s: &str, natural: bool) -> CollationKey {
+    if !natural {
+        return CollationKey(vec![Segment {
+            number: None,
+            collated: transform(s),
+        }]);
+    }
+    CollationKey(
+        chunks(s)
+            .map(|(is_digit, chunk)| Segment {
+                number: is_digit.then(|| chunk.trim_start_matches('0').to_string()),
+                collated: transform(chunk),
+            })
+            .collect(),
+    )
+}
+
+///Compares two strings, numbers are compared by value and the rest with the locale's collation.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    collation_key(a, true).cmp(&collation_key(b, true))
+}
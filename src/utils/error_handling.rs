@@ -1,4 +1,7 @@
 use std::io::Error;
+
+use crate::status_bar;
+
 pub fn log_error(err: Error) { /*TODO*/
 }
 pub fn log_error_str(msg: &str) {
@@ -6,5 +9,6 @@ pub fn log_error_str(msg: &str) {
 }
 pub fn user_notice(msg: &str) {
     println!("{}", msg);
+    status_bar::show_notice(msg);
 }
 pub fn log_debug(msg: &str) {}
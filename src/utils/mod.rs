@@ -1,11 +1,13 @@
-use std::{fs::File, io::Read};
+use std::{fs::File, io::Read, os::unix::ffi::OsStrExt, path::Path};
 
 use slint::{LogicalPosition, PhysicalPosition};
 
+pub mod collation;
 pub mod drag_and_drop;
 pub mod error_handling;
 pub mod file_picker;
 pub mod types;
+pub mod xdg;
 
 //Returns true if s is a valid directory
 pub fn is_directory_valid(s: &str) -> bool {
@@ -35,6 +37,45 @@ pub fn capitalize_first(s: &str) -> String {
     }
 }
 
+///Percent-encodes a path, as used in file URIs and .trashinfo files
+pub fn percent_encode_path(path: &Path) -> String {
+    path.as_os_str()
+        .as_bytes()
+        .iter()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                (*b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+///Decodes the %XX sequences of a percent-encoded string, invalid ones are kept as they are
+pub fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
 ///Simple utility to center a window (win2) on top of another (win1)
 pub fn center_window_on_another(
     win1_position: PhysicalPosition,
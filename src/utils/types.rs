@@ -1,4 +1,4 @@
-use chrono::{Local, LocalResult, TimeZone};
+use chrono::{Local, LocalResult, NaiveDateTime, TimeZone};
 use slint::SharedString;
 
 pub fn i32_to_i64((a, b): (i32, i32)) -> i64 {
@@ -63,3 +63,18 @@ pub fn format_date(i: i64) -> SharedString {
         "ERR".into()
     }
 }
+
+///Inverse of format_date, also accepts a 24h time (eg. 2024-05-01 17:30).
+///Returns the number of seconds since UNIX_EPOCH, or None if it isn't a valid local time.
+pub fn parse_date(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let naive = NaiveDateTime::parse_from_str(s, "%F %I:%M %p")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%F %H:%M"))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%F %H:%M:%S"))
+        .ok()?;
+    //Ambiguous when the clock goes back, take the first one
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.timestamp())
+}
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+/*
+ *  XDG base directories (https://specifications.freedesktop.org/basedir-spec/latest/)
+ *  Unset or empty variables fall back to the defaults of the specification.
+ * */
+
+fn env_or(var: &str, default: impl FnOnce() -> String) -> String {
+    std::env::var(var)
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(default)
+}
+
+fn home() -> String {
+    std::env::var("HOME").unwrap_or_default()
+}
+
+pub fn data_home() -> PathBuf {
+    PathBuf::from(env_or("XDG_DATA_HOME", || home() + "/.local/share"))
+}
+
+pub fn config_home() -> PathBuf {
+    PathBuf::from(env_or("XDG_CONFIG_HOME", || home() + "/.config"))
+}
+
+pub fn cache_home() -> PathBuf {
+    PathBuf::from(env_or("XDG_CACHE_HOME", || home() + "/.cache"))
+}
+
+pub fn state_home() -> PathBuf {
+    PathBuf::from(env_or("XDG_STATE_HOME", || home() + "/.local/state"))
+}
+
+///System data directories, most important first
+pub fn data_dirs() -> Vec<PathBuf> {
+    env_or("XDG_DATA_DIRS", || "/usr/local/share:/usr/share".into())
+        .split(':')
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+///System configuration directories, most important first
+pub fn config_dirs() -> Vec<PathBuf> {
+    env_or("XDG_CONFIG_DIRS", || "/etc/xdg".into())
+        .split(':')
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+///The user's data directory followed by the system ones, most important first
+pub fn all_data_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![data_home()];
+    dirs.extend(data_dirs());
+    dirs
+}
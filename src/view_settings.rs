@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use slint::{ComponentHandle, Model, VecModel};
+
+use crate::{
+    columns,
+    config::Config,
+    globals::config_read,
+    ui::{main_window::run_with_main_window, *},
+    utils::{error_handling::log_error_str, xdg},
+};
+
+/*
+ *  View settings (sort, columns, hidden files) remembered per directory.
+ *
+ *  Directories without their own settings use the global default, which comes from the
+ *  configuration until the user picks one. A directory gets its own entry the first time
+ *  its view is changed. Stored in $XDG_STATE_HOME/qdfm/view_settings.json.
+ * */
+
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ColumnSetting {
+    pub name: String,
+    pub width_pct: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ViewSettings {
+    ///Name of the column sorted by
+    pub sort: String,
+    pub ascending: bool,
+    ///Visible columns, in order
+    pub columns: Vec<ColumnSetting>,
+    pub show_hidden: bool,
+}
+
+impl ViewSettings {
+    ///The default from the configuration ("headers", "default_sort" and "show_hidden")
+    pub fn from_config(conf: &Config) -> Self {
+        let columns = conf.get_header_columns();
+        let (sort, ascending) = match columns.iter().find(|(_, _, sort)| *sort != 0) {
+            Some((name, _, sort)) => (name.clone(), *sort == 1),
+            None => (
+                conf.get::<String>("default_sort").unwrap_or("name".into()),
+                true,
+            ),
+        };
+        Self {
+            sort,
+            ascending,
+            columns: columns
+                .into_iter()
+                .map(|(name, width_pct, _)| ColumnSetting { name, width_pct })
+                .collect(),
+            show_hidden: conf.get::<bool>("show_hidden").unwrap_or(true),
+        }
+    }
+
+    pub fn has_column(&self, name: &str) -> bool {
+        self.columns.iter().any(|c| c.name == name)
+    }
+
+    ///Shows or hides a column. The name column is always shown.
+    ///Hiding the sorted column goes back to sorting by name.
+    pub fn toggle_column(&mut self, name: &str) {
+        if name == "name" {
+            return;
+        }
+        if self.has_column(name) {
+            self.columns.retain(|c| c.name != name);
+            if self.sort == name {
+                self.sort = "name".into();
+                self.ascending = true;
+            }
+        } else if let Some(i) = columns::column_index(name) {
+            self.columns.push(ColumnSetting {
+                name: name.to_string(),
+                width_pct: columns::COLUMNS[i as usize].default_width,
+            });
+        }
+    }
+
+    ///Column headers for the UI, with the sort indicator set
+    pub fn headers(&self) -> Vec<Header> {
+        self.columns
+            .iter()
+            .filter_map(|c| {
+                let sort = match (c.name == self.sort, self.ascending) {
+                    (false, _) => 0,
+                    (true, true) => 1,
+                    (true, false) => 2,
+                };
+                columns::header_for(&c.name, c.width_pct, sort)
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct ViewSettingsDb {
+    ///None until the user sets a default, the configuration is used instead
+    pub default: Option<ViewSettings>,
+    pub directories: HashMap<String, ViewSettings>,
+}
+
+impl ViewSettingsDb {
+    pub fn get(&self, path: &str, config_default: impl FnOnce() -> ViewSettings) -> ViewSettings {
+        self.directories
+            .get(path)
+            .or(self.default.as_ref())
+            .cloned()
+            .unwrap_or_else(config_default)
+    }
+
+    ///Changes the settings of the directory, starting from the default if it has none
+    pub fn update(
+        &mut self,
+        path: &str,
+        config_default: impl FnOnce() -> ViewSettings,
+        f: impl FnOnce(&mut ViewSettings),
+    ) {
+        let mut settings = self.get(path, config_default);
+        f(&mut settings);
+        self.directories.insert(path.to_string(), settings);
+    }
+
+    ///The directory's settings become the default for all the directories without their own
+    pub fn set_default_from(&mut self, path: &str, config_default: impl FnOnce() -> ViewSettings) {
+        self.default = Some(self.get(path, config_default));
+        self.directories.remove(path);
+    }
+}
+
+fn db_path() -> PathBuf {
+    xdg::state_home().join("qdfm").join("view_settings.json")
+}
+
+fn load() -> ViewSettingsDb {
+    let Ok(content) = std::fs::read_to_string(db_path()) else {
+        return ViewSettingsDb::default();
+    };
+    match serde_json::from_str::<ViewSettingsDb>(&content) {
+        Ok(mut db) => {
+            //Forget the directories that are gone
+            db.directories.retain(|path, _| Path::new(path).is_dir());
+            db
+        }
+        Err(e) => {
+            log_error_str(&format!(
+                "Could not read the view settings, using defaults: {}",
+                e
+            ));
+            ViewSettingsDb::default()
+        }
+    }
+}
+
+fn db() -> MutexGuard<'static, ViewSettingsDb> {
+    static DB: OnceLock<Mutex<ViewSettingsDb>> = OnceLock::new();
+    match DB.get_or_init(|| Mutex::new(load())).lock() {
+        Ok(e) => e,
+        Err(_) => {
+            panic!("Could not get view settings lock.");
+        }
+    }
+}
+
+fn save() -> Result<(), std::io::Error> {
+    let path = db_path();
+    let json = serde_json::to_string(&*db())?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(tmp, path)
+}
+
+//Changes often come in bursts (eg. resizing a column), so saving is delayed
+fn schedule_save() {
+    static SAVE_PENDING: AtomicBool = AtomicBool::new(false);
+    if SAVE_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| {
+        thread::sleep(SAVE_DELAY);
+        SAVE_PENDING.store(false, Ordering::SeqCst);
+        if let Err(e) = save() {
+            log_error_str(&format!("Could not save the view settings: {}", e));
+        }
+    });
+}
+
+fn config_default() -> ViewSettings {
+    ViewSettings::from_config(&config_read())
+}
+
+pub fn get(path: &str) -> ViewSettings {
+    db().get(path, config_default)
+}
+
+pub fn update(path: &str, f: impl FnOnce(&mut ViewSettings)) {
+    db().update(path, config_default, f);
+    schedule_save();
+}
+
+/*
+ *  These act on the current tab's directory.
+ * */
+
+fn current_path(mw: &MainWindow) -> String {
+    mw.global::<TabsAdapter>()
+        .invoke_get_current_tab()
+        .internal_path
+        .to_string()
+}
+
+pub fn current(mw: &MainWindow) -> ViewSettings {
+    get(&current_path(mw))
+}
+
+pub fn update_current(mw: &MainWindow, f: impl FnOnce(&mut ViewSettings)) {
+    update(&current_path(mw), f);
+}
+
+///Saves the column widths shown in the UI
+pub fn save_columns(mw: &MainWindow) {
+    let widths: Vec<(i32, f32)> = mw
+        .global::<ColumnHeadersAdapter>()
+        .get_headers()
+        .iter()
+        .map(|h| (h.inner_value, h.width_pct))
+        .collect();
+    update_current(mw, |s| {
+        for (c, (inner_value, width_pct)) in s.columns.iter_mut().zip(widths) {
+            if columns::column_index(&c.name) == Some(inner_value) {
+                c.width_pct = width_pct;
+            }
+        }
+    });
+}
+
+///Shows the column headers of the settings
+pub fn apply_headers(mw: &MainWindow, settings: &ViewSettings) {
+    mw.global::<ColumnHeadersAdapter>()
+        .set_headers(Rc::new(VecModel::from(settings.headers())).into());
+}
+
+pub fn toggle_column(name: &'static str) {
+    run_with_main_window(move |mw| {
+        update_current(mw, |s| s.toggle_column(name));
+        send_message(UIMessage::Refresh);
+    });
+}
+
+pub fn toggle_hidden() {
+    run_with_main_window(|mw| {
+        update_current(mw, |s| s.show_hidden = !s.show_hidden);
+        send_message(UIMessage::Refresh);
+    });
+}
+
+///The current directory's view becomes the default one
+pub fn set_current_as_default() {
+    run_with_main_window(|mw| {
+        db().set_default_from(&current_path(mw), config_default);
+        schedule_save();
+    });
+}
+
+///The current directory forgets its own view and uses the default one
+pub fn reset_current() {
+    run_with_main_window(|mw| {
+        db().directories.remove(&current_path(mw));
+        schedule_save();
+        send_message(UIMessage::Refresh);
+    });
+}
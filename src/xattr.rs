@@ -0,0 +1,273 @@
+use std::{ffi::CString, os::unix::ffi::OsStrExt, path::Path};
+
+use syscalls::{syscall2, syscall3, syscall4, syscall5, Errno, Sysno};
+
+/*
+ *  Thin wrappers around the xattr syscalls.
+ *  These follow symlinks, same as the metadata shown in the properties window.
+ * */
+
+pub const ACL_ACCESS: &str = "system.posix_acl_access";
+pub const ACL_DEFAULT: &str = "system.posix_acl_default";
+pub const USER_NAMESPACE: &str = "user.";
+
+fn to_cstring(path: &Path) -> Result<CString, Errno> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| Errno::EINVAL)
+}
+fn name_to_cstring(name: &str) -> Result<CString, Errno> {
+    CString::new(name).map_err(|_| Errno::EINVAL)
+}
+
+///Returns the names of all the extended attributes of a file, in every namespace we can see.
+pub fn list_xattrs(path: &Path) -> Result<Vec<String>, Errno> {
+    let c_path = to_cstring(path)?;
+    //First ask for the size, then read. The list may have grown in between, so loop on ERANGE.
+    let buf = loop {
+        let size = unsafe { syscall3(Sysno::listxattr, c_path.as_ptr() as usize, 0, 0)? };
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        let mut buf = vec![0u8; size];
+        match unsafe {
+            syscall3(
+                Sysno::listxattr,
+                c_path.as_ptr() as usize,
+                buf.as_mut_ptr() as usize,
+                size,
+            )
+        } {
+            Ok(read) => {
+                buf.truncate(read);
+                break buf;
+            }
+            Err(e) if e == Errno::ERANGE => continue,
+            Err(e) => return Err(e),
+        }
+    };
+    Ok(buf
+        .split(|b| *b == 0)
+        .filter(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).to_string())
+        .collect())
+}
+
+///Returns the raw value of an extended attribute
+pub fn get_xattr(path: &Path, name: &str) -> Result<Vec<u8>, Errno> {
+    let c_path = to_cstring(path)?;
+    let c_name = name_to_cstring(name)?;
+    loop {
+        let size = unsafe {
+            syscall4(
+                Sysno::getxattr,
+                c_path.as_ptr() as usize,
+                c_name.as_ptr() as usize,
+                0,
+                0,
+            )?
+        };
+        let mut buf = vec![0u8; size];
+        match unsafe {
+            syscall4(
+                Sysno::getxattr,
+                c_path.as_ptr() as usize,
+                c_name.as_ptr() as usize,
+                buf.as_mut_ptr() as usize,
+                size,
+            )
+        } {
+            Ok(read) => {
+                buf.truncate(read);
+                return Ok(buf);
+            }
+            Err(e) if e == Errno::ERANGE => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+///Creates or replaces an extended attribute
+pub fn set_xattr(path: &Path, name: &str, value: &[u8]) -> Result<(), Errno> {
+    let c_path = to_cstring(path)?;
+    let c_name = name_to_cstring(name)?;
+    unsafe {
+        syscall5(
+            Sysno::setxattr,
+            c_path.as_ptr() as usize,
+            c_name.as_ptr() as usize,
+            value.as_ptr() as usize,
+            value.len(),
+            0,
+        )?;
+    }
+    Ok(())
+}
+
+///Removes an extended attribute
+pub fn remove_xattr(path: &Path, name: &str) -> Result<(), Errno> {
+    let c_path = to_cstring(path)?;
+    let c_name = name_to_cstring(name)?;
+    unsafe {
+        syscall2(
+            Sysno::removexattr,
+            c_path.as_ptr() as usize,
+            c_name.as_ptr() as usize,
+        )?;
+    }
+    Ok(())
+}
+
+///Formats an errno for the user, eg. "Operation not supported"
+pub fn format_errno(e: Errno) -> String {
+    e.description()
+        .map(|d| d.to_string())
+        .unwrap_or_else(|| e.to_string())
+}
+
+///A value as shown to be edited, with what parse_xattr_value needs to turn it back into bytes
+#[derive(Default, PartialEq, Debug)]
+pub struct XattrText {
+    pub text: String,
+    ///Shown as hexadecimal prefixed with 0x
+    pub hex: bool,
+    ///The text had a trailing NUL, not shown
+    pub nul_terminated: bool,
+}
+
+///Values are shown as text when they are valid UTF-8 without control characters,
+///otherwise as hexadecimal prefixed with 0x.
+pub fn format_xattr_value(value: &[u8]) -> XattrText {
+    //Strings set from the shell often come with a trailing NUL
+    let trimmed = value.strip_suffix(&[0]);
+    match std::str::from_utf8(trimmed.unwrap_or(value)) {
+        Ok(s) if !s.chars().any(|c| c.is_control() && c != '\n' && c != '\t') => XattrText {
+            text: s.to_string(),
+            hex: false,
+            nul_terminated: trimmed.is_some(),
+        },
+        _ => XattrText {
+            text: "0x".to_string()
+                + &value
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>(),
+            hex: true,
+            nul_terminated: false,
+        },
+    }
+}
+
+///Inverse of format_xattr_value, from how the value was shown rather than what it looks like:
+///a text starting with 0x stays text. Returns None if a hexadecimal value isn't valid hex.
+pub fn parse_xattr_value(s: &str, hex: bool, nul_terminated: bool) -> Option<Vec<u8>> {
+    if hex {
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+        if hex.len() % 2 != 0 {
+            return None;
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect()
+    } else {
+        let mut value = s.as_bytes().to_vec();
+        if nul_terminated {
+            value.push(0);
+        }
+        Some(value)
+    }
+}
+
+/*
+ *  POSIX ACLs, as stored in system.posix_acl_access / system.posix_acl_default
+ *
+ *  The format is a little endian u32 version (2) followed by entries of
+ *  (u16 tag, u16 perm, u32 id). Entries must be sorted by tag then id.
+ * */
+
+const ACL_XATTR_VERSION: u32 = 2;
+pub const ACL_UNDEFINED_ID: u32 = u32::MAX;
+
+pub const ACL_USER_OBJ: u16 = 0x01;
+pub const ACL_USER: u16 = 0x02;
+pub const ACL_GROUP_OBJ: u16 = 0x04;
+pub const ACL_GROUP: u16 = 0x08;
+pub const ACL_MASK: u16 = 0x10;
+pub const ACL_OTHER: u16 = 0x20;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AclEntry {
+    pub tag: u16,
+    pub perm: u16, //rwx, 4/2/1
+    pub id: u32,
+}
+
+///Parses the raw value of an ACL xattr. Returns None if it is malformed.
+pub fn decode_acl(value: &[u8]) -> Option<Vec<AclEntry>> {
+    if value.len() < 4 || (value.len() - 4) % 8 != 0 {
+        return None;
+    }
+    if u32::from_le_bytes(value[0..4].try_into().ok()?) != ACL_XATTR_VERSION {
+        return None;
+    }
+    Some(
+        value[4..]
+            .chunks_exact(8)
+            .map(|e| AclEntry {
+                tag: u16::from_le_bytes([e[0], e[1]]),
+                perm: u16::from_le_bytes([e[2], e[3]]),
+                id: u32::from_le_bytes([e[4], e[5], e[6], e[7]]),
+            })
+            .collect(),
+    )
+}
+
+///Turns entries back into the raw xattr value.
+///Entries are sorted and a mask is added if named entries need one, like setfacl does.
+pub fn encode_acl(entries: &[AclEntry]) -> Vec<u8> {
+    let mut entries = entries.to_vec();
+    let has_named = entries
+        .iter()
+        .any(|e| e.tag == ACL_USER || e.tag == ACL_GROUP);
+    if has_named && !entries.iter().any(|e| e.tag == ACL_MASK) {
+        let mask = entries
+            .iter()
+            .filter(|e| e.tag == ACL_USER || e.tag == ACL_GROUP || e.tag == ACL_GROUP_OBJ)
+            .fold(0, |acc, e| acc | e.perm);
+        entries.push(AclEntry {
+            tag: ACL_MASK,
+            perm: mask,
+            id: ACL_UNDEFINED_ID,
+        });
+    }
+    entries.sort_by_key(|e| (e.tag, e.id));
+
+    let mut out = Vec::with_capacity(4 + entries.len() * 8);
+    out.extend_from_slice(&ACL_XATTR_VERSION.to_le_bytes());
+    for e in entries {
+        out.extend_from_slice(&e.tag.to_le_bytes());
+        out.extend_from_slice(&(e.perm & 0o7).to_le_bytes());
+        out.extend_from_slice(&e.id.to_le_bytes());
+    }
+    out
+}
+
+///The access ACL equivalent to a regular mode, used when a file has no ACL xattr.
+pub fn acl_from_mode(mode: u32) -> Vec<AclEntry> {
+    vec![
+        AclEntry {
+            tag: ACL_USER_OBJ,
+            perm: ((mode >> 6) & 0o7) as u16,
+            id: ACL_UNDEFINED_ID,
+        },
+        AclEntry {
+            tag: ACL_GROUP_OBJ,
+            perm: ((mode >> 3) & 0o7) as u16,
+            id: ACL_UNDEFINED_ID,
+        },
+        AclEntry {
+            tag: ACL_OTHER,
+            perm: (mode & 0o7) as u16,
+            id: ACL_UNDEFINED_ID,
+        },
+    ]
+}